@@ -0,0 +1,53 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use blake::Blake;
+
+
+const SIZES: [usize; 3] = [64, 4 * 1024, 1024 * 1024];
+/// The lengths compiled in.
+const WIDTHS: &[i32] = &[
+    #[cfg(feature = "width-224")] 224,
+    #[cfg(feature = "width-256")] 256,
+    #[cfg(feature = "width-384")] 384,
+    #[cfg(feature = "width-512")] 512,
+];
+
+
+fn one_shot(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("one-shot ({})", blake::bench::BACKEND));
+    for &size in &SIZES {
+        let data = vec![0xA5; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        for &hashbitlen in WIDTHS {
+            let mut hashval = vec![0; hashbitlen as usize / 8];
+            group.bench_with_input(BenchmarkId::new(format!("BLAKE-{}", hashbitlen), size), &data, |b, data| {
+                b.iter(|| blake::hash(hashbitlen, data, &mut hashval).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn streaming(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("streaming 4 KiB updates ({})", blake::bench::BACKEND));
+    for &size in &SIZES {
+        let data = vec![0xA5; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        for &hashbitlen in WIDTHS {
+            let mut hashval = vec![0; hashbitlen as usize / 8];
+            group.bench_with_input(BenchmarkId::new(format!("BLAKE-{}", hashbitlen), size), &data, |b, data| {
+                b.iter(|| {
+                    let mut state = Blake::new(hashbitlen).unwrap();
+                    for chunk in data.chunks(4 * 1024) {
+                        state.update(chunk);
+                    }
+                    state.finalise(&mut hashval);
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+
+criterion_group!(benches, one_shot, streaming);
+criterion_main!(benches);
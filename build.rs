@@ -1,8 +1,156 @@
-extern crate gcc;
+use std::path::{Path, PathBuf};
+use std::fs::{self, File};
+use std::io::Write;
+use std::env;
 
 
 fn main() {
-    gcc::Build::new()
-        .file("ext/blake/blake_ref.c")
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let layout_check = out_dir.join("layout_check.c");
+    write_layout_check(&layout_check);
+    generate_bindings(&out_dir.join("bindings.rs"));
+
+    match env::var_os("BLAKE_LIB_DIR") {
+        Some(lib_dir) => link_system(Path::new(&lib_dir), &layout_check),
+        None => build_bundled(&layout_check),
+    }
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/native.rs");
+    println!("cargo:rerun-if-changed=ext/blake");
+    println!("cargo:rerun-if-env-changed=BLAKE_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=BLAKE_STATIC");
+}
+
+
+/// Build and statically link the bundled reference implementation, without the widths whose features are disabled.
+///
+/// With the `force-soft` feature, the compiler is told not to auto-vectorise it, nor to use vector registers at all,
+/// where it supports that.
+fn build_bundled(layout_check: &Path) {
+    let mut build = cc::Build::new();
+    for hashbitlen in &["224", "256", "384", "512"] {
+        if env::var_os(format!("CARGO_FEATURE_WIDTH_{}", hashbitlen)).is_none() {
+            build.define(&format!("BLAKE_RS_NO_{}", hashbitlen), None);
+        }
+    }
+    if env::var_os("CARGO_FEATURE_FORCE_SOFT").is_some() {
+        for flag in &["-fno-tree-vectorize", "-fno-tree-slp-vectorize", "-fno-vectorize", "-fno-slp-vectorize", "-mgeneral-regs-only"] {
+            build.flag_if_supported(flag);
+        }
+    }
+    build.file("ext/blake/blake_ref.c")
+        .file(layout_check)
+        .include("ext/blake")
         .compile("libblake.a");
 }
+
+/// Link the system libblake in the specified directory instead of building the bundled one.
+///
+/// It's linked statically if `BLAKE_STATIC` is set to anything but `0`, dynamically if it's set to `0`,
+/// and, if it's unset, statically with the `static` feature or when targetting musl, and dynamically otherwise.
+///
+/// The header in `ext/blake` is still checked against `src/native.rs`, so the library must've been built from it.
+///
+/// Panics with the `force-soft` feature, since how the system library was compiled can't be controlled.
+fn link_system(lib_dir: &Path, layout_check: &Path) {
+    if env::var_os("CARGO_FEATURE_FORCE_SOFT").is_some() {
+        panic!("The force-soft feature requires the bundled implementation, but BLAKE_LIB_DIR is set to {}", lib_dir.display());
+    }
+
+    cc::Build::new()
+        .file(layout_check)
+        .include("ext/blake")
+        .compile("libblake_layout_check.a");
+
+    let static_link = match env::var("BLAKE_STATIC") {
+        Ok(value) => value != "0",
+        Err(_) => env::var_os("CARGO_FEATURE_STATIC").is_some() || env::var("CARGO_CFG_TARGET_ENV").map(|e| e == "musl").unwrap_or(false),
+    };
+
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    println!("cargo:rustc-link-lib={}=blake", if static_link { "static" } else { "dylib" });
+}
+
+
+/// Generate a C file failing to compile if the Rust mirror of `hashState` in `src/native.rs` doesn't have the same
+/// size and field offsets as the one in the C header.
+///
+/// The Rust definition is translated into an equivalent C struct, which, being `#[repr(C)]`,
+/// has the same layout as the Rust one for the target being compiled for, and compared with the original.
+fn write_layout_check(out: &Path) {
+    let native = fs::read_to_string("src/native.rs").unwrap();
+    let body = native.split("pub struct hashState {").nth(1).expect("hashState not found in src/native.rs");
+    let body = &body[..body.find('}').unwrap()];
+
+    let fields: Vec<(&str, String)> = body.split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(|f| {
+            let mut parts = f.splitn(2, ':');
+            let name = parts.next().unwrap().trim();
+            (name, c_declaration(name, parts.next().unwrap().trim()))
+        })
+        .collect();
+
+    let mut out = File::create(out).unwrap();
+    writeln!(out, "#include <stddef.h>").unwrap();
+    writeln!(out, "#include <stdint.h>").unwrap();
+    writeln!(out, "#include \"blake_ref.h\"").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "#define BLAKE_RS_LAYOUT_CHECK(what, cond) typedef char blake_rs_hashState_layout_mismatch_##what[(cond) ? 1 : -1]").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "struct rust_hashState {{").unwrap();
+    for (_, decl) in &fields {
+        writeln!(out, "  {};", decl).unwrap();
+    }
+    writeln!(out, "}};").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "BLAKE_RS_LAYOUT_CHECK(size, sizeof(hashState) == sizeof(struct rust_hashState));").unwrap();
+    for &(name, _) in &fields {
+        writeln!(out,
+                 "BLAKE_RS_LAYOUT_CHECK({0}, offsetof(hashState, {0}) == offsetof(struct rust_hashState, {0}) && \
+                  sizeof(((hashState *)0)->{0}) == sizeof(((struct rust_hashState *)0)->{0}));",
+                 name)
+            .unwrap();
+    }
+}
+
+/// Generate the `extern` declarations and `hashState` from the C header with bindgen.
+#[cfg(feature = "bindgen")]
+fn generate_bindings(out: &Path) {
+    bindgen::Builder::default()
+        .header("ext/blake/blake_ref.h")
+        .allowlist_function("BLAKE_Hash_.*")
+        .allowlist_type("hashState")
+        .default_enum_style(bindgen::EnumVariation::Consts)
+        .generate()
+        .expect("Failed to generate bindings for ext/blake/blake_ref.h")
+        .write_to_file(out)
+        .expect("Failed to write bindings");
+}
+
+#[cfg(not(feature = "bindgen"))]
+fn generate_bindings(_: &Path) {}
+
+/// Translate a Rust field of the specified type into a C declaration.
+fn c_declaration(name: &str, tp: &str) -> String {
+    if tp.starts_with('[') {
+        let mut parts = tp[1..tp.len() - 1].splitn(2, ';');
+        let element = parts.next().unwrap().trim();
+        let length = parts.next().unwrap().trim();
+        format!("{} {}[{}]", c_type(element), name, length)
+    } else {
+        format!("{} {}", c_type(tp), name)
+    }
+}
+
+fn c_type(tp: &str) -> &'static str {
+    match tp {
+        "libc::c_int" | "c_int" => "int",
+        "u32" => "uint32_t",
+        "u64" => "uint64_t",
+        "u8" | "BitSequence" => "uint8_t",
+        _ => panic!("Unknown type {} in hashState in src/native.rs", tp),
+    }
+}
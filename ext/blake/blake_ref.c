@@ -2,7 +2,16 @@
 #include <stdio.h>
 #include "blake_ref.h"
 
+/* blake-rs: widths can be compiled out with BLAKE_RS_NO_<hashbitlen>, and their compression function with them */
+#if defined(BLAKE_RS_NO_224) && defined(BLAKE_RS_NO_256)
+#define BLAKE_RS_NO_32
+#endif
+#if defined(BLAKE_RS_NO_384) && defined(BLAKE_RS_NO_512)
+#define BLAKE_RS_NO_64
+#endif
 
+
+#ifndef BLAKE_RS_NO_32
 static HashReturn compress32( hashState * state, const BitSequence * datablock ) {
 
   u32 v[16];
@@ -98,8 +107,10 @@ static HashReturn compress32( hashState * state, const BitSequence * datablock )
 
   return SUCCESS;
 }
+#endif /* BLAKE_RS_NO_32 */
 
 
+#ifndef BLAKE_RS_NO_64
 static HashReturn compress64( hashState * state, const BitSequence * datablock ) {
 
   u64 v[16];
@@ -194,13 +205,27 @@ static HashReturn compress64( hashState * state, const BitSequence * datablock )
 
   return SUCCESS;
 }
+#endif /* BLAKE_RS_NO_64 */
 
 
 
-HashReturn BLAKE_Hash_Init( hashState * state, int hashbitlen ) {
+HashReturn BLAKE_CALL BLAKE_Hash_Init( hashState * state, int hashbitlen ) {
 
   int i;
 
+#ifdef BLAKE_RS_NO_224
+  if ( hashbitlen == 224 ) return BAD_HASHBITLEN;
+#endif
+#ifdef BLAKE_RS_NO_256
+  if ( hashbitlen == 256 ) return BAD_HASHBITLEN;
+#endif
+#ifdef BLAKE_RS_NO_384
+  if ( hashbitlen == 384 ) return BAD_HASHBITLEN;
+#endif
+#ifdef BLAKE_RS_NO_512
+  if ( hashbitlen == 512 ) return BAD_HASHBITLEN;
+#endif
+
   if ( (hashbitlen == 224) || (hashbitlen == 256) )  {
     /* 224- and 256-bit versions (32-bit words) */
 
@@ -255,7 +280,7 @@ HashReturn BLAKE_Hash_Init( hashState * state, int hashbitlen ) {
 
 
 
-HashReturn BLAKE_Hash_AddSalt( hashState * state, const BitSequence * salt ) {
+HashReturn BLAKE_CALL BLAKE_Hash_AddSalt( hashState * state, const BitSequence * salt ) {
 
 
   /* if hashbitlen=224 or 256, then the salt should be 128-bit (16 bytes) */
@@ -283,6 +308,7 @@ HashReturn BLAKE_Hash_AddSalt( hashState * state, const BitSequence * salt ) {
 
 
 
+#ifndef BLAKE_RS_NO_32
 static HashReturn Update32(hashState * state, const BitSequence * data, DataLength databitlen ) {
 
 
@@ -338,7 +364,9 @@ static HashReturn Update32(hashState * state, const BitSequence * data, DataLeng
 
   return SUCCESS;
 }
+#endif /* BLAKE_RS_NO_32 */
 
+#ifndef BLAKE_RS_NO_64
 static HashReturn Update64(hashState * state, const BitSequence * data, DataLength databitlen ) {
 
 
@@ -389,17 +417,24 @@ static HashReturn Update64(hashState * state, const BitSequence * data, DataLeng
 
   return SUCCESS;
 }
+#endif /* BLAKE_RS_NO_64 */
 
 
-HashReturn BLAKE_Hash_Update(hashState * state, const BitSequence * data, DataLength databitlen ) {
+HashReturn BLAKE_CALL BLAKE_Hash_Update(hashState * state, const BitSequence * data, DataLength databitlen ) {
 
+#ifndef BLAKE_RS_NO_32
   if ( state->hashbitlen < 384 )
     return Update32( state, data, databitlen );
-  else
+#endif
+#ifndef BLAKE_RS_NO_64
+  if ( state->hashbitlen >= 384 )
     return Update64( state, data, databitlen );
+#endif
+  return FAIL;
 }
 
 
+#ifndef BLAKE_RS_NO_32
 static HashReturn Final32( hashState * state, BitSequence * hashval ) {
 
 
@@ -523,8 +558,10 @@ static HashReturn Final32( hashState * state, BitSequence * hashval ) {
 
   return SUCCESS;
 }
+#endif /* BLAKE_RS_NO_32 */
 
 
+#ifndef BLAKE_RS_NO_64
 static HashReturn Final64( hashState * state, BitSequence * hashval ) {
 
 
@@ -646,16 +683,22 @@ static HashReturn Final64( hashState * state, BitSequence * hashval ) {
 
   return SUCCESS;
 }
+#endif /* BLAKE_RS_NO_64 */
 
-HashReturn BLAKE_Hash_Final( hashState * state, BitSequence * hashval ) {
+HashReturn BLAKE_CALL BLAKE_Hash_Final( hashState * state, BitSequence * hashval ) {
 
+#ifndef BLAKE_RS_NO_32
   if ( state->hashbitlen < 384 )
     return Final32( state, hashval );
-  else
+#endif
+#ifndef BLAKE_RS_NO_64
+  if ( state->hashbitlen >= 384 )
     return Final64( state, hashval );
+#endif
+  return FAIL;
 }
 
-HashReturn BLAKE_Hash_Hash( int hashbitlen, const BitSequence * data, DataLength databitlen,
+HashReturn BLAKE_CALL BLAKE_Hash_Hash( int hashbitlen, const BitSequence * data, DataLength databitlen,
 		 BitSequence * hashval ) {
 
   HashReturn ret;
@@ -2,6 +2,16 @@
 #define NB_ROUNDS32 14
 #define NB_ROUNDS64 16
 
+/*
+  calling convention of the exported functions, which is fixed so that compiling with a different default
+  (like MSVC's /Gz) doesn't change their symbols' decoration
+*/
+#ifdef _MSC_VER
+#define BLAKE_CALL __cdecl
+#else
+#define BLAKE_CALL
+#endif
+
 /*
   32-bit type (for most systems, including NIST's test machine)
 */
@@ -89,7 +99,7 @@ typedef struct  {
   SUCCESS on success
   BAD_HASHBITLEN if hashbitlen invalid
 */
-HashReturn BLAKE_Hash_Init( hashState * state, int hashbitlen );
+HashReturn BLAKE_CALL BLAKE_Hash_Init( hashState * state, int hashbitlen );
 
 /*
   adds a salt to the hash function (OPTIONAL)
@@ -102,7 +112,7 @@ HashReturn BLAKE_Hash_Init( hashState * state, int hashbitlen );
   OUTPUT
   SUCCESS on success
  */
-HashReturn BLAKE_Hash_AddSalt( hashState * state, const BitSequence * salt );
+HashReturn BLAKE_CALL BLAKE_Hash_AddSalt( hashState * state, const BitSequence * salt );
 
 /*
   update the state (chain value) with new data, storing overhead data if necessary
@@ -115,7 +125,7 @@ HashReturn BLAKE_Hash_AddSalt( hashState * state, const BitSequence * salt );
   OUTPUT
   SUCCESS on success
 */
-HashReturn BLAKE_Hash_Update( hashState * state, const BitSequence * data, DataLength databitlen );
+HashReturn BLAKE_CALL BLAKE_Hash_Update( hashState * state, const BitSequence * data, DataLength databitlen );
 
 /*
   finalize the hash, hashing remaining data and padding the message
@@ -127,7 +137,7 @@ HashReturn BLAKE_Hash_Update( hashState * state, const BitSequence * data, DataL
   OUTPUT
   SUCCESS on success
 */
-HashReturn BLAKE_Hash_Final( hashState * state, BitSequence * hashval );
+HashReturn BLAKE_CALL BLAKE_Hash_Final( hashState * state, BitSequence * hashval );
 
 /*
   all-in-once function
@@ -140,7 +150,7 @@ HashReturn BLAKE_Hash_Final( hashState * state, BitSequence * hashval );
   FAIL if arbitrary failure
   BAD_HASHBITLEN if invalid hashbitlen
 */
-HashReturn BLAKE_Hash_Hash( int hashbitlen, const BitSequence * data, DataLength databitlen,
+HashReturn BLAKE_CALL BLAKE_Hash_Hash( int hashbitlen, const BitSequence * data, DataLength databitlen,
 		 BitSequence * hashval );
 
 /*
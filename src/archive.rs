@@ -0,0 +1,233 @@
+//! Hashing of archive members, without extracting them.
+//!
+//! `hash_tar()`, with the `tar` feature, and `hash_zip()`, with the `zip` feature, stream each member's contents
+//! through the hash function. `manifest()` turns their results into a manifest of the archive,
+//! which can be stored with `dir::write_manifest()`, and compared against that of the extracted tree.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::archive;
+//! # fn main() {
+//! # #[cfg(feature = "tar")] {
+//! let mut builder = tar::Builder::new(vec![]);
+//! let mut header = tar::Header::new_gnu();
+//! header.set_size(37);
+//! header.set_cksum();
+//! builder.append_data(&mut header, "fox.txt", &b"The lazy fox jumps over the lazy dog."[..]).unwrap();
+//! let tarball = builder.into_inner().unwrap();
+//!
+//! let mut result = [0; 32];
+//! blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+//!
+//! let entries = archive::hash_tar(256, &tarball[..]).unwrap();
+//! assert_eq!(entries.len(), 1);
+//! assert_eq!(entries[0].path.to_str(), Some("fox.txt"));
+//! assert_eq!(entries[0].size, 37);
+//! assert_eq!(&entries[0].digest[..], &result[..]);
+//! # }
+//! # }
+//! ```
+
+
+use super::copy_and_hash_with_buffer;
+use super::dir::ManifestEntry;
+use super::digest_size;
+use std::path::{Component, PathBuf};
+#[cfg(any(feature = "tar", feature = "zip"))]
+use std::path::Path;
+#[cfg(feature = "zip")]
+use std::io::Seek;
+use std::io::{self, Read};
+
+
+/// The digest of a single archive member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// The member's path within the archive
+    pub path: PathBuf,
+    /// The member's size in bytes
+    pub size: u64,
+    /// The digest of the member's contents
+    pub digest: Vec<u8>,
+}
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+
+/// Hash every regular file in the tar stream, in order, returning their paths, sizes, and digests.
+///
+/// Directories, links, and other special entries are skipped.
+/// Members' paths are normalised, as in `hash_zip()`.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// one with kind `io::ErrorKind::InvalidData` if a member's path is absolute, escapes the archive with `..`,
+/// or is empty, as in `hash_zip()`, or any error encountered reading or parsing the stream.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::archive;
+/// # use std::io;
+/// let tar_of = |name: &[u8]| {
+///     let mut builder = tar::Builder::new(vec![]);
+///     let mut header = tar::Header::new_gnu();
+///     header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name);
+///     header.set_size(37);
+///     header.set_cksum();
+///     builder.append(&header, &b"The lazy fox jumps over the lazy dog."[..]).unwrap();
+///     builder.into_inner().unwrap()
+/// };
+///
+/// let entries = archive::hash_tar(256, &tar_of(b"den/./../fox.txt")[..]).unwrap();
+/// assert_eq!(entries[0].path.to_str(), Some("fox.txt"));
+/// assert!(archive::manifest(&entries).is_ok());
+///
+/// assert_eq!(archive::hash_tar(256, &tar_of(b"../fox.txt")[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+/// ```
+#[cfg(feature = "tar")]
+pub fn hash_tar<R: Read>(hashbitlen: i32, reader: R) -> io::Result<Vec<ArchiveEntry>> {
+    let size = digest_size(hashbitlen)?;
+
+    let mut buf = vec![0; BUFFER_SIZE];
+    let mut ret = vec![];
+    for entry in ::tar::Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path()?;
+        let path = enclosed(&path).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{:?} escapes the archive", path)))?;
+        let mut digest = vec![0; size];
+        let size = copy_and_hash_with_buffer(hashbitlen, &mut entry, io::sink(), &mut digest, &mut buf)?;
+        ret.push(ArchiveEntry {
+            path,
+            size,
+            digest,
+        });
+    }
+    Ok(ret)
+}
+
+/// Hash every regular file in the zip archive, in the order of its central directory, returning their paths,
+/// (decompressed) sizes, and digests.
+///
+/// Each member is decompressed as it's hashed, and its CRC-32 is checked at the end.
+/// Directories and symbolic links are skipped.
+/// Members' paths are normalised, with `.` and `..` components resolved.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// one with kind `io::ErrorKind::InvalidData` if a member's path is absolute, escapes the archive with `..`,
+/// or is empty, or if the archive is malformed, one with kind `io::ErrorKind::Unsupported` if a member is encrypted
+/// or compressed with an unsupported method, or any error encountered reading.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::archive;
+/// # use std::io::{Cursor, Write};
+/// # fn main() {
+/// let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+/// writer.add_directory("docs/", zip::write::SimpleFileOptions::default()).unwrap();
+/// writer.start_file("docs/fox.txt", zip::write::SimpleFileOptions::default()).unwrap();
+/// writer.write_all(b"The lazy fox jumps over the lazy dog.").unwrap();
+/// let bundle = writer.finish().unwrap();
+///
+/// let mut result = [0; 32];
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+///
+/// let entries = archive::hash_zip(256, bundle).unwrap();
+/// assert_eq!(entries.len(), 1);
+/// assert_eq!(entries[0].path.to_str(), Some("docs/fox.txt"));
+/// assert_eq!(entries[0].size, 37);
+/// assert_eq!(&entries[0].digest[..], &result[..]);
+/// # }
+/// ```
+#[cfg(feature = "zip")]
+pub fn hash_zip<R: Read + Seek>(hashbitlen: i32, reader: R) -> io::Result<Vec<ArchiveEntry>> {
+    let size = digest_size(hashbitlen)?;
+
+    let mut archive = ::zip::ZipArchive::new(reader)?;
+    let mut buf = vec![0; BUFFER_SIZE];
+    let mut ret = vec![];
+    for index in 0..archive.len() {
+        let mut member = archive.by_index(index)?;
+        if member.is_dir() || member.is_symlink() {
+            continue;
+        }
+
+        let path = member.enclosed_name()
+            .and_then(|path| enclosed(&path))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{:?} escapes the archive", member.name())))?;
+        let mut digest = vec![0; size];
+        let size = copy_and_hash_with_buffer(hashbitlen, &mut member, io::sink(), &mut digest, &mut buf)?;
+        ret.push(ArchiveEntry {
+            path,
+            size,
+            digest,
+        });
+    }
+    Ok(ret)
+}
+
+/// Make a manifest of the archive from the digests of its members, ordered by path, as `dir::manifest_with()` would
+/// of the extracted tree, without metadata.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidData` if a path isn't valid UTF-8, or isn't relative and normalised.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::archive::{self, ArchiveEntry};
+/// let entries = [ArchiveEntry { path: "b/fox.txt".into(), size: 37, digest: vec![0xF2; 32] },
+///                ArchiveEntry { path: "a.txt".into(), size: 0, digest: vec![0x71; 32] }];
+///
+/// let mut text = vec![];
+/// blake::dir::write_manifest(&archive::manifest(&entries).unwrap(), &mut text).unwrap();
+/// assert!(String::from_utf8(text).unwrap().ends_with("71  a.txt\nf2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2  b/fox.txt\n"));
+///
+/// assert!(archive::manifest(&[ArchiveEntry { path: "../up".into(), size: 0, digest: vec![] }]).is_err());
+/// ```
+pub fn manifest(entries: &[ArchiveEntry]) -> io::Result<Vec<ManifestEntry>> {
+    let mut ret = entries.iter()
+        .map(|entry| {
+            let malformed = || io::Error::new(io::ErrorKind::InvalidData, format!("{:?} isn't a relative UTF-8 path", entry.path));
+            let components = entry.path
+                .components()
+                .map(|component| match component {
+                    Component::Normal(name) => name.to_str().ok_or_else(malformed),
+                    _ => Err(malformed()),
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            Ok(ManifestEntry {
+                path: components.join("/"),
+                digest: entry.digest.clone(),
+                metadata: None,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    ret.sort_by(|l, r| l.path.cmp(&r.path));
+    Ok(ret)
+}
+
+
+/// Normalise the path, resolving `.` and `..` components, or `None` if it's absolute, goes above the root with `..`,
+/// as `zip`'s `enclosed_name()` checks, or is empty.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn enclosed(path: &Path) -> Option<PathBuf> {
+    let mut ret = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => return None,
+            Component::ParentDir => {
+                if !ret.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(name) => ret.push(name),
+            Component::CurDir => {}
+        }
+    }
+    if ret.as_os_str().is_empty() { None } else { Some(ret) }
+}
@@ -0,0 +1,225 @@
+//! Tamper-evident, hash-chained logs.
+//!
+//! Every record appended to a `ChainedLog` is followed by its link: the BLAKE-256 of the previous record's link
+//! (32 zeroes for the first record), the record's length, and the record. Changing, removing, reordering, or inserting
+//! any record breaks the chain from that point on, which `verify()` detects; truncating the log after a record doesn't,
+//! so the head (the last link) should be kept or published elsewhere.
+//!
+//! |field |encoding                                     |
+//! |------|---------------------------------------------|
+//! |length|the record's length, as a little-endian `u64`|
+//! |record|the record's bytes                           |
+//! |link  |the 32-byte link                             |
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::audit::{self, ChainedLog, LogStatus};
+//! let mut log = ChainedLog::new(vec![]);
+//! log.append(b"alice logged in").unwrap();
+//! log.append(b"alice deleted the bourgeoisie").unwrap();
+//! let head = log.head();
+//!
+//! let mut data = log.into_inner();
+//! assert_eq!(audit::verify(&data[..]).unwrap(), LogStatus::Intact { records: 2, head });
+//!
+//! data[8] = b'A';
+//! assert_eq!(audit::verify(&data[..]).unwrap(), LogStatus::Broken { record: 0, offset: 0 });
+//! ```
+
+
+use super::b256::{self, Digest, Hasher};
+use super::BlakeError;
+use std::io::{self, Read, Write};
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+
+/// A writer of hash-chained logs, as described in the module-level documentation.
+#[derive(Debug)]
+pub struct ChainedLog<W> {
+    inner: W,
+    head: Digest,
+    records: u64,
+}
+
+/// The result of verifying a log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStatus {
+    /// All records are intact
+    Intact {
+        /// The amount of records
+        records: u64,
+        /// The last record's link, or 32 zeroes if there are none
+        head: Digest,
+    },
+    /// The record with the specified index, at the specified offset, is truncated or doesn't link to the previous one
+    Broken {
+        /// The index of the broken record
+        record: u64,
+        /// The offset of the broken record's start, in bytes
+        offset: u64,
+    },
+}
+
+
+impl<W: Write> ChainedLog<W> {
+    /// Start a new log, writing to the specified writer.
+    #[must_use]
+    pub fn new(inner: W) -> ChainedLog<W> {
+        ChainedLog::resume(inner, Digest::from_bytes([0; b256::DIGEST_SIZE]), 0)
+    }
+
+    /// Continue a log with the specified head and amount of records, as returned by `verify()`,
+    /// writing to the specified writer.
+    pub fn resume(inner: W, head: Digest, records: u64) -> ChainedLog<W> {
+        ChainedLog {
+            inner,
+            head,
+            records,
+        }
+    }
+
+    /// Append a record, returning its link.
+    ///
+    /// The record is written with a single `write_all()` call.
+    pub fn append(&mut self, record: &[u8]) -> io::Result<Digest> {
+        let link = link(&self.head, record);
+
+        let mut data = Vec::with_capacity(8 + record.len() + b256::DIGEST_SIZE);
+        data.extend_from_slice(&(record.len() as u64).to_le_bytes());
+        data.extend_from_slice(record);
+        data.extend_from_slice(link.as_bytes());
+        self.inner.write_all(&data)?;
+
+        self.head = link;
+        self.records += 1;
+        Ok(link)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Get the last record's link, or 32 zeroes if there are none.
+    pub fn head(&self) -> Digest {
+        self.head
+    }
+
+    /// Get the amount of records in the log.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwrap the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl ChainedLog<File> {
+    /// Open the log file at the specified path for appending, creating it if it doesn't exist.
+    ///
+    /// Returns an `io::Error` of kind `InvalidData`, wrapping `BlakeError::DigestMismatch`, if the existing log is
+    /// broken, or any error encountered reading it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::audit::{self, ChainedLog, LogStatus};
+    /// # use std::fs;
+    /// let path = std::env::temp_dir().join("blake-rs-doctest-audit");
+    /// # let _ = fs::remove_file(&path);
+    /// ChainedLog::open(&path).unwrap().append(b"alice logged in").unwrap();
+    ///
+    /// let mut log = ChainedLog::open(&path).unwrap();
+    /// assert_eq!(log.records(), 1);
+    /// log.append(b"alice logged out").unwrap();
+    ///
+    /// match audit::verify_file(&path).unwrap() {
+    ///     LogStatus::Intact { records, .. } => assert_eq!(records, 2),
+    ///     status => panic!("{:?}", status),
+    /// }
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<ChainedLog<File>> {
+        let file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        match verify(io::BufReader::new(&file))? {
+            LogStatus::Intact { records, head } => Ok(ChainedLog::resume(file, head, records)),
+            LogStatus::Broken { .. } => Err(BlakeError::DigestMismatch.into()),
+        }
+    }
+}
+
+
+/// Verify the log read from the specified reader, reporting the first broken record, if any.
+///
+/// Returns any error encountered reading the log.
+pub fn verify<R: Read>(mut reader: R) -> io::Result<LogStatus> {
+    let mut head = Digest::from_bytes([0; b256::DIGEST_SIZE]);
+    let mut records = 0;
+    let mut offset = 0;
+
+    loop {
+        let mut length = [0; 8];
+        match read_full(&mut reader, &mut length)? {
+            0 => return Ok(LogStatus::Intact { records, head }),
+            8 => {}
+            _ => return Ok(LogStatus::Broken { record: records, offset }),
+        }
+
+        let length = u64::from_le_bytes(length);
+        let mut record = vec![];
+        if (&mut reader).take(length).read_to_end(&mut record)? as u64 != length {
+            return Ok(LogStatus::Broken { record: records, offset });
+        }
+
+        let mut stored = [0; b256::DIGEST_SIZE];
+        if read_full(&mut reader, &mut stored)? != stored.len() {
+            return Ok(LogStatus::Broken { record: records, offset });
+        }
+
+        let expected = link(&head, &record);
+        if expected != Digest::from_bytes(stored) {
+            return Ok(LogStatus::Broken { record: records, offset });
+        }
+
+        head = expected;
+        records += 1;
+        offset += 8 + length + b256::DIGEST_SIZE as u64;
+    }
+}
+
+/// Verify the log file at the specified path, as with `verify()`.
+pub fn verify_file<P: AsRef<Path>>(path: P) -> io::Result<LogStatus> {
+    verify(io::BufReader::new(File::open(path)?))
+}
+
+
+fn link(previous: &Digest, record: &[u8]) -> Digest {
+    let mut hasher = Hasher::new();
+    hasher.update(previous.as_bytes());
+    hasher.update((record.len() as u64).to_le_bytes());
+    hasher.update(record);
+    hasher.finalise()
+}
+
+/// Read until the buffer is full or the end of the stream, returning the amount read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
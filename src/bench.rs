@@ -0,0 +1,53 @@
+//! A throughput self-test, for sanity-checking the backend at startup.
+//!
+//! For comparing widths, sizes and one-shot against streaming hashing, see the Criterion benchmarks,
+//! run with `cargo bench`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::time::Duration;
+//! # #[cfg(feature = "width-256")] {
+//! let mbps = blake::bench::measure(256, Duration::from_millis(50)).unwrap();
+//! println!("{}: {:.0} MB/s", blake::bench::BACKEND, mbps);
+//! assert!(mbps > 0.0);
+//! # }
+//! ```
+
+
+use super::{Result, hash, digest_size};
+use std::time::{Duration, Instant};
+
+
+/// Name of the backend doing the hashing.
+#[cfg(not(feature = "force-soft"))]
+pub const BACKEND: &str = "c-reference";
+
+/// Name of the backend doing the hashing.
+#[cfg(feature = "force-soft")]
+pub const BACKEND: &str = "c-reference-soft";
+
+/// The size of the buffer hashed repeatedly by `measure()`.
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+
+/// Hash a 64 KiB buffer with the hash function of the specified length repeatedly, for at least the specified
+/// duration, and return the throughput in megabytes (10⁶ bytes) per second.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`.
+pub fn measure(hashbitlen: i32, duration: Duration) -> Result<f64> {
+    let mut hashval = vec![0; digest_size(hashbitlen)?];
+    let data: Vec<u8> = (0..BUFFER_SIZE).map(|i| i as u8).collect();
+
+    let start = Instant::now();
+    let mut hashed = 0u64;
+    loop {
+        hash(hashbitlen, &data, &mut hashval)?;
+        hashed += data.len() as u64;
+
+        let elapsed = start.elapsed();
+        if elapsed >= duration {
+            return Ok(hashed as f64 / elapsed.as_secs_f64() / 1_000_000.0);
+        }
+    }
+}
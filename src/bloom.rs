@@ -0,0 +1,207 @@
+//! Bloom filters, indexed by slices of a single BLAKE-512 digest.
+//!
+//! The BLAKE-512 digest of an item is split into 8-byte slices, and the *i*th of the filter's *k* bit indices is the
+//! *i*th slice, as a little-endian `u64`, modulo the filter's size in bits. Bit *j* is bit `j % 8` (from the least
+//! significant) of byte `j / 8`, so filters built by any implementation following this are interchangeable.
+//!
+//! |field     |encoding                                           |
+//! |----------|---------------------------------------------------|
+//! |*k*       |a single byte                                      |
+//! |size      |the size in bits, as a little-endian `u64`         |
+//! |bits      |the bits, packed as above, padded with zero bits   |
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::bloom::BloomFilter;
+//! let mut filter = BloomFilter::with_rate(1000, 0.01);
+//! filter.insert(b"alice");
+//! filter.insert(b"bob");
+//!
+//! assert!(filter.contains(b"alice"));
+//! assert!(filter.contains(b"bob"));
+//! assert!(!filter.contains(b"mallory"));
+//!
+//! let filter = BloomFilter::from_bytes(&filter.to_bytes()).unwrap();
+//! assert!(filter.contains(b"alice"));
+//! ```
+
+
+use super::{BlakeError, Result, hash};
+
+
+/// The maximum number of indices per item, as many as there are 8-byte slices in a BLAKE-512 digest.
+pub const MAX_HASHES: u32 = 8;
+
+
+/// A Bloom filter, as described in the module-level documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    size: u64,
+    hashes: u32,
+}
+
+
+impl BloomFilter {
+    /// Create an empty filter of the specified size in bits, setting the specified amount of bits per item.
+    ///
+    /// # Panics
+    ///
+    /// If `size` is 0, if `hashes` is 0 or greater than `MAX_HASHES`, or if the filter doesn't fit in memory.
+    #[must_use]
+    pub fn new(size: u64, hashes: u32) -> BloomFilter {
+        assert!(size != 0, "BloomFilter::new(): size must be positive");
+        assert!(hashes != 0 && hashes <= MAX_HASHES,
+                "BloomFilter::new(): hashes must be between 1 and {}",
+                MAX_HASHES);
+
+        BloomFilter {
+            bits: vec![0; size.div_ceil(8) as usize],
+            size,
+            hashes,
+        }
+    }
+
+    /// Create an empty filter sized for the specified amount of items, with the specified false positive rate,
+    /// with the usual formulae, limiting the amount of bits per item to `MAX_HASHES`.
+    ///
+    /// # Panics
+    ///
+    /// If `items` is 0, or if `false_positive_rate` isn't between 0 and 1, exclusive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::bloom::BloomFilter;
+    /// let filter = BloomFilter::with_rate(1000, 0.01);
+    /// assert_eq!(filter.size(), 9586);
+    /// assert_eq!(filter.hashes(), 7);
+    /// ```
+    #[must_use]
+    pub fn with_rate(items: u64, false_positive_rate: f64) -> BloomFilter {
+        assert!(items != 0, "BloomFilter::with_rate(): items must be positive");
+        assert!(false_positive_rate > 0.0 && false_positive_rate < 1.0,
+                "BloomFilter::with_rate(): false_positive_rate must be between 0 and 1");
+
+        let ln2 = std::f64::consts::LN_2;
+        let size = (-(items as f64) * false_positive_rate.ln() / (ln2 * ln2)).ceil().max(1.0) as u64;
+        let hashes = ((size as f64 / items as f64) * ln2).round().max(1.0).min(MAX_HASHES as f64) as u32;
+        BloomFilter::new(size, hashes)
+    }
+
+    /// Add an item to the filter.
+    pub fn insert(&mut self, item: &[u8]) {
+        for index in self.indices(item).iter().take(self.hashes as usize) {
+            self.bits[(index / 8) as usize] |= 1 << (index % 8);
+        }
+    }
+
+    /// Check whether an item may have been added to the filter.
+    ///
+    /// Items that were added are always reported as present, items that weren't may be.
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.indices(item).iter().take(self.hashes as usize).all(|index| self.bits[(index / 8) as usize] & (1 << (index % 8)) != 0)
+    }
+
+    /// Remove all items from the filter.
+    pub fn clear(&mut self) {
+        for byte in &mut self.bits {
+            *byte = 0;
+        }
+    }
+
+    /// Add all items in the other filter to this one.
+    ///
+    /// Returns `Err(BlakeError::BadEncoding)` if the filters' sizes or amounts of bits per item differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::bloom::BloomFilter;
+    /// let mut left  = BloomFilter::new(1024, 4);
+    /// let mut right = BloomFilter::new(1024, 4);
+    /// left.insert(b"alice");
+    /// right.insert(b"bob");
+    ///
+    /// left.union(&right).unwrap();
+    /// assert!(left.contains(b"alice") && left.contains(b"bob"));
+    ///
+    /// assert!(left.union(&BloomFilter::new(1024, 5)).is_err());
+    /// ```
+    pub fn union(&mut self, other: &BloomFilter) -> Result<()> {
+        if self.size != other.size || self.hashes != other.hashes {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        for (byte, other) in self.bits.iter_mut().zip(&other.bits) {
+            *byte |= other;
+        }
+        Ok(())
+    }
+
+    /// Get the size of the filter in bits.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get the amount of bits set per item.
+    pub fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    /// Get the filter's bits, packed as described in the module-level documentation.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    /// Serialise the filter, as described in the module-level documentation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(1 + 8 + self.bits.len());
+        ret.push(self.hashes as u8);
+        ret.extend_from_slice(&self.size.to_le_bytes());
+        ret.extend_from_slice(&self.bits);
+        ret
+    }
+
+    /// Deserialise a filter serialised with `to_bytes()`.
+    ///
+    /// Returns `Err(BlakeError::BadEncoding)` if the data is truncated or has trailing bytes,
+    /// or if the amount of bits per item or the size is invalid, or padding bits are set.
+    pub fn from_bytes(data: &[u8]) -> Result<BloomFilter> {
+        if data.len() < 1 + 8 {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        let hashes = data[0] as u32;
+        let mut size = [0; 8];
+        size.copy_from_slice(&data[1..9]);
+        let size = u64::from_le_bytes(size);
+        let bits = &data[9..];
+
+        if hashes == 0 || hashes > MAX_HASHES || size == 0 || size.div_ceil(8) != bits.len() as u64 ||
+           (size % 8 != 0 && bits[bits.len() - 1] >> (size % 8) != 0) {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        Ok(BloomFilter {
+            bits: bits.to_vec(),
+            size,
+            hashes,
+        })
+    }
+
+
+    fn indices(&self, item: &[u8]) -> [u64; MAX_HASHES as usize] {
+        let mut digest = [0; 64];
+        hash(512, item, &mut digest).expect("512 is a valid hashbitlen");
+
+        let mut ret = [0; MAX_HASHES as usize];
+        for (index, slice) in ret.iter_mut().zip(digest.chunks(8)) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(slice);
+            *index = u64::from_le_bytes(bytes) % self.size;
+        }
+        ret
+    }
+}
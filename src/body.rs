@@ -0,0 +1,127 @@
+//! Hashing of HTTP bodies as they stream through, via [`http-body`](https://docs.rs/http-body).
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::body::HashingBody;
+//! # use http_body::Body;
+//! # use http_body_util::Full;
+//! # use bytes::Bytes;
+//! # use std::task::{Context, Poll, Waker};
+//! # use std::pin::Pin;
+//! # fn main() {
+//! let (mut body, digest) = HashingBody::new(Full::new(Bytes::from_static(b"The lazy fox jumps over the lazy dog.")), 256)
+//!     .unwrap();
+//!
+//! let mut cx = Context::from_waker(Waker::noop());
+//! while let Poll::Ready(Some(frame)) = Pin::new(&mut body).poll_frame(&mut cx) {
+//!     assert_eq!(frame.unwrap().into_data().unwrap(), &b"The lazy fox jumps over the lazy dog."[..]);
+//! }
+//!
+//! let mut result = [0; 32];
+//! blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+//! assert_eq!(digest.get(), Some(result.to_vec()));
+//! # }
+//! ```
+
+
+use super::{Blake, Result, digest_size};
+use http_body::{Body, Frame, SizeHint};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::pin::Pin;
+use bytes::{Buf, Bytes};
+
+
+/// A body passing the wrapped body's frames through unchanged, hashing their data.
+///
+/// The data is passed through as `Bytes`, which, for bodies whose data already is `Bytes`, doesn't copy it.
+pub struct HashingBody<B> {
+    inner: B,
+    state: Option<Blake>,
+    digest: BodyDigest,
+}
+
+/// A handle to the digest of a `HashingBody`, available once the body has been read to the end.
+#[derive(Debug, Clone, Default)]
+pub struct BodyDigest {
+    digest: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+
+impl<B: Body + Unpin> HashingBody<B> {
+    /// Wrap the specified body, hashing it with the hash function of the specified length, returning the wrapper
+    /// and a handle to get the digest from, since the body is usually consumed by the HTTP implementation.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`.
+    pub fn new(inner: B, hashbitlen: i32) -> Result<(HashingBody<B>, BodyDigest)> {
+        let digest = BodyDigest::default();
+        Ok((HashingBody {
+                inner,
+                state: Some(Blake::new(hashbitlen)?),
+                digest: digest.clone(),
+            },
+            digest))
+    }
+
+    /// Get the digest of the body, if it has been read to the end.
+    pub fn digest(&self) -> Option<Vec<u8>> {
+        self.digest.get()
+    }
+
+    fn finish(&mut self) {
+        if let Some(mut state) = self.state.take() {
+            let mut digest = vec![0; digest_size(state.hashbitlen()).expect("valid hashbitlen")];
+            state.finalise(&mut digest);
+            *self.digest.digest.lock().unwrap_or_else(|e| e.into_inner()) = Some(digest);
+        }
+    }
+}
+
+impl<B: Body + Unpin> Body for HashingBody<B> {
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<std::result::Result<Frame<Bytes>, B::Error>>> {
+        let this = &mut *self;
+        let ret = match Pin::new(&mut this.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => {
+                let state = &mut this.state;
+                Poll::Ready(Some(Ok(frame.map_data(|mut data| {
+                    let data = data.copy_to_bytes(data.remaining());
+                    if let Some(ref mut state) = *state {
+                        state.update(&data);
+                    }
+                    data
+                }))))
+            }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        };
+
+        // Finish as soon as possible, since the HTTP implementation may stop polling once is_end_stream() is true
+        match ret {
+            Poll::Ready(None) => this.finish(),
+            _ if this.inner.is_end_stream() => this.finish(),
+            _ => {}
+        }
+        ret
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.state.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+
+impl BodyDigest {
+    /// Get the digest of the body, if it has been read to the end.
+    pub fn get(&self) -> Option<Vec<u8>> {
+        self.digest.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
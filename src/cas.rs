@@ -0,0 +1,121 @@
+//! Content-addressed storage, keyed by BLAKE-256 digests.
+//!
+//! Blobs are stored under their digest, so storing the same content twice stores it once,
+//! and anything read back is verified against the digest it was requested by.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::cas::{FsStore, Store};
+//! # use std::fs;
+//! let root = std::env::temp_dir().join("blake-rs-doctest-cas");
+//! # let _ = fs::remove_dir_all(&root);
+//! let store = FsStore::open(&root).unwrap();
+//!
+//! let digest = store.put(b"The lazy fox jumps over the lazy dog.").unwrap();
+//! assert_eq!(digest, blake::b256::hash(b"The lazy fox jumps over the lazy dog."));
+//!
+//! assert!(store.contains(&digest).unwrap());
+//! assert_eq!(store.get(&digest).unwrap(), Some(b"The lazy fox jumps over the lazy dog.".to_vec()));
+//! assert_eq!(store.get(&blake::b256::hash(b"Abolish the bourgeoisie")).unwrap(), None);
+//! # fs::remove_dir_all(&root).unwrap();
+//! ```
+
+
+use super::b256::{self, Digest};
+use super::BlakeError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::io;
+
+
+/// A content-addressed blob store.
+pub trait Store {
+    /// Store the blob, if not already present, returning its digest.
+    fn put(&self, data: &[u8]) -> io::Result<Digest>;
+
+    /// Get the blob with the specified digest, or `None` if it isn't stored.
+    ///
+    /// Returns an `io::Error` of kind `InvalidData`, wrapping `BlakeError::DigestMismatch`, if the stored blob
+    /// doesn't match the digest.
+    fn get(&self, digest: &Digest) -> io::Result<Option<Vec<u8>>>;
+
+    /// Check whether a blob with the specified digest is stored.
+    fn contains(&self, digest: &Digest) -> io::Result<bool>;
+}
+
+/// A `Store` keeping each blob in its own file, under a subdirectory named after the first byte of the digest
+/// (as in `ab/cdef…` for a digest starting with `0xAB 0xCD 0xEF`).
+///
+/// Blobs are written to temporary files first, then renamed into place, so a blob that's present is always complete.
+#[derive(Debug, Clone)]
+pub struct FsStore {
+    root: PathBuf,
+}
+
+static TEMPORARY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+
+impl FsStore {
+    /// Open the store rooted at the specified directory, creating it if needed.
+    pub fn open<P: AsRef<Path>>(root: P) -> io::Result<FsStore> {
+        fs::create_dir_all(root.as_ref())?;
+        Ok(FsStore { root: root.as_ref().to_path_buf() })
+    }
+
+    /// Get the root directory of the store.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the path the blob with the specified digest is stored at.
+    pub fn path(&self, digest: &Digest) -> PathBuf {
+        let hex = digest.to_string();
+        self.root.join(&hex[..2]).join(&hex[2..])
+    }
+}
+
+impl Store for FsStore {
+    fn put(&self, data: &[u8]) -> io::Result<Digest> {
+        let digest = b256::hash(data);
+        let path = self.path(&digest);
+        if path.exists() {
+            return Ok(digest);
+        }
+
+        let shard = path.parent().expect("blob paths are in a shard");
+        fs::create_dir_all(shard)?;
+
+        let temporary = shard.join(format!(".tmp-{}-{}", std::process::id(), TEMPORARY_COUNTER.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&temporary, data)?;
+        if let Err(e) = fs::rename(&temporary, &path) {
+            let _ = fs::remove_file(&temporary);
+            return Err(e);
+        }
+
+        Ok(digest)
+    }
+
+    fn get(&self, digest: &Digest) -> io::Result<Option<Vec<u8>>> {
+        let data = match fs::read(self.path(digest)) {
+            Ok(data) => data,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if digest.verify(&data) {
+            Ok(Some(data))
+        } else {
+            Err(BlakeError::DigestMismatch.into())
+        }
+    }
+
+    fn contains(&self, digest: &Digest) -> io::Result<bool> {
+        match fs::metadata(self.path(digest)) {
+            Ok(_) => Ok(true),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
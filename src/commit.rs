@@ -0,0 +1,116 @@
+//! A hash-based commitment scheme.
+//!
+//! `commit()` binds to a message without revealing it, by hashing it together with a random 256-bit nonce,
+//! which is kept secret in the `Opening` until the commitment is opened by revealing both the message and the
+//! `Opening`.
+//!
+//! The commitment is BLAKE-256 of the domain-separation tag `"blake-rs commitment v1"` (as in `hash_domain()`),
+//! followed by the nonce and the message.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::commit;
+//! // Bidding phase: publish the commitment, keep the opening
+//! let (commitment, opening) = commit::commit(b"bid: 1000 PLN");
+//!
+//! // Reveal phase: publish the bid and the opening
+//! assert!(commit::verify(&commitment, b"bid: 1000 PLN", &opening));
+//! assert!(!commit::verify(&commitment, b"bid: 2000 PLN", &opening));
+//! ```
+
+
+use super::stack::StackState;
+use super::constant_time_eq;
+
+
+/// A published commitment to a message.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Commitment([u8; 32]);
+
+/// The secret needed to open a `Commitment`, to be revealed with the message.
+#[derive(Clone)]
+pub struct Opening([u8; 32]);
+
+const DOMAIN: &str = "blake-rs commitment v1";
+
+
+/// Commit to the specified message with a fresh random nonce.
+///
+/// Panics if the operating system's random number generator isn't available.
+pub fn commit(msg: &[u8]) -> (Commitment, Opening) {
+    let mut nonce = [0; 32];
+    getrandom::fill(&mut nonce).expect("Failed to get randomness for nonce");
+
+    let opening = Opening(nonce);
+    (commitment_for(msg, &opening), opening)
+}
+
+/// Check, in constant time, whether the commitment was made to the specified message with the specified opening.
+pub fn verify(commitment: &Commitment, msg: &[u8], opening: &Opening) -> bool {
+    constant_time_eq(&commitment_for(msg, opening).0, &commitment.0)
+}
+
+
+impl Commitment {
+    /// Wrap a commitment received as raw bytes.
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; 32]) -> Commitment {
+        Commitment(bytes)
+    }
+
+    /// Get the raw bytes for publishing.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Opening {
+    /// Wrap an opening received as raw bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::commit::{self, Commitment, Opening};
+    /// let (commitment, opening) = commit::commit(b"bid: 1000 PLN");
+    /// let (commitment, opening) = (*commitment.as_bytes(), *opening.as_bytes());
+    ///
+    /// assert!(commit::verify(&Commitment::from_bytes(commitment), b"bid: 1000 PLN", &Opening::from_bytes(opening)));
+    /// ```
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; 32]) -> Opening {
+        Opening(bytes)
+    }
+
+    /// Get the raw bytes for revealing.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Zeroising the opening sets all its bytes to `0`.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Opening {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// With the `zeroize` feature, the opening is zeroed when dropped.
+impl Drop for Opening {
+    fn drop(&mut self) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+
+fn commitment_for(msg: &[u8], opening: &Opening) -> Commitment {
+    let mut state = StackState::new_domain(256, DOMAIN).expect("256 is a valid hashbitlen");
+    state.update(opening.0);
+    state.update(msg);
+
+    let mut ret = [0; 32];
+    state.finalise(&mut ret).expect("the buffer fits the digest");
+    Commitment(ret)
+}
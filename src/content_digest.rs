@@ -0,0 +1,243 @@
+//! Helpers for the [RFC 9530](https://www.rfc-editor.org/rfc/rfc9530) `Content-Digest` and `Repr-Digest` HTTP
+//! headers.
+//!
+//! Both headers share the same structured-field dictionary format, like `blake-256=:<base64 digest>:`;
+//! BLAKE hash functions are identified by the `blake-224`, `blake-256`, `blake-384` and `blake-512` keys.
+//! Entries for other algorithms are ignored.
+//!
+//! # Examples
+//!
+//! Producing a header for a response body, then validating it on the other side.
+//!
+//! ```
+//! # #[cfg(feature = "width-256")] {
+//! let body = b"The lazy fox jumps over the lazy dog.";
+//! let header = blake::content_digest::header_value(256, body).unwrap();
+//! assert_eq!(header, "blake-256=:8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=:");
+//!
+//! assert_eq!(blake::content_digest::verify(&header, body), Ok(true));
+//! assert_eq!(blake::content_digest::verify(&header, b"The lazy fox jumps over the lazy cat."), Ok(false));
+//! # }
+//! ```
+
+
+use super::{Blake, BlakeError, Result, hash, digest_size, constant_time_eq};
+use super::encoding::{base64_encode, base64_decode};
+
+
+/// Hash the whole body with the hash function of the given length and format the result as a header value.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` isn't a valid BLAKE length.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "width-224")] {
+/// assert_eq!(blake::content_digest::header_value(224, b"").unwrap(),
+///            "blake-224=:fcUxOxwEUSoXS9ZQO4lgeuy+4JA9QKilaclO7Q==:");
+/// # }
+/// ```
+pub fn header_value(hashbitlen: i32, body: &[u8]) -> Result<String> {
+    let mut digest = vec![0; digest_size(hashbitlen)?];
+    hash(hashbitlen, body, &mut digest)?;
+    Ok(format_entry(hashbitlen, &digest))
+}
+
+/// Finalise the streaming hasher the body was fed through and format the result as a header value.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::Blake;
+/// # use std::io;
+/// # #[cfg(feature = "width-256")] {
+/// let mut state = Blake::new(256).unwrap();
+/// io::copy(&mut &b"The lazy fox jumps over the lazy dog."[..], &mut state).unwrap();
+///
+/// assert_eq!(blake::content_digest::header_value_from_state(&mut state),
+///            blake::content_digest::header_value(256, b"The lazy fox jumps over the lazy dog.").unwrap());
+/// # }
+/// ```
+pub fn header_value_from_state(state: &mut Blake) -> String {
+    let mut digest = vec![0; state.hashbitlen() as usize / 8];
+    state.finalise(&mut digest);
+    format_entry(state.hashbitlen(), &digest)
+}
+
+/// Parse a header value, returning the length and digest of each BLAKE entry, in order of appearance.
+///
+/// Entries for non-BLAKE algorithms, and for BLAKE lengths whose `width-*` feature is disabled, are skipped.
+///
+/// Returns `Err(BlakeError::BadEncoding)` if the value isn't a well-formed dictionary or a BLAKE entry isn't a
+/// correctly-sized byte sequence.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::BlakeError;
+/// # #[cfg(feature = "width-256")] {
+/// let header = "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:, \
+///               blake-256=:8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=:";
+///
+/// let entries = blake::content_digest::parse(header).unwrap();
+/// assert_eq!(entries.len(), 1);
+/// assert_eq!(entries[0].0, 256);
+/// assert_eq!(&entries[0].1[..4], &[0xF2, 0xE5, 0xA9, 0xD0]);
+///
+/// assert_eq!(blake::content_digest::parse("blake-256=:8uWp0JPY:"), Err(BlakeError::BadEncoding));
+/// assert_eq!(blake::content_digest::parse("blake-256=\"abc\""), Err(BlakeError::BadEncoding));
+/// # }
+/// ```
+pub fn parse(value: &str) -> Result<Vec<(i32, Vec<u8>)>> {
+    let mut ret = vec![];
+
+    for member in split_members(value)? {
+        let member = member.trim_matches(|c| c == ' ' || c == '\t');
+        let (key, val) = match member.find('=') {
+            Some(idx) => (&member[..idx], &member[idx + 1..]),
+            None => (member.split(';').next().unwrap_or(""), ""),
+        };
+
+        if key.is_empty() || !key.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || b"_-.*".contains(&c)) {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        let hashbitlen = match key {
+            "blake-224" => 224,
+            "blake-256" => 256,
+            "blake-384" => 384,
+            "blake-512" => 512,
+            _ => continue,
+        };
+        let size = match digest_size(hashbitlen) {
+            Ok(size) => size,
+            Err(_) => continue,
+        };
+
+        // Byte sequences can't contain ';', so everything after the closing ':' is parameters
+        let val = val.split(';').next().unwrap_or("");
+        if val.len() < 2 || !val.starts_with(':') || !val.ends_with(':') {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        let digest = base64_decode(&val[1..val.len() - 1]).ok_or(BlakeError::BadEncoding)?;
+        if digest.len() != size {
+            return Err(BlakeError::BadEncoding);
+        }
+        ret.push((hashbitlen, digest));
+    }
+
+    Ok(ret)
+}
+
+/// Check whether the body matches all BLAKE entries in the header value.
+///
+/// Returns:
+///
+///   * `Err(BlakeError::BadEncoding)` if the value is malformed or has no BLAKE entries, or
+///   * `Ok(matched)` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::BlakeError;
+/// # #[cfg(all(feature = "width-256", feature = "width-512"))] {
+/// let body = b"The lazy fox jumps over the lazy dog.";
+/// let header = format!("{}, {}",
+///                      blake::content_digest::header_value(256, body).unwrap(),
+///                      blake::content_digest::header_value(512, body).unwrap());
+///
+/// assert_eq!(blake::content_digest::verify(&header, body), Ok(true));
+/// assert_eq!(blake::content_digest::verify("sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:", body),
+///            Err(BlakeError::BadEncoding));
+/// # }
+/// ```
+pub fn verify(value: &str, body: &[u8]) -> Result<bool> {
+    let entries = parse(value)?;
+    if entries.is_empty() {
+        return Err(BlakeError::BadEncoding);
+    }
+
+    let mut matched = true;
+    for (hashbitlen, expected) in entries {
+        let mut digest = vec![0; expected.len()];
+        hash(hashbitlen, body, &mut digest)?;
+        matched &= constant_time_eq(&digest, &expected);
+    }
+    Ok(matched)
+}
+
+/// Finalise the streaming hasher the body was fed through and check whether it matches all header entries for its
+/// length.
+///
+/// Returns:
+///
+///   * `Err(BlakeError::BadEncoding)` if the value is malformed,
+///   * `Err(BlakeError::BadHashbitlen)` if the value has no entries for the state's length, or
+///   * `Ok(matched)` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::{BlakeError, Blake};
+/// # #[cfg(all(feature = "width-384", feature = "width-512"))] {
+/// let header = blake::content_digest::header_value(384, "Zażółć gęślą jaźń".as_bytes()).unwrap();
+///
+/// let mut state = Blake::new(384).unwrap();
+/// state.update("Zażółć ".as_bytes());
+/// state.update("gęślą ".as_bytes());
+/// state.update("jaźń".as_bytes());
+/// assert_eq!(blake::content_digest::verify_state(&header, &mut state), Ok(true));
+///
+/// assert_eq!(blake::content_digest::verify_state(&header, &mut Blake::new(512).unwrap()), Err(BlakeError::BadHashbitlen));
+/// # }
+/// ```
+pub fn verify_state(value: &str, state: &mut Blake) -> Result<bool> {
+    let entries = parse(value)?;
+    if !entries.iter().any(|&(hashbitlen, _)| hashbitlen == state.hashbitlen()) {
+        return Err(BlakeError::BadHashbitlen);
+    }
+
+    let mut digest = vec![0; state.hashbitlen() as usize / 8];
+    state.finalise(&mut digest);
+    Ok(entries.iter().filter(|&&(hashbitlen, _)| hashbitlen == state.hashbitlen()).all(|(_, expected)| constant_time_eq(&digest, expected)))
+}
+
+
+fn format_entry(hashbitlen: i32, digest: &[u8]) -> String {
+    format!("blake-{}=:{}:", hashbitlen, base64_encode(digest))
+}
+
+/// Split a structured-field dictionary into its members on commas not in quoted strings.
+fn split_members(value: &str) -> Result<Vec<&str>> {
+    let mut ret = vec![];
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (idx, c) in value.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            ret.push(&value[start..idx]);
+            start = idx + 1;
+        }
+    }
+    if in_string {
+        return Err(BlakeError::BadEncoding);
+    }
+
+    let last = &value[start..];
+    if !(ret.is_empty() && last.trim().is_empty()) {
+        ret.push(last);
+    }
+    Ok(ret)
+}
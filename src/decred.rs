@@ -0,0 +1,126 @@
+//! [Decred](https://decred.org) block header hashing.
+//!
+//! A Decred block's hash is the BLAKE-256 digest of its 180-byte serialised header.
+//! The header's first 128 bytes (the version, the previous block's hash, the merkle and stake roots, and the vote data)
+//! fill exactly two blocks, and don't change while mining, so a `HeaderHasher` hashes them once,
+//! and then only the remaining 52 bytes (with the timestamp, nonce, and extra data) for every candidate header.
+//!
+//! Digests are returned in the order they're serialised in on the wire, and in headers;
+//! block explorers, RPC interfaces, and `dcrd` display them reversed, as `to_display()` and `from_display()` do.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::decred::{self, HeaderHasher};
+//! let mut header = [0; decred::HEADER_SIZE];
+//! header[0] = 1;
+//!
+//! let hasher = HeaderHasher::new(&header);
+//! for nonce in 0u32..16 {
+//!     header[140..144].copy_from_slice(&nonce.to_le_bytes());
+//!     assert_eq!(hasher.hash(&header), decred::header_hash(&header));
+//! }
+//! ```
+
+
+use super::{BlakeError, Result};
+use super::b256::Digest;
+use super::encoding::hex_decode;
+use super::stack::StackState;
+
+
+/// The size of a serialised block header.
+pub const HEADER_SIZE: usize = 180;
+
+/// The size of the part of the header hashed into the midstate: two blocks.
+pub const MIDSTATE_SIZE: usize = 128;
+
+
+/// A hasher for headers sharing their first `MIDSTATE_SIZE` bytes.
+#[derive(Clone)]
+pub struct HeaderHasher {
+    midstate: StackState,
+    prefix: [u8; MIDSTATE_SIZE],
+}
+
+
+impl HeaderHasher {
+    /// Precompute the midstate for headers starting like the specified one.
+    #[must_use]
+    pub fn new(header: &[u8; HEADER_SIZE]) -> HeaderHasher {
+        let mut midstate = StackState::new(256).expect("256 is a valid hashbitlen");
+        midstate.update(&header[..MIDSTATE_SIZE]);
+
+        let mut prefix = [0; MIDSTATE_SIZE];
+        prefix.copy_from_slice(&header[..MIDSTATE_SIZE]);
+        HeaderHasher { midstate, prefix }
+    }
+
+    /// Hash a header, hashing only its last 52 bytes if it starts like the one the midstate was computed for.
+    pub fn hash(&self, header: &[u8; HEADER_SIZE]) -> Digest {
+        if header[..MIDSTATE_SIZE] != self.prefix[..] {
+            return header_hash(header);
+        }
+        self.hash_tail(&header[MIDSTATE_SIZE..])
+    }
+
+    /// Hash the header starting like the one the midstate was computed for and ending with the specified 52 bytes.
+    ///
+    /// # Panics
+    ///
+    /// If the tail isn't 52 bytes long.
+    pub fn hash_tail(&self, tail: &[u8]) -> Digest {
+        assert_eq!(tail.len(), HEADER_SIZE - MIDSTATE_SIZE, "Decred header tail not 52 bytes long");
+
+        let mut state = self.midstate.clone();
+        state.update(tail);
+
+        let mut digest = [0; 32];
+        state.finalise(&mut digest).expect("the buffer fits the digest");
+        Digest::from_bytes(digest)
+    }
+}
+
+
+/// Hash a serialised block header.
+pub fn header_hash(header: &[u8; HEADER_SIZE]) -> Digest {
+    let mut state = StackState::new(256).expect("256 is a valid hashbitlen");
+    state.update(header);
+
+    let mut digest = [0; 32];
+    state.finalise(&mut digest).expect("the buffer fits the digest");
+    Digest::from_bytes(digest)
+}
+
+/// Format the digest as displayed by Decred tools: reversed, as lower-case hexadecimal.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::b256::Digest;
+/// # use blake::decred;
+/// let mut bytes = [0; 32];
+/// bytes[0] = 0xAB;
+/// let displayed = decred::to_display(&Digest::from_bytes(bytes));
+/// assert!(displayed.ends_with("ab"));
+/// assert_eq!(decred::from_display(&displayed), Ok(Digest::from_bytes(bytes)));
+/// ```
+pub fn to_display(digest: &Digest) -> String {
+    digest.as_bytes().iter().rev().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parse a digest as displayed by Decred tools, as in `to_display()`.
+///
+/// Returns `Err(BlakeError::BadEncoding)` if the string isn't 64 hexadecimal digits.
+pub fn from_display(displayed: &str) -> Result<Digest> {
+    let bytes = hex_decode(displayed).ok_or(BlakeError::BadEncoding)?;
+    if bytes.len() != 32 {
+        return Err(BlakeError::BadEncoding);
+    }
+
+    let mut digest = [0; 32];
+    for (out, byte) in digest.iter_mut().zip(bytes.iter().rev()) {
+        *out = *byte;
+    }
+    Ok(Digest::from_bytes(digest))
+}
@@ -0,0 +1,205 @@
+//! Differential verification: hashing with the C reference implementation and a portable Rust one in lockstep.
+//!
+//! This is a paranoid mode for environments where a miscompiled or faulty backend must not go unnoticed.
+//! After every update, and on finalisation, the digests both backends would produce are compared,
+//! and `Err(BlakeError::BackendMismatch)` is returned if they differ; a diverged state stays diverged.
+//!
+//! Every update costs a finalisation of each backend on top of the hashing itself, so this is much slower than `Blake`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::differential::DifferentialBlake;
+//! # #[cfg(feature = "width-256")] {
+//! let mut result_checked = [0; 32];
+//! let mut result         = [0; 32];
+//!
+//! let mut state = DifferentialBlake::new(256).unwrap();
+//! state.update(b"The lazy fox ").unwrap();
+//! state.update(b"jumps over the lazy dog.").unwrap();
+//! state.finalise(&mut result_checked).unwrap();
+//!
+//! blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+//! assert_eq!(result_checked, result);
+//! # }
+//! ```
+
+
+use super::{Blake, BlakeError, Result, digest_size};
+use super::bench::BACKEND;
+use super::portable::{self, Portable};
+#[cfg(feature = "simd")]
+use super::simd;
+use std::io::{self, Write};
+
+
+/// A hash state running both backends, as described in the module-level documentation.
+#[derive(Clone)]
+pub struct DifferentialBlake {
+    reference: Blake,
+    portable: Portable,
+    diverged: bool,
+}
+
+
+/// Names of the backends compared, the reference one first.
+pub const BACKENDS: [&str; 2] = [BACKEND, portable::BACKEND];
+
+/// Names of the backends compared by states from `DifferentialBlake::new_simd()`, the reference one first.
+#[cfg(feature = "simd")]
+pub const SIMD_BACKENDS: [&str; 2] = [BACKEND, simd::BACKEND];
+
+
+impl DifferentialBlake {
+    /// Create a new hash state on both backends.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`.
+    pub fn new(hashbitlen: i32) -> Result<DifferentialBlake> {
+        Ok(DifferentialBlake {
+            reference: Blake::new(hashbitlen)?,
+            portable: Portable::new(hashbitlen)?,
+            diverged: false,
+        })
+    }
+
+    /// Create a new hash state on the reference backend and the portable one vectorised with `std::simd`,
+    /// instead of the scalar one.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::differential::DifferentialBlake;
+    /// let data: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+    /// for &hashbitlen in &[224, 256, 384, 512] {
+    ///     for len in 0..data.len() {
+    ///         let mut result_simd   = [0; 64];
+    ///         let mut result_scalar = [0; 64];
+    ///
+    ///         let mut state_simd   = DifferentialBlake::new_simd(hashbitlen).unwrap();
+    ///         let mut state_scalar = DifferentialBlake::new(hashbitlen).unwrap();
+    ///         state_simd.add_salt(&[0xA5; 32]).unwrap();
+    ///         state_scalar.add_salt(&[0xA5; 32]).unwrap();
+    ///         state_simd.update(&data[..len]).unwrap();
+    ///         state_scalar.update(&data[..len]).unwrap();
+    ///         state_simd.finalise(&mut result_simd).unwrap();
+    ///         state_scalar.finalise(&mut result_scalar).unwrap();
+    ///
+    ///         assert_eq!(&result_simd[..], &result_scalar[..]);
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "simd")]
+    pub fn new_simd(hashbitlen: i32) -> Result<DifferentialBlake> {
+        Ok(DifferentialBlake {
+            reference: Blake::new(hashbitlen)?,
+            portable: Portable::new_simd(hashbitlen)?,
+            diverged: false,
+        })
+    }
+
+    /// Get the length of the hash function's output, in bits, as passed to `DifferentialBlake::new()`.
+    pub fn hashbitlen(&self) -> i32 {
+        self.reference.hashbitlen()
+    }
+
+    /// Add a salt to the hash function on both backends, as in `Blake::add_salt()`.
+    ///
+    /// Returns `Err(BlakeError::Fail)` if the salt is shorter than the hash function's salt length.
+    pub fn add_salt(&mut self, salt: &[u8]) -> Result<()> {
+        let salt_size = if self.hashbitlen() < 384 { 16 } else { 32 };
+        if salt.len() < salt_size {
+            return Err(BlakeError::Fail);
+        }
+
+        self.reference.add_salt(salt)?;
+        self.portable.add_salt(salt);
+        self.check().map(|_| ())
+    }
+
+    /// Append the provided data to the hash function on both backends, then compare them.
+    ///
+    /// Returns `Err(BlakeError::BackendMismatch)` if the backends have diverged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::differential::DifferentialBlake;
+    /// # #[cfg(feature = "width-512")] {
+    /// let mut state = DifferentialBlake::new(512).unwrap();
+    /// for chunk in vec![0xA5; 1000].chunks(37) {
+    ///     state.update(chunk).unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        let data = data.as_ref();
+        if self.diverged {
+            return Err(BlakeError::BackendMismatch);
+        }
+
+        self.reference.update(data);
+        self.portable.update(data);
+        self.check().map(|_| ())
+    }
+
+    /// Finalise the hash on both backends, writing the digest to `hashval` if they agree.
+    ///
+    /// Returns `Err(BlakeError::BadLength)` if the provided space is smaller than the hash function's size,
+    /// or `Err(BlakeError::BackendMismatch)` if the backends have diverged, leaving `hashval` untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::BlakeError;
+    /// # use blake::differential::DifferentialBlake;
+    /// # #[cfg(feature = "width-256")] {
+    /// let mut state = DifferentialBlake::new(256).unwrap();
+    /// state.update(b"The lazy fox jumps over the lazy dog.").unwrap();
+    /// assert_eq!(state.finalise(&mut [0; 31]), Err(BlakeError::BadLength));
+    /// assert_eq!(state.finalise(&mut [0; 32]), Ok(()));
+    /// # }
+    /// ```
+    pub fn finalise(&mut self, hashval: &mut [u8]) -> Result<()> {
+        if hashval.len() < digest_size(self.hashbitlen())? {
+            return Err(BlakeError::BadLength);
+        }
+
+        let digest = self.check()?;
+        hashval[..digest.len()].copy_from_slice(&digest);
+        Ok(())
+    }
+
+
+    /// Compare the digests both backends would produce, returning it if they agree.
+    fn check(&mut self) -> Result<Vec<u8>> {
+        if self.diverged {
+            return Err(BlakeError::BackendMismatch);
+        }
+
+        let size = digest_size(self.hashbitlen())?;
+        let mut reference = vec![0; size];
+        let mut portable = vec![0; size];
+        self.reference.clone().finalise(&mut reference);
+        self.portable.finalise(&mut portable);
+
+        if reference != portable {
+            self.diverged = true;
+            return Err(BlakeError::BackendMismatch);
+        }
+        Ok(reference)
+    }
+}
+
+impl Write for DifferentialBlake {
+    /// Errors wrap `BlakeError::BackendMismatch`, retrievable with `BlakeError::from_io_error()`.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+//! Implementations of the [`digest`](https://docs.rs/digest/0.9) 0.9 traits for the per-width hashers,
+//! for crates still depending on that version, like `hmac` 0.10.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::{Blake256, b256};
+//! use hmac::{Hmac, Mac, NewMac};
+//!
+//! let mut mac = Hmac::<Blake256>::new_varkey(b"key").unwrap();
+//! mac.update(b"The lazy fox jumps over the lazy dog.");
+//! let tag = mac.finalize().into_bytes();
+//!
+//! let mut key = [0; 64];
+//! key[..3].copy_from_slice(b"key");
+//! let inner = b256::Hasher::new().chain(key.map(|b| b ^ 0x36)).chain(b"The lazy fox jumps over the lazy dog.").finalise();
+//! let outer = b256::Hasher::new().chain(key.map(|b| b ^ 0x5C)).chain(inner.as_bytes()).finalise();
+//! assert_eq!(&tag[..], outer.as_bytes());
+//! ```
+
+#![cfg_attr(not(any(feature = "width-224", feature = "width-256", feature = "width-384", feature = "width-512")), allow(unused_imports, unused_macros))]
+
+
+use digest_09::generic_array::typenum;
+use digest_09::generic_array::GenericArray;
+use digest_09::{BlockInput, FixedOutputDirty, Reset, Update};
+use std::mem;
+
+
+/// Implement the traits for the specified width module's `Hasher`, with the specified digest and block sizes.
+macro_rules! digest09_impls {
+    ($module:ident, $digest_size:ty, $block_size:ty) => {
+        impl Update for super::$module::Hasher {
+            fn update(&mut self, data: impl AsRef<[u8]>) {
+                super::$module::Hasher::update(self, data);
+            }
+        }
+
+        impl BlockInput for super::$module::Hasher {
+            type BlockSize = $block_size;
+        }
+
+        impl FixedOutputDirty for super::$module::Hasher {
+            type OutputSize = $digest_size;
+
+            fn finalize_into_dirty(&mut self, out: &mut GenericArray<u8, $digest_size>) {
+                out.copy_from_slice(mem::take(self).finalise().as_bytes());
+            }
+        }
+
+        impl Reset for super::$module::Hasher {
+            fn reset(&mut self) {
+                super::$module::Hasher::reset(self);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "width-224")]
+digest09_impls!(b224, typenum::U28, typenum::U64);
+#[cfg(feature = "width-256")]
+digest09_impls!(b256, typenum::U32, typenum::U64);
+#[cfg(feature = "width-384")]
+digest09_impls!(b384, typenum::U48, typenum::U128);
+#[cfg(feature = "width-512")]
+digest09_impls!(b512, typenum::U64, typenum::U128);
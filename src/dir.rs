@@ -0,0 +1,902 @@
+//! Hashing and comparison of whole directory trees.
+//!
+//! `hash_dir()` produces a single digest of a tree, independent of the order in which the filesystem lists it,
+//! and of the platform's path separator. Conceptually, it's the `StructuredHasher` hash of, for each regular file,
+//! in byte-wise order of path relative to the root, with components separated by `/`, a record of:
+//!
+//!   * `str()` of the relative path,
+//!   * `u64()` of `0`,
+//!   * `bytes()` of the file's digest, and,
+//!   * if `DirOptions::mode()` is enabled, `u64()` of the file's permission bits.
+//!
+//! If `DirOptions::symlinks()` is enabled, each symbolic link is included as a record of `str()` of the relative path,
+//! `u64()` of `1`, and `str()` of the link's target; otherwise they're skipped. Links are never followed.
+//!
+//! Empty directories don't affect the digest, and neither do the contents of special files.
+//! Paths that aren't valid UTF-8 are an error.
+//!
+//! Paths can be excluded with gitignore-style patterns, specified with `DirOptions::ignore()`,
+//! or read from `.blakeignore` files with `DirOptions::ignore_files()`. As in git, the last matching pattern decides,
+//! patterns from `.blakeignore` files in deeper directories take precedence, and a file in an ignored directory can't
+//! be re-included.
+//!
+//! `manifest_with()` lists the files in a tree with their digests instead, for `write_manifest()` to store in the text
+//! format of `b2sum` and the like, and for `check_manifest()` to verify the tree against later.
+//! With the `json` feature, `write_json_manifest()` stores them as JSON instead, along with each file's size,
+//! modification time, and permission bits.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::fs;
+//! # #[cfg(feature = "width-256")] {
+//! let root = std::env::temp_dir().join("blake-rs-doctest-dir");
+//! # let _ = fs::remove_dir_all(&root);
+//! fs::create_dir_all(root.join("a/b")).unwrap();
+//! fs::write(root.join("a/b/fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+//! fs::write(root.join("proles.txt"), b"Proletarians of all countries, unite!").unwrap();
+//!
+//! let mut result_before = [0; 32];
+//! blake::dir::hash_dir(256, &root, &mut result_before).unwrap();
+//!
+//! fs::write(root.join("a/b/fox.txt"), b"The lazy dog jumps over the lazy fox.").unwrap();
+//! let mut result_after = [0; 32];
+//! blake::dir::hash_dir(256, &root, &mut result_after).unwrap();
+//!
+//! assert!(result_before != result_after);
+//! # fs::remove_dir_all(&root).unwrap();
+//! # }
+//! ```
+
+
+use super::structured::StructuredHasher;
+use super::ignore::IgnoreRules;
+use super::{BlakeError, IoOptions, RateLimiter, trace, check_hashval, digest_size, hash, hash_file_with};
+use super::encoding::hex_decode;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::fs::{self, FileType};
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::sync::atomic::{self, AtomicBool, AtomicU64};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fmt, thread};
+
+
+/// Options for hashing directory trees with `hash_dir_with()` and `diff_dirs()`.
+#[derive(Debug, Clone, Default)]
+pub struct DirOptions {
+    mode: bool,
+    symlinks: bool,
+    threads: usize,
+    ignore: IgnoreRules,
+    ignore_files: bool,
+    rate_limit: Option<Arc<RateLimiter>>,
+    buffer_size: Option<usize>,
+    cancel: Option<Arc<AtomicBool>>,
+    progress: Option<DirProgress>,
+}
+
+/// A callback receiving the amount of bytes hashed so far and the relative path of the file being hashed,
+/// set with `DirOptions::progress()`.
+type ProgressFn = dyn Fn(u64, &str) + Send + Sync;
+
+#[derive(Clone)]
+struct DirProgress {
+    callback: Arc<ProgressFn>,
+    granularity: u64,
+}
+
+/// A difference between two directory trees, found by `diff_dirs()`, or between a manifest and a tree, by `check_manifest()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A file present only in the new tree
+    Added {
+        path: String,
+        digest: Vec<u8>,
+    },
+    /// A file present only in the old tree
+    Removed {
+        path: String,
+        digest: Vec<u8>,
+    },
+    /// A file present in both trees, with different contents
+    Modified {
+        path: String,
+        old: Vec<u8>,
+        new: Vec<u8>,
+    },
+    /// A file with the same contents as in the manifest, but a different size or permission bits;
+    /// only reported by `check_manifest()`
+    MetadataChanged {
+        path: String,
+        old: FileMetadata,
+        new: FileMetadata,
+    },
+}
+
+/// A file in a manifest of a tree, from `manifest_with()` or `read_manifest()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// The path relative to the root of the tree, with components separated by `/`
+    pub path: String,
+    /// The file's digest, or its target's if it's a symbolic link
+    pub digest: Vec<u8>,
+    /// The file's metadata, if known; text manifests don't store it
+    pub metadata: Option<FileMetadata>,
+}
+
+/// A file's metadata, as stored in JSON manifests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// The size in bytes, of the link itself for symbolic links
+    pub size: u64,
+    /// The modification time, in seconds since the Unix epoch
+    pub mtime: i64,
+    /// The permission bits, as in `DirOptions::mode()`, if enabled, and not a symbolic link
+    pub mode: Option<u64>,
+}
+
+/// A file found while walking a tree, with its path relative to the root.
+struct Entry {
+    relative: String,
+    path: PathBuf,
+    file_type: FileType,
+}
+
+const KIND_FILE: u64 = 0;
+const KIND_SYMLINK: u64 = 1;
+
+/// The name of the files patterns are read from, if enabled with `DirOptions::ignore_files()`.
+pub const IGNORE_FILE: &str = ".blakeignore";
+
+
+impl DirOptions {
+    /// Create options hashing only the contents of regular files.
+    #[must_use]
+    pub fn new() -> DirOptions {
+        DirOptions::default()
+    }
+
+    /// Whether to include the files' permission bits.
+    ///
+    /// On Unices, these are the lower 12 bits of the mode; elsewhere, `0o444` for read-only files and `0o644` otherwise.
+    pub fn mode(mut self, mode: bool) -> DirOptions {
+        self.mode = mode;
+        self
+    }
+
+    /// Whether to include symbolic links, and their targets.
+    pub fn symlinks(mut self, symlinks: bool) -> DirOptions {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Hash files on the specified amount of threads; `0` or `1` hash them on the calling thread.
+    ///
+    /// This doesn't change the results.
+    pub fn threads(mut self, threads: usize) -> DirOptions {
+        self.threads = threads;
+        self
+    }
+
+    /// Exclude paths matching the specified gitignore-style pattern, relative to the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::dir::DirOptions;
+    /// # use std::fs;
+    /// # #[cfg(feature = "width-256")] {
+    /// let root = std::env::temp_dir().join("blake-rs-doctest-dir-ignore");
+    /// # let _ = fs::remove_dir_all(&root);
+    /// fs::create_dir_all(root.join("target/debug")).unwrap();
+    /// fs::write(root.join("fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+    ///
+    /// let mut result_clean = [0; 32];
+    /// blake::dir::hash_dir(256, &root, &mut result_clean).unwrap();
+    ///
+    /// fs::write(root.join("target/debug/fox.o"), b"\x7FELF").unwrap();
+    /// fs::write(root.join("fox.txt~"), b"The lazy fox").unwrap();
+    ///
+    /// let mut result_built = [0; 32];
+    /// blake::dir::hash_dir_with(256, &root, &mut result_built, &DirOptions::new().ignore("/target/").ignore("*~")).unwrap();
+    ///
+    /// assert_eq!(result_clean, result_built);
+    /// # fs::remove_dir_all(&root).unwrap();
+    /// # }
+    /// ```
+    pub fn ignore(mut self, pattern: &str) -> DirOptions {
+        self.ignore.add("", pattern);
+        self
+    }
+
+    /// Whether to read patterns from `.blakeignore` files, in gitignore syntax, relative to the directory containing
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::dir::DirOptions;
+    /// # use std::fs;
+    /// # #[cfg(feature = "width-256")] {
+    /// let root = std::env::temp_dir().join("blake-rs-doctest-dir-ignore-files");
+    /// # let _ = fs::remove_dir_all(&root);
+    /// fs::create_dir_all(root.join("cache")).unwrap();
+    /// fs::write(root.join(".blakeignore"), "cache/\n*.log\n!keep.log\n").unwrap();
+    /// fs::write(root.join("cache/fox.bin"), b"The lazy fox").unwrap();
+    /// fs::write(root.join("build.log"), b"jumps over").unwrap();
+    /// fs::write(root.join("keep.log"), b"the lazy dog.").unwrap();
+    ///
+    /// let empty = std::env::temp_dir().join("blake-rs-doctest-dir-ignore-files-empty");
+    /// # let _ = fs::remove_dir_all(&empty);
+    /// fs::create_dir_all(&empty).unwrap();
+    /// let added = blake::dir::diff_dirs(256, &empty, &root, &DirOptions::new().ignore_files(true)).unwrap();
+    /// assert_eq!(added.iter().map(|c| c.path()).collect::<Vec<_>>(), vec![".blakeignore", "keep.log"]);
+    /// # fs::remove_dir_all(&root).unwrap();
+    /// # fs::remove_dir_all(&empty).unwrap();
+    /// # }
+    /// ```
+    pub fn ignore_files(mut self, ignore_files: bool) -> DirOptions {
+        self.ignore_files = ignore_files;
+        self
+    }
+
+    /// Limit the total throughput of hashing files, across all threads, with the specified limiter,
+    /// as with `IoOptions::rate_limit()`.
+    pub fn rate_limit(mut self, limiter: Arc<RateLimiter>) -> DirOptions {
+        self.rate_limit = Some(limiter);
+        self
+    }
+
+    /// Read files in chunks of up to the specified amount of bytes, as with `IoOptions::buffer_size()`.
+    ///
+    /// Panics if `bytes` is `0`.
+    pub fn buffer_size(mut self, bytes: usize) -> DirOptions {
+        assert!(bytes != 0, "Buffer size must be positive");
+        self.buffer_size = Some(bytes);
+        self
+    }
+
+    /// Stop as soon as possible after the specified flag is set, returning an `io::Error` wrapping
+    /// `BlakeError::Cancelled`.
+    ///
+    /// The flag is checked before every directory entry while walking the tree, and before every file and every read
+    /// while hashing them, as with `IoOptions::cancel()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::dir::DirOptions;
+    /// # use blake::BlakeError;
+    /// # use std::sync::atomic::AtomicBool;
+    /// # use std::sync::Arc;
+    /// # #[cfg(feature = "width-256")] {
+    /// let cancel = Arc::new(AtomicBool::new(true));
+    ///
+    /// let mut result = [0; 32];
+    /// let err = blake::dir::hash_dir_with(256, ".", &mut result, &DirOptions::new().cancel(&cancel)).unwrap_err();
+    /// assert_eq!(BlakeError::from_io_error(&err), Some(BlakeError::Cancelled));
+    /// # }
+    /// ```
+    pub fn cancel(mut self, flag: &Arc<AtomicBool>) -> DirOptions {
+        self.cancel = Some(flag.clone());
+        self
+    }
+
+    /// Call the specified function with the amount of bytes hashed so far, across all files in the tree (or both trees,
+    /// in `diff_dirs()`), and the relative path of the file being hashed, every time at least `granularity` more bytes
+    /// of that file have been hashed, and when it's been hashed completely, as with `IoOptions::progress()`.
+    ///
+    /// With multiple threads, the function is called from all of them, as they hash their files.
+    /// Symbolic links' targets aren't counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::dir::DirOptions;
+    /// # use std::sync::{Arc, Mutex};
+    /// # use std::fs;
+    /// # #[cfg(feature = "width-256")] {
+    /// let root = std::env::temp_dir().join("blake-rs-doctest-dir-progress");
+    /// # let _ = fs::remove_dir_all(&root);
+    /// fs::create_dir_all(&root).unwrap();
+    /// fs::write(root.join("fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+    /// fs::write(root.join("proles.txt"), b"Proletarians of all countries, unite!").unwrap();
+    ///
+    /// let reports = Arc::new(Mutex::new(vec![]));
+    /// let sink = reports.clone();
+    /// let options = DirOptions::new().progress(move |done, path| sink.lock().unwrap().push((done, path.to_string())), 1024);
+    ///
+    /// let mut result = [0; 32];
+    /// blake::dir::hash_dir_with(256, &root, &mut result, &options).unwrap();
+    /// assert_eq!(*reports.lock().unwrap(), vec![(37, "fox.txt".to_string()), (74, "proles.txt".to_string())]);
+    /// # fs::remove_dir_all(&root).unwrap();
+    /// # }
+    /// ```
+    pub fn progress<F: Fn(u64, &str) + Send + Sync + 'static>(mut self, callback: F, granularity: u64) -> DirOptions {
+        self.progress = Some(DirProgress {
+            callback: Arc::new(callback),
+            granularity,
+        });
+        self
+    }
+
+    /// Return an `io::Error` wrapping `BlakeError::Cancelled` if the cancellation flag is set.
+    fn check_cancelled(&self) -> io::Result<()> {
+        if self.cancel.as_ref().map(|c| c.load(atomic::Ordering::Relaxed)).unwrap_or(false) {
+            Err(BlakeError::Cancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+impl fmt::Debug for DirProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DirProgress").field("granularity", &self.granularity).finish_non_exhaustive()
+    }
+}
+
+
+impl Change {
+    /// Get the path of the changed file, relative to the root of the tree.
+    pub fn path(&self) -> &str {
+        match *self {
+            Change::Added { ref path, .. } |
+            Change::Removed { ref path, .. } |
+            Change::Modified { ref path, .. } |
+            Change::MetadataChanged { ref path, .. } => path,
+        }
+    }
+}
+
+
+/// Hash the directory tree at the specified path, as described in the module-level documentation.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or if `hashval` is shorter than the hash function's size, before walking the tree,
+/// one with kind `io::ErrorKind::InvalidData` if a path isn't valid UTF-8,
+/// one wrapping `BlakeError::Cancelled` if cancelled via `DirOptions::cancel()`,
+/// or any error encountered walking the tree or reading the files.
+pub fn hash_dir<P: AsRef<Path>>(hashbitlen: i32, root: P, hashval: &mut [u8]) -> io::Result<()> {
+    hash_dir_with(hashbitlen, root, hashval, &DirOptions::new())
+}
+
+/// Hash the directory tree at the specified path with the specified options.
+///
+/// Refer to `hash_dir()` for details.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::dir::DirOptions;
+/// # use std::fs;
+/// # #[cfg(feature = "width-256")] {
+/// let root = std::env::temp_dir().join("blake-rs-doctest-dir-with");
+/// # let _ = fs::remove_dir_all(&root);
+/// fs::create_dir_all(&root).unwrap();
+/// fs::write(root.join("fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+///
+/// let mut result_contents = [0; 32];
+/// let mut result_mode     = [0; 32];
+/// blake::dir::hash_dir_with(256, &root, &mut result_contents, &DirOptions::new()).unwrap();
+/// blake::dir::hash_dir_with(256, &root, &mut result_mode, &DirOptions::new().mode(true)).unwrap();
+///
+/// assert!(result_contents != result_mode);
+/// assert!(blake::dir::hash_dir_with(256, &root, &mut result_mode[..31], &DirOptions::new()).is_err());
+/// # fs::remove_dir_all(&root).unwrap();
+/// # }
+/// ```
+pub fn hash_dir_with<P: AsRef<Path>>(hashbitlen: i32, root: P, hashval: &mut [u8], options: &DirOptions) -> io::Result<()> {
+    check_hashval(hashbitlen, hashval)?;
+    let mut hasher = StructuredHasher::new(hashbitlen)?;
+
+    let hashed = AtomicU64::new(0);
+    trace::instrumented_stream("hash_dir", hashbitlen, || {
+        let entries = walk(root.as_ref(), options)?;
+        let digests = digests(hashbitlen, &entries, options, &hashed)?;
+        for (entry, digest) in entries.iter().zip(digests) {
+            if entry.file_type.is_symlink() {
+                let target = symlink_target(&entry.path)?;
+                hasher.record(|r| {
+                    r.str(&entry.relative).u64(KIND_SYMLINK).str(&target);
+                });
+            } else {
+                let mode = if options.mode { Some(mode(&entry.path)?) } else { None };
+                hasher.record(|r| {
+                    r.str(&entry.relative).u64(KIND_FILE).bytes(&digest);
+                    if let Some(mode) = mode {
+                        r.u64(mode);
+                    }
+                });
+            }
+        }
+
+        hasher.finalise(hashval).expect("the buffer fits the digest");
+        Ok(hashed.load(atomic::Ordering::Relaxed))
+    })?;
+    Ok(())
+}
+
+/// Hash two directory trees and report the files added, removed, and modified between them, ordered by path.
+///
+/// Files are compared by their digests, as in `hash_dir()`; if symbolic links are enabled in the options, they're
+/// compared by the digests of their targets. Permission bits aren't compared.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// one with kind `io::ErrorKind::InvalidData` if a path isn't valid UTF-8,
+/// one wrapping `BlakeError::Cancelled` if cancelled via `DirOptions::cancel()`,
+/// or any error encountered walking the trees or reading the files.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::dir::{Change, DirOptions};
+/// # use std::fs;
+/// # #[cfg(feature = "width-256")] {
+/// let old = std::env::temp_dir().join("blake-rs-doctest-diff-old");
+/// let new = std::env::temp_dir().join("blake-rs-doctest-diff-new");
+/// # let _ = fs::remove_dir_all(&old);
+/// # let _ = fs::remove_dir_all(&new);
+/// fs::create_dir_all(&old).unwrap();
+/// fs::create_dir_all(&new).unwrap();
+/// fs::write(old.join("same.txt"), b"Abolish the bourgeoisie").unwrap();
+/// fs::write(new.join("same.txt"), b"Abolish the bourgeoisie").unwrap();
+/// fs::write(old.join("fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+/// fs::write(new.join("fox.txt"), b"The lazy dog jumps over the lazy fox.").unwrap();
+/// fs::write(new.join("proles.txt"), b"Proletarians of all countries, unite!").unwrap();
+///
+/// let changes = blake::dir::diff_dirs(256, &old, &new, &DirOptions::new().threads(2)).unwrap();
+/// assert_eq!(changes.iter().map(Change::path).collect::<Vec<_>>(), vec!["fox.txt", "proles.txt"]);
+/// match changes[0] {
+///     Change::Modified { ref old, ref new, .. } => assert!(old != new),
+///     ref change => panic!("{:?}", change),
+/// }
+/// # fs::remove_dir_all(&old).unwrap();
+/// # fs::remove_dir_all(&new).unwrap();
+/// # }
+/// ```
+pub fn diff_dirs<P: AsRef<Path>, Q: AsRef<Path>>(hashbitlen: i32, old: P, new: Q, options: &DirOptions) -> io::Result<Vec<Change>> {
+    let hashed = AtomicU64::new(0);
+    let mut manifests = None;
+    trace::instrumented_stream("diff_dirs", hashbitlen, || {
+        let old = manifest_counted(hashbitlen, old.as_ref(), options, &hashed)?;
+        let new = manifest_counted(hashbitlen, new.as_ref(), options, &hashed)?;
+        manifests = Some((old, new));
+        Ok(hashed.load(atomic::Ordering::Relaxed))
+    })?;
+
+    let (old, new) = manifests.expect("manifests listed");
+    Ok(diff(old, new, false))
+}
+
+/// List the files in the directory tree at the specified path, and their digests, ordered by path.
+///
+/// Symbolic links, if enabled in the options, are listed with the digests of their targets, as in `diff_dirs()`.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// one with kind `io::ErrorKind::InvalidData` if a path isn't valid UTF-8,
+/// one wrapping `BlakeError::Cancelled` if cancelled via `DirOptions::cancel()`,
+/// or any error encountered walking the tree or reading the files.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::dir::DirOptions;
+/// # use std::fs;
+/// # #[cfg(feature = "width-256")] {
+/// let root = std::env::temp_dir().join("blake-rs-doctest-manifest");
+/// # let _ = fs::remove_dir_all(&root);
+/// fs::create_dir_all(root.join("a")).unwrap();
+/// fs::write(root.join("a/fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+/// fs::write(root.join("proles.txt"), b"Proletarians of all countries, unite!").unwrap();
+///
+/// let entries = blake::dir::manifest_with(256, &root, &DirOptions::new()).unwrap();
+/// let mut manifest = vec![];
+/// blake::dir::write_manifest(&entries, &mut manifest).unwrap();
+/// assert!(String::from_utf8(manifest).unwrap()
+///     .starts_with("f2e5a9d093d8aa234e6c545061e817be838b57d8998f15df72e1037fbfeb4fc7  a/fox.txt\n"));
+///
+/// fs::write(root.join("proles.txt"), b"Workers of the world, unite!").unwrap();
+/// let changes = blake::dir::check_manifest(256, &root, &entries, &DirOptions::new()).unwrap();
+/// assert_eq!(changes.iter().map(|c| c.path()).collect::<Vec<_>>(), vec!["proles.txt"]);
+/// # fs::remove_dir_all(&root).unwrap();
+/// # }
+/// ```
+pub fn manifest_with<P: AsRef<Path>>(hashbitlen: i32, root: P, options: &DirOptions) -> io::Result<Vec<ManifestEntry>> {
+    manifest_instrumented("manifest", hashbitlen, root.as_ref(), options)
+}
+
+/// Write the manifest in the text format of `b2sum` and the like: a line of the lower-case hexadecimal digest,
+/// two spaces, and the path, for each entry.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidData` if a path contains a newline,
+/// or any error encountered writing.
+pub fn write_manifest<W: Write>(entries: &[ManifestEntry], mut out: W) -> io::Result<()> {
+    for entry in entries {
+        if entry.path.contains('\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{:?} contains a newline", entry.path)));
+        }
+
+        for byte in &entry.digest {
+            write!(out, "{:02x}", byte)?;
+        }
+        writeln!(out, "  {}", entry.path)?;
+    }
+    Ok(())
+}
+
+/// Read a manifest written by `write_manifest()`, or by `b2sum` and the like; empty lines are skipped.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidData` if a line isn't a hexadecimal digest, two spaces, and a path,
+/// or any error encountered reading.
+pub fn read_manifest<R: BufRead>(input: R) -> io::Result<Vec<ManifestEntry>> {
+    let mut ret = vec![];
+    for line in input.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+
+        let malformed = || io::Error::new(io::ErrorKind::InvalidData, format!("Malformed manifest line {:?}", line));
+        let (digest, path) = line.split_once("  ").ok_or_else(malformed)?;
+        ret.push(ManifestEntry {
+            path: path.to_string(),
+            digest: hex_decode(digest).ok_or_else(malformed)?,
+            metadata: None,
+        });
+    }
+    Ok(ret)
+}
+
+/// Write the manifest as JSON, with the files' metadata, for tools that can't parse the text format.
+///
+/// The document is an object with `"version"`, currently `1`, and `"files"`, an array of, for each entry,
+/// an object with the `"path"`, the lower-case hexadecimal `"digest"`, the `"size"` in bytes,
+/// the `"mtime"` in seconds since the Unix epoch, and, if known, the `"mode"`.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if an entry has no metadata, as when read from a text manifest,
+/// or any error encountered writing.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::dir::DirOptions;
+/// # use std::fs;
+/// let root = std::env::temp_dir().join("blake-rs-doctest-json-manifest");
+/// # let _ = fs::remove_dir_all(&root);
+/// fs::create_dir_all(&root).unwrap();
+/// fs::write(root.join("fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+///
+/// let entries = blake::dir::manifest_with(256, &root, &DirOptions::new()).unwrap();
+/// let mut manifest = vec![];
+/// blake::dir::write_json_manifest(&entries, &mut manifest).unwrap();
+///
+/// let read = blake::dir::read_json_manifest(&manifest[..]).unwrap();
+/// assert_eq!(read, entries);
+/// assert_eq!(read[0].metadata.unwrap().size, 37);
+/// assert!(blake::dir::check_manifest(256, &root, &read, &DirOptions::new()).unwrap().is_empty());
+/// # fs::remove_dir_all(&root).unwrap();
+/// ```
+#[cfg(feature = "json")]
+pub fn write_json_manifest<W: Write>(entries: &[ManifestEntry], out: W) -> io::Result<()> {
+    let files = entries.iter()
+        .map(|entry| {
+            let metadata = entry.metadata
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} has no metadata", entry.path)))?;
+            let mut file = ::serde_json::Map::new();
+            file.insert("path".to_string(), entry.path.clone().into());
+            file.insert("digest".to_string(), entry.digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>().into());
+            file.insert("size".to_string(), metadata.size.into());
+            file.insert("mtime".to_string(), metadata.mtime.into());
+            if let Some(mode) = metadata.mode {
+                file.insert("mode".to_string(), mode.into());
+            }
+            Ok(file.into())
+        })
+        .collect::<io::Result<Vec<::serde_json::Value>>>()?;
+
+    let mut document = ::serde_json::Map::new();
+    document.insert("version".to_string(), 1.into());
+    document.insert("files".to_string(), files.into());
+    ::serde_json::to_writer_pretty(out, &document)?;
+    Ok(())
+}
+
+/// Read a manifest written by `write_json_manifest()`, with the files' metadata.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidData` if the input isn't JSON,
+/// isn't a version `1` manifest, or an entry is missing a field or has one of the wrong type,
+/// or any error encountered reading.
+#[cfg(feature = "json")]
+pub fn read_json_manifest<R: io::Read>(input: R) -> io::Result<Vec<ManifestEntry>> {
+    let malformed = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("Malformed JSON manifest: {}", what));
+
+    let document: ::serde_json::Value = ::serde_json::from_reader(input)?;
+    if document["version"] != 1 {
+        return Err(malformed("unsupported version"));
+    }
+    document["files"]
+        .as_array()
+        .ok_or_else(|| malformed("no files"))?
+        .iter()
+        .map(|file| {
+            let path = file["path"].as_str().ok_or_else(|| malformed("bad path"))?;
+            Ok(ManifestEntry {
+                path: path.to_string(),
+                digest: file["digest"].as_str().and_then(hex_decode).ok_or_else(|| malformed("bad digest"))?,
+                metadata: Some(FileMetadata {
+                    size: file["size"].as_u64().ok_or_else(|| malformed("bad size"))?,
+                    mtime: file["mtime"].as_i64().ok_or_else(|| malformed("bad mtime"))?,
+                    mode: match file.get("mode") {
+                        Some(mode) => Some(mode.as_u64().ok_or_else(|| malformed("bad mode"))?),
+                        None => None,
+                    },
+                }),
+            })
+        })
+        .collect()
+}
+
+/// Check the directory tree at the specified path against a manifest of it, as from `manifest_with()`,
+/// reporting the changes from the manifest to the tree, ordered by path, as in `diff_dirs()`;
+/// none if the tree matches.
+///
+/// If the manifest has the files' metadata, as when read from a JSON manifest, files with the same contents are also
+/// checked for a different size and, if recorded in the manifest and enabled with `DirOptions::mode()`,
+/// different permission bits, and reported as `Change::MetadataChanged`. Modification times aren't compared,
+/// since copying or checking out a tree changes them.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::dir::{Change, DirOptions};
+/// # use std::fs;
+/// # #[cfg(feature = "width-256")] {
+/// let root = std::env::temp_dir().join("blake-rs-doctest-check-manifest-mode");
+/// # let _ = fs::remove_dir_all(&root);
+/// fs::create_dir_all(&root).unwrap();
+/// fs::write(root.join("fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+///
+/// let options = DirOptions::new().mode(true);
+/// let mut entries = blake::dir::manifest_with(256, &root, &options).unwrap();
+/// assert!(blake::dir::check_manifest(256, &root, &entries, &options).unwrap().is_empty());
+///
+/// entries[0].metadata.as_mut().unwrap().mode = Some(0o4755);
+/// match blake::dir::check_manifest(256, &root, &entries, &options).unwrap()[..] {
+///     [Change::MetadataChanged { ref old, ref new, .. }] => assert!(old.mode != new.mode),
+///     ref changes => panic!("{:?}", changes),
+/// }
+/// # fs::remove_dir_all(&root).unwrap();
+/// # }
+/// ```
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// one with kind `io::ErrorKind::InvalidData` if a path isn't valid UTF-8,
+/// one wrapping `BlakeError::Cancelled` if cancelled via `DirOptions::cancel()`,
+/// or any error encountered walking the tree or reading the files.
+pub fn check_manifest<P: AsRef<Path>>(hashbitlen: i32, root: P, expected: &[ManifestEntry], options: &DirOptions) -> io::Result<Vec<Change>> {
+    let mut expected = expected.to_vec();
+    expected.sort_by(|l, r| l.path.cmp(&r.path));
+    Ok(diff(expected, manifest_instrumented("check_manifest", hashbitlen, root.as_ref(), options)?, true))
+}
+
+
+/// Merge two manifests, ordered by path, into the changes from the old to the new one,
+/// comparing the metadata of files with the same contents if `metadata` is set.
+fn diff(old: Vec<ManifestEntry>, new: Vec<ManifestEntry>, metadata: bool) -> Vec<Change> {
+    let mut old = old.into_iter().peekable();
+    let mut new = new.into_iter().peekable();
+
+    let mut ret = vec![];
+    loop {
+        let order = match (old.peek(), new.peek()) {
+            (Some(o), Some(n)) => o.path.cmp(&n.path),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+
+        match order {
+            Ordering::Less => {
+                let ManifestEntry { path, digest, .. } = old.next().unwrap();
+                ret.push(Change::Removed { path, digest });
+            }
+            Ordering::Greater => {
+                let ManifestEntry { path, digest, .. } = new.next().unwrap();
+                ret.push(Change::Added { path, digest });
+            }
+            Ordering::Equal => {
+                let (old, new) = (old.next().unwrap(), new.next().unwrap());
+                if old.digest != new.digest {
+                    ret.push(Change::Modified {
+                        path: old.path,
+                        old: old.digest,
+                        new: new.digest,
+                    });
+                } else if let (true, Some(old_metadata), Some(new_metadata)) = (metadata, old.metadata, new.metadata) {
+                    let mode_changed = matches!((old_metadata.mode, new_metadata.mode), (Some(o), Some(n)) if o != n);
+                    if old_metadata.size != new_metadata.size || mode_changed {
+                        ret.push(Change::MetadataChanged {
+                            path: old.path,
+                            old: old_metadata,
+                            new: new_metadata,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// List the files in the tree, as in `manifest_with()`, in a span of the specified operation.
+fn manifest_instrumented(operation: &'static str, hashbitlen: i32, root: &Path, options: &DirOptions) -> io::Result<Vec<ManifestEntry>> {
+    let hashed = AtomicU64::new(0);
+    let mut manifest = vec![];
+    trace::instrumented_stream(operation, hashbitlen, || {
+        manifest = manifest_counted(hashbitlen, root, options, &hashed)?;
+        Ok(hashed.load(atomic::Ordering::Relaxed))
+    })?;
+    Ok(manifest)
+}
+
+/// List the files in the tree, as in `manifest_with()`, counting the bytes hashed in `hashed`.
+fn manifest_counted(hashbitlen: i32, root: &Path, options: &DirOptions, hashed: &AtomicU64) -> io::Result<Vec<ManifestEntry>> {
+    let entries = walk(root, options)?;
+    let digests = digests(hashbitlen, &entries, options, hashed)?;
+    entries.into_iter()
+        .zip(digests)
+        .map(|(entry, digest)| {
+            let mode = if options.mode && !entry.file_type.is_symlink() { Some(mode(&entry.path)?) } else { None };
+            let metadata = fs::symlink_metadata(&entry.path)?;
+            Ok(ManifestEntry {
+                metadata: Some(FileMetadata {
+                    size: metadata.len(),
+                    mtime: unix_time(metadata.modified()?),
+                    mode,
+                }),
+                path: entry.relative,
+                digest,
+            })
+        })
+        .collect()
+}
+
+/// Hash the files (and symbolic links' targets), on as many threads as specified in the options,
+/// counting the bytes hashed in `hashed` for progress reports.
+fn digests(hashbitlen: i32, entries: &[Entry], options: &DirOptions, hashed: &AtomicU64) -> io::Result<Vec<Vec<u8>>> {
+    let size = digest_size(hashbitlen)?;
+    let hash_all = |entries: &[Entry]| -> io::Result<Vec<Vec<u8>>> {
+        entries.iter()
+            .map(|entry| {
+                options.check_cancelled()?;
+                let mut digest = vec![0; size];
+                if entry.file_type.is_symlink() {
+                    let target = symlink_target(&entry.path)?;
+                    hash(hashbitlen, target.as_bytes(), &mut digest)?;
+                    hashed.fetch_add(target.len() as u64, atomic::Ordering::Relaxed);
+                } else {
+                    let mut file_done = 0;
+                    let mut report = |done: u64, _: Option<u64>| {
+                        if let Some(ref progress) = options.progress {
+                            let total = hashed.fetch_add(done - file_done, atomic::Ordering::Relaxed) + (done - file_done);
+                            file_done = done;
+                            (progress.callback)(total, &entry.relative);
+                        }
+                    };
+
+                    let mut io_options = IoOptions::new();
+                    if let Some(ref progress) = options.progress {
+                        io_options = io_options.progress(&mut report, progress.granularity);
+                    }
+                    if let Some(ref limiter) = options.rate_limit {
+                        io_options = io_options.rate_limit(limiter);
+                    }
+                    if let Some(bytes) = options.buffer_size {
+                        io_options = io_options.buffer_size(bytes);
+                    }
+                    if let Some(ref flag) = options.cancel {
+                        io_options = io_options.cancel(flag);
+                    }
+                    let done = hash_file_with(hashbitlen, &entry.path, &mut digest, &mut io_options)?;
+                    // Count what the progress reports didn't, all of it if there were none
+                    hashed.fetch_add(done - file_done, atomic::Ordering::Relaxed);
+                }
+                Ok(digest)
+            })
+            .collect()
+    };
+
+    if options.threads <= 1 || entries.len() <= 1 {
+        return hash_all(entries);
+    }
+
+    let chunk_size = entries.len().div_ceil(options.threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = entries.chunks(chunk_size).map(|chunk| scope.spawn(move || hash_all(chunk))).collect();
+
+        let mut ret = Vec::with_capacity(entries.len());
+        for worker in workers {
+            ret.extend(worker.join().expect("hashing thread panicked")?);
+        }
+        Ok(ret)
+    })
+}
+
+/// Find all regular files (and, if enabled, symbolic links) under the root, sorted by relative path.
+fn walk(root: &Path, options: &DirOptions) -> io::Result<Vec<Entry>> {
+    let mut ret = vec![];
+    walk_into(root, "", options, &mut options.ignore.clone(), &mut ret)?;
+    ret.sort_by(|l, r| l.relative.cmp(&r.relative));
+    Ok(ret)
+}
+
+fn walk_into(dir: &Path, prefix: &str, options: &DirOptions, ignore: &mut IgnoreRules, out: &mut Vec<Entry>) -> io::Result<()> {
+    let outer_rules = ignore.len();
+    if options.ignore_files {
+        match fs::read_to_string(dir.join(IGNORE_FILE)) {
+            Ok(patterns) => ignore.add_file(prefix, &patterns),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    for entry in fs::read_dir(dir)? {
+        options.check_cancelled()?;
+        let entry = entry?;
+        let name = entry.file_name();
+        let relative = format!("{}{}", prefix, name.to_str().ok_or_else(|| non_utf8(&entry.path()))?);
+        let file_type = entry.file_type()?;
+
+        if ignore.is_ignored(&relative, file_type.is_dir()) {
+            continue;
+        }
+
+        if file_type.is_dir() {
+            walk_into(&entry.path(), &format!("{}/", relative), options, ignore, out)?;
+        } else if file_type.is_file() || (file_type.is_symlink() && options.symlinks) {
+            out.push(Entry {
+                relative,
+                path: entry.path(),
+                file_type,
+            });
+        }
+    }
+
+    ignore.truncate(outer_rules);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mode(path: &Path) -> io::Result<u64> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok((fs::metadata(path)?.permissions().mode() & 0o7777) as u64)
+}
+
+#[cfg(not(unix))]
+fn mode(path: &Path) -> io::Result<u64> {
+    Ok(if fs::metadata(path)?.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    })
+}
+
+fn unix_time(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    }
+}
+
+fn symlink_target(path: &Path) -> io::Result<String> {
+    let target = fs::read_link(path)?;
+    target.into_os_string().into_string().map_err(|target| non_utf8(Path::new(&target)))
+}
+
+fn non_utf8(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{} is not valid UTF-8", path.display()))
+}
@@ -0,0 +1,99 @@
+use super::VerifyingWriter;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::io;
+use std::process;
+
+
+/// Download the file at the specified URL to the specified path, if it hashes to the expected digest,
+/// with the hash function of the specified length, returning the amount of bytes downloaded.
+///
+/// The response is streamed through a `VerifyingWriter` into a temporary file next to the destination,
+/// which is synced and renamed over it only if the digest matches, and removed otherwise;
+/// so the destination is either left untouched or atomically replaced with the verified file,
+/// even if the download is interrupted.
+///
+/// This uses `reqwest`'s blocking client, so mustn't be called from within an asynchronous runtime;
+/// use `tokio::task::spawn_blocking()` or the like there.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or if the expected digest isn't of the hash function's size, one with kind `io::ErrorKind::InvalidData`,
+/// wrapping `BlakeError::DigestMismatch`, if the digest doesn't match, one wrapping the `reqwest::Error`
+/// if the request fails or the response's status isn't a success, or any error encountered writing the file.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::BlakeError;
+/// # use std::io::{Read, Write};
+/// # use std::net::TcpListener;
+/// # use std::{fs, thread};
+/// # let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// # let url = format!("http://{}/fox.txt", listener.local_addr().unwrap());
+/// # thread::spawn(move || for stream in listener.incoming() {
+/// #     let mut stream = stream.unwrap();
+/// #     stream.read(&mut [0; 1024]).unwrap();
+/// #     stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 37\r\nConnection: close\r\n\r\n").unwrap();
+/// #     stream.write_all(b"The lazy fox jumps over the lazy dog.").unwrap();
+/// # });
+/// let dest = std::env::temp_dir().join("blake-rs-doctest-download.txt");
+/// # let _ = fs::remove_file(&dest);
+/// let mut expected = [0; 32];
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut expected).unwrap();
+///
+/// assert_eq!(blake::download_verified(256, &url, &expected, &dest).unwrap(), 37);
+/// assert_eq!(fs::read(&dest).unwrap(), b"The lazy fox jumps over the lazy dog.");
+///
+/// fs::write(&dest, b"Previous version").unwrap();
+/// let err = blake::download_verified(256, &url, &[0; 32], &dest).unwrap_err();
+/// assert_eq!(BlakeError::from_io_error(&err), Some(BlakeError::DigestMismatch));
+/// assert_eq!(fs::read(&dest).unwrap(), b"Previous version");
+/// # fs::remove_file(&dest).unwrap();
+/// ```
+pub fn download_verified<P: AsRef<Path>>(hashbitlen: i32, url: &str, expected: &[u8], dest: P) -> io::Result<u64> {
+    download_verified_with(&::reqwest::blocking::Client::new(), hashbitlen, url, expected, dest)
+}
+
+/// Download the file at the specified URL to the specified path with the specified client, for proxies, timeouts,
+/// and the like, if it hashes to the expected digest.
+///
+/// Refer to `download_verified()` for details.
+pub fn download_verified_with<P: AsRef<Path>>(client: &::reqwest::blocking::Client, hashbitlen: i32, url: &str, expected: &[u8], dest: P)
+                                              -> io::Result<u64> {
+    let dest = dest.as_ref();
+    let (temp_path, temp) = temp_file(dest)?;
+
+    let result = (|| {
+        let mut writer = VerifyingWriter::new(temp, hashbitlen, expected)?;
+        let mut response = client.get(url).send().and_then(|r| r.error_for_status()).map_err(io::Error::other)?;
+        let size = io::copy(&mut response, &mut writer)?;
+
+        writer.finish()?.sync_all()?;
+        fs::rename(&temp_path, dest)?;
+        Ok(size)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+
+/// Create a new file next to the destination, with a name unique to this process.
+fn temp_file(dest: &Path) -> io::Result<(PathBuf, File)> {
+    let name = dest.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} isn't a file path", dest)))?;
+
+    let mut attempt = 0;
+    loop {
+        let mut temp_name = name.to_os_string();
+        temp_name.push(format!(".{}.{}.part", process::id(), attempt));
+        let temp_path = dest.with_file_name(temp_name);
+
+        match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+
+/// Encode the specified bytes as padded standard-alphabet base64.
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut ret = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | (b[2] as u32);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ret.push(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                ret.push('=');
+            }
+        }
+    }
+
+    ret
+}
+
+/// Decode padded standard-alphabet base64, returning `None` if the input isn't canonically-encoded.
+pub fn base64_decode(data: &str) -> Option<Vec<u8>> {
+    let data = data.as_bytes();
+    if !data.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut ret = Vec::with_capacity(data.len() / 4 * 3);
+    for (idx, chunk) in data.chunks(4).enumerate() {
+        let last = idx == data.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding != 0 && !last) {
+            return None;
+        }
+
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            n = (n << 6) | BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+        }
+        n <<= 6 * padding as u32;
+
+        // Reject non-zero trailing bits, since they'd make multiple strings decode to the same bytes
+        if n & ((1 << (8 * padding as u32)) - 1) != 0 {
+            return None;
+        }
+
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        ret.extend_from_slice(&bytes[..3 - padding]);
+    }
+
+    Some(ret)
+}
+
+/// Decode hexadecimal of either case, returning `None` if the input isn't an even amount of hexadecimal digits.
+pub fn hex_decode(data: &str) -> Option<Vec<u8>> {
+    let data = data.as_bytes();
+    if !data.len().is_multiple_of(2) {
+        return None;
+    }
+
+    data.chunks(2)
+        .map(|pair| {
+            let digit = |c: u8| (c as char).to_digit(16);
+            Some((digit(pair[0])? << 4 | digit(pair[1])?) as u8)
+        })
+        .collect()
+}
+
+/// Encode the specified bytes as unpadded RFC 4648 base32.
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut ret = String::with_capacity((data.len() * 8).div_ceil(5));
+
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            ret.push(BASE32_ALPHABET[((buffer >> bits) & 0x1F) as usize] as char);
+        }
+    }
+    if bits != 0 {
+        ret.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize] as char);
+    }
+
+    ret
+}
@@ -0,0 +1,796 @@
+use super::stack::StackState;
+use super::{BlakeError, trace, digest_size};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use std::sync::Mutex;
+use std::thread;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::fs::File;
+use std::{cmp, mem};
+
+
+/// Options for hashing data from I/O sources with `hash_reader_with()` and `hash_file_with()`.
+///
+/// # Examples
+///
+/// Reporting progress every 16 bytes.
+///
+/// ```
+/// # use blake::IoOptions;
+/// # #[cfg(feature = "width-256")] {
+/// let mut reports = vec![];
+/// let mut progress = |done, total| reports.push((done, total));
+///
+/// let mut result = [0; 32];
+/// blake::hash_reader_with(256,
+///                         &b"The lazy fox jumps over the lazy dog."[..],
+///                         &mut result,
+///                         &mut IoOptions::new().progress(&mut progress, 16))
+///     .unwrap();
+///
+/// assert_eq!(reports, vec![(37, None)]);
+/// # }
+/// ```
+#[derive(Default)]
+pub struct IoOptions<'a> {
+    progress: Option<&'a mut (dyn FnMut(u64, Option<u64>) + Send)>,
+    progress_granularity: u64,
+    checkpoint: Option<&'a mut CheckpointFn<'a>>,
+    checkpoint_interval: u64,
+    cancel: Option<&'a AtomicBool>,
+    rate_limit: Option<&'a RateLimiter>,
+    buffer_size: Option<usize>,
+}
+
+/// A callback receiving the amount of bytes hashed so far and their digest, set with `IoOptions::checkpoint()`.
+type CheckpointFn<'a> = dyn FnMut(u64, &[u8]) + Send + 'a;
+
+/// A token bucket limiting the throughput of hashing from I/O sources, which can be shared between them and
+/// between threads, to limit their total throughput.
+///
+/// Up to one second's worth of bytes can be read in a burst, after the limiter hasn't been used for a while.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::{IoOptions, RateLimiter};
+/// # use std::time::{Duration, Instant};
+/// # use std::io::{self, Read};
+/// # #[cfg(feature = "width-256")] {
+/// let limiter = RateLimiter::new(64 * 1024);
+///
+/// let start = Instant::now();
+/// let mut result = [0; 32];
+/// blake::hash_reader_with(256, io::repeat(0).take(96 * 1024), &mut result, &mut IoOptions::new().rate_limit(&limiter))
+///     .unwrap();
+/// assert!(start.elapsed() >= Duration::from_millis(500));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    bytes_per_second: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+
+impl<'a> IoOptions<'a> {
+    /// Create options with no progress reporting and no cancellation.
+    #[must_use]
+    pub fn new() -> IoOptions<'a> {
+        IoOptions::default()
+    }
+
+    /// Call the specified function with the amount of bytes hashed so far, and the total amount of bytes to hash,
+    /// if known, every time at least `granularity` more bytes have been hashed, and at the end,
+    /// unless the last call already reported all bytes.
+    ///
+    /// The callback must be `Send`, so futures from `hash_file_async_with()` can be spawned onto other threads.
+    pub fn progress(mut self, callback: &'a mut (dyn FnMut(u64, Option<u64>) + Send), granularity: u64) -> IoOptions<'a> {
+        self.progress = Some(callback);
+        self.progress_granularity = granularity;
+        self
+    }
+
+    /// Every `interval` bytes, call the specified function with the amount of bytes hashed so far and the digest of all
+    /// of them, as if the data ended there, without affecting the digest of the whole.
+    ///
+    /// The checkpoints fall on exact multiples of `interval`, regardless of how much each read returns;
+    /// each is computed by finalising a fork of the state, which costs one or two compressions.
+    /// If the amount of data is a multiple of `interval`, the last checkpoint is the digest of the whole.
+    ///
+    /// Panics if `interval` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// Sending checkpoints to another thread.
+    ///
+    /// ```
+    /// # use blake::IoOptions;
+    /// # use std::io::{self, Read};
+    /// # use std::sync::mpsc;
+    /// # #[cfg(feature = "width-256")] {
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut checkpoint = |done, digest: &[u8]| sender.send((done, digest.to_vec())).unwrap();
+    ///
+    /// let mut result = [0; 32];
+    /// blake::hash_reader_with(256, io::repeat(0xA5).take(2500), &mut result,
+    ///                         &mut IoOptions::new().checkpoint(&mut checkpoint, 1000))
+    ///     .unwrap();
+    ///
+    /// let checkpoints: Vec<_> = receiver.try_iter().collect();
+    /// assert_eq!(checkpoints.iter().map(|&(done, _)| done).collect::<Vec<_>>(), vec![1000, 2000]);
+    ///
+    /// let mut result_first = [0; 32];
+    /// blake::hash(256, &[0xA5; 1000][..], &mut result_first).unwrap();
+    /// assert_eq!(checkpoints[0].1, result_first);
+    /// # }
+    /// ```
+    pub fn checkpoint(mut self, callback: &'a mut (dyn FnMut(u64, &[u8]) + Send), interval: u64) -> IoOptions<'a> {
+        assert!(interval != 0, "Checkpoint interval must be positive");
+        self.checkpoint = Some(callback);
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Stop hashing as soon as possible after the specified flag is set, returning an `io::Error` wrapping
+    /// `BlakeError::Cancelled`.
+    ///
+    /// The flag is checked before every read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use blake::{BlakeError, IoOptions};
+    /// # use std::io::{self, Read};
+    /// # #[cfg(feature = "width-256")] {
+    /// let cancel = AtomicBool::new(false);
+    /// let mut progress = |_, _| cancel.store(true, Ordering::Relaxed);
+    ///
+    /// let mut result = [0; 32];
+    /// let err = blake::hash_reader_with(256,
+    ///                                   io::repeat(0).take(1024 * 1024 * 1024),
+    ///                                   &mut result,
+    ///                                   &mut IoOptions::new().progress(&mut progress, 1).cancel(&cancel))
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(BlakeError::from_io_error(&err), Some(BlakeError::Cancelled));
+    /// # }
+    /// ```
+    pub fn cancel(mut self, flag: &'a AtomicBool) -> IoOptions<'a> {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// Limit the throughput with the specified limiter, sleeping after reads as needed.
+    pub fn rate_limit(mut self, limiter: &'a RateLimiter) -> IoOptions<'a> {
+        self.rate_limit = Some(limiter);
+        self
+    }
+
+    /// Read in chunks of up to the specified amount of bytes, instead of the default 64 KiB.
+    ///
+    /// Larger buffers can help with high-latency storage, like network filesystems, smaller ones with memory use.
+    ///
+    /// Panics if `bytes` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::IoOptions;
+    /// # use std::io::{self, Read};
+    /// # #[cfg(feature = "width-256")] {
+    /// let mut result_large = [0; 32];
+    /// let mut result       = [0; 32];
+    ///
+    /// blake::hash_reader_with(256, io::repeat(0xA5).take(300 * 1024), &mut result_large,
+    ///                         &mut IoOptions::new().buffer_size(1024 * 1024))
+    ///     .unwrap();
+    /// blake::hash_reader(256, io::repeat(0xA5).take(300 * 1024), &mut result).unwrap();
+    /// assert_eq!(result_large, result);
+    /// # }
+    /// ```
+    pub fn buffer_size(mut self, bytes: usize) -> IoOptions<'a> {
+        assert!(bytes != 0, "Buffer size must be positive");
+        self.buffer_size = Some(bytes);
+        self
+    }
+
+    /// Return an `io::Error` wrapping `BlakeError::Cancelled` if the cancellation flag is set.
+    fn check_cancelled(&self) -> io::Result<()> {
+        if self.cancel.map(|c| c.load(Ordering::Relaxed)).unwrap_or(false) {
+            Err(BlakeError::Cancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+impl RateLimiter {
+    /// Create a limiter allowing the specified amount of bytes per second.
+    ///
+    /// Panics if `bytes_per_second` is `0`.
+    #[must_use]
+    pub fn new(bytes_per_second: u64) -> RateLimiter {
+        assert!(bytes_per_second != 0, "Rate limit must be positive");
+        RateLimiter {
+            bytes_per_second: bytes_per_second as f64,
+            bucket: Mutex::new((bytes_per_second as f64, Instant::now())),
+        }
+    }
+
+    /// Take the specified amount of bytes from the bucket, sleeping until they'd have been refilled if it goes into
+    /// debt.
+    pub fn consume(&self, bytes: u64) {
+        if let Some(wait) = self.debit(bytes) {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Take the specified amount of bytes from the bucket, returning how long until they'd have been refilled
+    /// if it goes into debt.
+    fn debit(&self, bytes: u64) -> Option<Duration> {
+        let debt = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let (ref mut tokens, ref mut last) = *bucket;
+
+            let now = Instant::now();
+            *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.bytes_per_second).min(self.bytes_per_second);
+            *last = now;
+
+            *tokens -= bytes as f64;
+            -*tokens
+        };
+
+        if debt > 0.0 {
+            Some(Duration::from_secs_f64(debt / self.bytes_per_second))
+        } else {
+            None
+        }
+    }
+}
+
+
+/// The amount of data hashed so far, for reporting it via `IoOptions::progress()`.
+struct Progress {
+    done: u64,
+    total: Option<u64>,
+    reported: Option<u64>,
+    next_report: u64,
+}
+
+impl Progress {
+    fn new(total: Option<u64>, options: &IoOptions<'_>) -> Progress {
+        Progress {
+            done: 0,
+            total,
+            reported: None,
+            next_report: options.progress_granularity,
+        }
+    }
+
+    /// Hash the data just read, emitting checkpoints as it crosses their interval,
+    /// and count it, reporting progress if at least the granularity has been hashed since the last report.
+    fn hash(&mut self, state: &mut StackState, data: &[u8], options: &mut IoOptions<'_>) {
+        match options.checkpoint {
+            Some(ref mut checkpoint) => {
+                let interval = options.checkpoint_interval;
+                let mut at = self.done;
+                let mut rest = data;
+                while !rest.is_empty() {
+                    let len = cmp::min(rest.len() as u64, interval - at % interval) as usize;
+                    state.update(&rest[..len]);
+                    at += len as u64;
+                    rest = &rest[len..];
+
+                    if at.is_multiple_of(interval) {
+                        let mut digest = [0; 64];
+                        state.clone().finalise(&mut digest).expect("the buffer fits the digest");
+                        checkpoint(at, &digest[..state.hashbitlen() as usize / 8]);
+                    }
+                }
+            }
+            None => state.update(data),
+        }
+        self.done += data.len() as u64;
+
+        if let Some(ref mut progress) = options.progress {
+            if self.done >= self.next_report {
+                progress(self.done, self.total);
+                self.reported = Some(self.done);
+                self.next_report = self.done.saturating_add(options.progress_granularity.max(1));
+            }
+        }
+    }
+
+    /// Report all bytes hashed, unless the last report already did.
+    fn finish(&self, options: &mut IoOptions<'_>) {
+        if let Some(ref mut progress) = options.progress {
+            if self.reported != Some(self.done) {
+                progress(self.done, self.total);
+            }
+        }
+    }
+}
+
+
+/// Hash all data from the reader, returning the amount of bytes hashed.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or if `hashval` is shorter than the hash function's size, before reading anything,
+/// one wrapping `BlakeError::Cancelled` if cancelled via `IoOptions::cancel()`,
+/// or any error returned by the reader other than `io::ErrorKind::Interrupted`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io;
+/// # #[cfg(feature = "width-256")] {
+/// let mut result_reader = [0; 32];
+/// let mut result        = [0; 32];
+///
+/// assert_eq!(blake::hash_reader(256, &b"The lazy fox jumps over the lazy dog."[..], &mut result_reader).unwrap(), 37);
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+///
+/// assert_eq!(result_reader, result);
+/// assert_eq!(blake::hash_reader(256, &b"The lazy fox jumps over the lazy dog."[..], &mut [0; 31]).unwrap_err().kind(),
+///            io::ErrorKind::InvalidInput);
+/// # }
+/// ```
+pub fn hash_reader<R: Read>(hashbitlen: i32, reader: R, hashval: &mut [u8]) -> io::Result<u64> {
+    hash_reader_with(hashbitlen, reader, hashval, &mut IoOptions::new())
+}
+
+/// Hash all data from the reader with the specified options, returning the amount of bytes hashed.
+///
+/// Refer to `hash_reader()` for details.
+pub fn hash_reader_with<R: Read>(hashbitlen: i32, reader: R, hashval: &mut [u8], options: &mut IoOptions<'_>) -> io::Result<u64> {
+    hash_sized_reader("hash_reader", hashbitlen, reader, None, hashval, options)
+}
+
+/// Hash the contents of the file at the specified path, returning the amount of bytes hashed.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or if `hashval` is shorter than the hash function's size, before opening the file,
+/// one wrapping `BlakeError::Cancelled` if cancelled via `IoOptions::cancel()`,
+/// or any error encountered opening or reading the file.
+///
+/// # Examples
+///
+/// ```
+/// # use std::iter::FromIterator;
+/// # #[cfg(feature = "width-256")] {
+/// let mut result = [0; 32];
+/// blake::hash_file(256, "LICENSE", &mut result).unwrap();
+///
+/// assert_eq!(Vec::from_iter(result.iter().map(|&i| i)),
+///            vec![0xED, 0xE4, 0xD8, 0xF8, 0x49, 0x25, 0xD0, 0xBD,
+///                 0x06, 0xA4, 0xDC, 0x1C, 0xFD, 0x1B, 0x45, 0x62,
+///                 0xA4, 0xBD, 0x35, 0x25, 0x76, 0x9B, 0x97, 0xF1,
+///                 0x9B, 0x21, 0xC8, 0xDF, 0xDC, 0x4A, 0x80, 0xB1]);
+/// # }
+/// ```
+pub fn hash_file<P: AsRef<Path>>(hashbitlen: i32, path: P, hashval: &mut [u8]) -> io::Result<u64> {
+    hash_file_with(hashbitlen, path, hashval, &mut IoOptions::new())
+}
+
+/// Hash the contents of the file at the specified path with the specified options, returning the amount of bytes
+/// hashed.
+///
+/// The total passed to the progress callback is the file's size at the time it was opened.
+///
+/// Refer to `hash_file()` for details.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::IoOptions;
+/// # use std::fs;
+/// # #[cfg(feature = "width-256")] {
+/// let mut last_report = None;
+/// let mut progress = |done, total| last_report = Some((done, total));
+///
+/// let mut result = [0; 32];
+/// blake::hash_file_with(256, "LICENSE", &mut result, &mut IoOptions::new().progress(&mut progress, 1024)).unwrap();
+///
+/// let size = fs::metadata("LICENSE").unwrap().len();
+/// assert_eq!(last_report, Some((size, Some(size))));
+/// # }
+/// ```
+pub fn hash_file_with<P: AsRef<Path>>(hashbitlen: i32, path: P, hashval: &mut [u8], options: &mut IoOptions<'_>) -> io::Result<u64> {
+    check_hashval(hashbitlen, hashval)?;
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    hash_sized_reader("hash_file", hashbitlen, file, Some(size), hashval, options)
+}
+
+/// Check whether the two readers yield the same data, by hashing them concurrently and comparing the digests.
+///
+/// The first reader is hashed on the calling thread, the second on another one.
+/// As soon as one of them has yielded more data than the other one did in total, both stop, and they don't match.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or any error returned by either reader other than `io::ErrorKind::Interrupted`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::{self, Read};
+/// # #[cfg(feature = "width-256")] {
+/// let fox = &b"The lazy fox jumps over the lazy dog."[..];
+/// assert!(blake::readers_equal_by_hash(256, fox, fox).unwrap());
+/// assert!(!blake::readers_equal_by_hash(256, fox, &b"The lazy dog jumps over the lazy fox."[..]).unwrap());
+///
+/// // Stops reading the endless stream after the first buffer past the end of the other one
+/// assert!(!blake::readers_equal_by_hash(256, fox, io::repeat(0)).unwrap());
+/// # }
+/// ```
+pub fn readers_equal_by_hash<R1: Read, R2: Read + Send>(hashbitlen: i32, reader1: R1, reader2: R2) -> io::Result<bool> {
+    readers_equal_sized("readers_equal_by_hash", hashbitlen, reader1, None, reader2, None)
+}
+
+/// Check whether the files at the specified paths have the same contents, by hashing them concurrently and comparing
+/// the digests, as in `readers_equal_by_hash()`.
+///
+/// Files of different sizes don't match, and aren't read.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or any error encountered opening or reading the files.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// assert!(blake::files_equal_by_hash(256, "LICENSE", "LICENSE").unwrap());
+/// assert!(!blake::files_equal_by_hash(256, "LICENSE", "README.md").unwrap());
+/// # }
+/// ```
+pub fn files_equal_by_hash<P: AsRef<Path>, Q: AsRef<Path>>(hashbitlen: i32, path1: P, path2: Q) -> io::Result<bool> {
+    digest_size(hashbitlen)?;
+    let file1 = File::open(path1)?;
+    let file2 = File::open(path2)?;
+    let size1 = file1.metadata()?.len();
+    let size2 = file2.metadata()?.len();
+    if size1 != size2 {
+        return Ok(false);
+    }
+
+    readers_equal_sized("files_equal_by_hash", hashbitlen, file1, Some(size1), file2, Some(size2))
+}
+
+/// Hash the contents of the file at the specified path, as in `hash_file()`, reading it via `tokio::fs`.
+///
+/// The reads are done on tokio's blocking thread pool, and each buffer read is hashed on the task before the next,
+/// which, for the default 64 KiB buffer, takes on the order of 100µs; so no runtime worker thread is blocked for long,
+/// and hashing many files concurrently doesn't take a thread each.
+///
+/// The returned future is `Send`, and can be dropped to stop hashing.
+///
+/// # Examples
+///
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut result_async = [0; 32];
+/// let mut result       = [0; 32];
+///
+/// let (result_async, size) = tokio::spawn(async move {
+///         let size = blake::hash_file_async(256, "LICENSE", &mut result_async).await.unwrap();
+///         (result_async, size)
+///     })
+///     .await
+///     .unwrap();
+/// assert_eq!(blake::hash_file(256, "LICENSE", &mut result).unwrap(), size);
+/// assert_eq!(result_async, result);
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn hash_file_async<P: AsRef<Path>>(hashbitlen: i32, path: P, hashval: &mut [u8]) -> io::Result<u64> {
+    hash_file_async_with(hashbitlen, path, hashval, &mut IoOptions::new()).await
+}
+
+/// Hash the contents of the file at the specified path with the specified options, as in `hash_file_with()`,
+/// reading it via `tokio::fs`.
+///
+/// The cancellation flag is checked before every read, as in the synchronous version,
+/// and the progress callback is called on the task.
+/// Rate-limiting waits with `tokio::time::sleep()`, so requires the runtime's timer to be enabled.
+///
+/// Refer to `hash_file_async()` for details.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::IoOptions;
+/// # use std::fs;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut last_report = None;
+/// let mut progress = |done, total| last_report = Some((done, total));
+///
+/// let mut result = [0; 32];
+/// blake::hash_file_async_with(256, "LICENSE", &mut result, &mut IoOptions::new().progress(&mut progress, 1024)).await.unwrap();
+///
+/// let size = fs::metadata("LICENSE").unwrap().len();
+/// assert_eq!(last_report, Some((size, Some(size))));
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn hash_file_async_with<P: AsRef<Path>>(hashbitlen: i32, path: P, hashval: &mut [u8], options: &mut IoOptions<'_>) -> io::Result<u64> {
+    use tokio::io::AsyncReadExt;
+
+    check_hashval(hashbitlen, hashval)?;
+    let mut state = StackState::new(hashbitlen)?;
+    let mut file = tokio::fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+
+    trace::instrumented_stream_async("hash_file_async", hashbitlen, async {
+        let mut buf = vec![0; options.buffer_size.unwrap_or(BUFFER_SIZE)];
+        let mut progress = Progress::new(Some(size), options);
+
+        loop {
+            options.check_cancelled()?;
+
+            let read = match file.read(&mut buf).await {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            progress.hash(&mut state, &buf[..read], options);
+
+            if let Some(wait) = options.rate_limit.and_then(|limiter| limiter.debit(read as u64)) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        progress.finish(options);
+        state.finalise(hashval)?;
+        Ok(progress.done)
+    })
+        .await
+}
+
+/// Copy all data from the reader to the writer, hashing it on the way, returning the amount of bytes copied.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or if `hashval` is shorter than the hash function's size, before reading anything,
+/// or any error returned by the reader other than `io::ErrorKind::Interrupted`, or by the writer.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// let mut result_copied = [0; 32];
+/// let mut result        = [0; 32];
+///
+/// let mut copy = vec![];
+/// assert_eq!(blake::copy_and_hash(256, &b"The lazy fox jumps over the lazy dog."[..], &mut copy, &mut result_copied).unwrap(),
+///            37);
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+///
+/// assert_eq!(&copy[..], &b"The lazy fox jumps over the lazy dog."[..]);
+/// assert_eq!(result_copied, result);
+/// # }
+/// ```
+pub fn copy_and_hash<R: Read, W: Write>(hashbitlen: i32, reader: R, writer: W, hashval: &mut [u8]) -> io::Result<u64> {
+    copy_and_hash_with_buffer(hashbitlen, reader, writer, hashval, &mut vec![0; BUFFER_SIZE])
+}
+
+/// Copy all data from the reader to the writer, hashing it on the way, as in `copy_and_hash()`,
+/// using the specified buffer, which can be reused between calls.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if the buffer is empty; refer to `copy_and_hash()` for the rest.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// let mut buffer = vec![0; 4096];
+/// let mut copies = vec![vec![], vec![]];
+/// let mut result = [0; 32];
+/// for (copy, data) in copies.iter_mut().zip(&[&b"Abolish"[..], b"the bourgeoisie"]) {
+///     blake::copy_and_hash_with_buffer(256, &data[..], copy, &mut result, &mut buffer).unwrap();
+/// }
+///
+/// assert_eq!(copies, vec![b"Abolish".to_vec(), b"the bourgeoisie".to_vec()]);
+/// assert!(blake::copy_and_hash_with_buffer(256, &b"Abolish"[..], vec![], &mut result, &mut []).is_err());
+/// # }
+/// ```
+pub fn copy_and_hash_with_buffer<R: Read, W: Write>(hashbitlen: i32, mut reader: R, mut writer: W, hashval: &mut [u8], buf: &mut [u8])
+                                                    -> io::Result<u64> {
+    check_hashval(hashbitlen, hashval)?;
+    if buf.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "buffer must not be empty"));
+    }
+    let mut state = StackState::new(hashbitlen)?;
+
+    trace::instrumented_stream("copy_and_hash", hashbitlen, || {
+        let mut done = 0u64;
+        loop {
+            let read = match reader.read(buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            writer.write_all(&buf[..read])?;
+            state.update(&buf[..read]);
+            done += read as u64;
+        }
+
+        state.finalise(hashval)?;
+        Ok(done)
+    })
+}
+
+/// Hash all data from the reader, as in `hash_reader()`, also hashing every `piece_size` bytes of it separately,
+/// returning the digests of the pieces.
+///
+/// The last piece is shorter if the data isn't a whole number of pieces, and there are no pieces if there's no data.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// if `piece_size` is `0`, or if `hashval` is shorter than the hash function's size,
+/// or any error returned by the reader other than `io::ErrorKind::Interrupted`.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// let mut result_whole = [0; 32];
+/// let mut result       = [0; 32];
+///
+/// let pieces = blake::hash_reader_pieces(256, &b"The lazy fox jumps over the lazy dog."[..], 16, &mut result_whole).unwrap();
+/// assert_eq!(pieces.len(), 3);
+///
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+/// assert_eq!(result_whole, result);
+///
+/// blake::hash(256, b"ps over the lazy", &mut result).unwrap();
+/// assert_eq!(&pieces[1][..], &result[..]);
+/// blake::hash(256, b" dog.", &mut result).unwrap();
+/// assert_eq!(&pieces[2][..], &result[..]);
+///
+/// assert!(blake::hash_reader_pieces(256, &b"The lazy fox jumps over the lazy dog."[..], 16, &mut result[..31]).is_err());
+/// # }
+/// ```
+pub fn hash_reader_pieces<R: Read>(hashbitlen: i32, mut reader: R, piece_size: u64, hashval: &mut [u8]) -> io::Result<Vec<Vec<u8>>> {
+    let size = digest_size(hashbitlen)?;
+    if piece_size == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "piece size must be positive"));
+    }
+    if hashval.len() < size {
+        return Err(BlakeError::BadLength.into());
+    }
+    let new_state = || StackState::new(hashbitlen).expect("valid hashbitlen");
+
+    let mut pieces = vec![];
+    trace::instrumented_stream("hash_reader_pieces", hashbitlen, || {
+        let mut buf = vec![0; BUFFER_SIZE];
+        let mut whole = new_state();
+        let mut piece = new_state();
+        let mut piece_done = 0u64;
+        let mut done = 0u64;
+
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            whole.update(&buf[..read]);
+            done += read as u64;
+
+            let mut data = &buf[..read];
+            while !data.is_empty() {
+                let len = cmp::min(data.len() as u64, piece_size - piece_done) as usize;
+                piece.update(&data[..len]);
+                piece_done += len as u64;
+                data = &data[len..];
+
+                if piece_done == piece_size {
+                    let mut digest = vec![0; size];
+                    mem::replace(&mut piece, new_state()).finalise(&mut digest).expect("the buffer fits the digest");
+                    pieces.push(digest);
+                    piece_done = 0;
+                }
+            }
+        }
+
+        if piece_done != 0 {
+            let mut digest = vec![0; size];
+            piece.finalise(&mut digest).expect("the buffer fits the digest");
+            pieces.push(digest);
+        }
+
+        whole.finalise(hashval)?;
+        Ok(done)
+    })?;
+    Ok(pieces)
+}
+
+
+fn readers_equal_sized<R1: Read, R2: Read + Send>(operation: &'static str, hashbitlen: i32, reader1: R1, total1: Option<u64>, reader2: R2,
+                                                   total2: Option<u64>)
+                                                   -> io::Result<bool> {
+    let size = digest_size(hashbitlen)?;
+    let lengths = [AtomicU64::new(u64::MAX), AtomicU64::new(u64::MAX)];
+    let cancel = AtomicBool::new(false);
+
+    let hash_one = |index: usize, reader: &mut dyn Read, total| -> io::Result<Vec<u8>> {
+        let other_length = &lengths[1 - index];
+        let mut progress = |done, _| if done > other_length.load(Ordering::Relaxed) {
+            cancel.store(true, Ordering::Relaxed);
+        };
+
+        let mut digest = vec![0; size];
+        let mut options = IoOptions::new().progress(&mut progress, 1).cancel(&cancel);
+        let length = hash_sized_reader(operation, hashbitlen, reader, total, &mut digest, &mut options)?;
+        lengths[index].store(length, Ordering::Relaxed);
+        Ok(digest)
+    };
+
+    let (digest1, digest2) = thread::scope(|scope| {
+        let hash_one = &hash_one;
+        let mut reader1 = reader1;
+        let mut reader2 = reader2;
+        let worker = scope.spawn(move || hash_one(1, &mut reader2, total2));
+        let digest1 = hash_one(0, &mut reader1, total1);
+        (digest1, worker.join().expect("hashing thread panicked"))
+    });
+
+    let cancelled = |e: &io::Error| BlakeError::from_io_error(e) == Some(BlakeError::Cancelled);
+    match (digest1, digest2) {
+        (Err(e), _) | (_, Err(e)) if !cancelled(&e) => Err(e),
+        (Ok(digest1), Ok(digest2)) => Ok(lengths[0].load(Ordering::Relaxed) == lengths[1].load(Ordering::Relaxed) && digest1 == digest2),
+        _ => Ok(false),
+    }
+}
+
+fn hash_sized_reader<R: Read>(operation: &'static str, hashbitlen: i32, mut reader: R, total: Option<u64>, hashval: &mut [u8],
+                              options: &mut IoOptions<'_>)
+                              -> io::Result<u64> {
+    check_hashval(hashbitlen, hashval)?;
+    let mut state = StackState::new(hashbitlen)?;
+
+    trace::instrumented_stream(operation, hashbitlen, || {
+        let mut buf = vec![0; options.buffer_size.unwrap_or(BUFFER_SIZE)];
+        let mut progress = Progress::new(total, options);
+
+        loop {
+            options.check_cancelled()?;
+
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            progress.hash(&mut state, &buf[..read], options);
+
+            if let Some(limiter) = options.rate_limit {
+                limiter.consume(read as u64);
+            }
+        }
+
+        progress.finish(options);
+        state.finalise(hashval)?;
+        Ok(progress.done)
+    })
+}
+
+/// Check that `hashval` fits the digest of the hash function of the given length.
+fn check_hashval(hashbitlen: i32, hashval: &[u8]) -> io::Result<()> {
+    if hashval.len() < digest_size(hashbitlen)? {
+        return Err(BlakeError::BadLength.into());
+    }
+    Ok(())
+}
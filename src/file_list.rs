@@ -0,0 +1,93 @@
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+
+
+/// An iterator over the paths listed in a reader, one per line or NUL-terminated,
+/// like those accepted by `--files-from` in `tar` and `rsync`, or output by `find -print` and `find -print0`.
+///
+/// Paths are read one at a time, so lists of any length can be processed in constant memory.
+/// Empty entries are skipped. On Unix, paths are arbitrary bytes; elsewhere, they must be valid UTF-8.
+///
+/// # Examples
+///
+/// Hashing the files from `find . -type f -print0`.
+///
+/// ```
+/// # use blake::FileList;
+/// # use std::path::PathBuf;
+/// # #[cfg(feature = "width-256")] {
+/// let listing = &b"./LICENSE\0./README.md\0"[..];
+///
+/// let mut result = [0; 32];
+/// for path in FileList::nul_terminated(listing) {
+///     let path = path.unwrap();
+///     blake::hash_file(256, &path, &mut result).unwrap();
+/// }
+///
+/// let paths: Vec<_> = FileList::new(&b"LICENSE\n\nREADME.md"[..]).map(Result::unwrap).collect();
+/// assert_eq!(paths, vec![PathBuf::from("LICENSE"), PathBuf::from("README.md")]);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FileList<R> {
+    reader: R,
+    terminator: u8,
+}
+
+
+impl<R: BufRead> FileList<R> {
+    /// List the paths on the lines of the reader, as in `--files-from`.
+    ///
+    /// Only `'\n'` ends a line; a `'\r'` before it is part of the path.
+    pub fn new(reader: R) -> FileList<R> {
+        FileList {
+            reader,
+            terminator: b'\n',
+        }
+    }
+
+    /// List the NUL-terminated paths in the reader, as in `--files-from` with `-0` or `--null`;
+    /// the only way to list paths containing newlines.
+    pub fn nul_terminated(reader: R) -> FileList<R> {
+        FileList {
+            reader,
+            terminator: b'\0',
+        }
+    }
+}
+
+/// Yields `Err` with kind `io::ErrorKind::InvalidData` for a path that isn't valid UTF-8 on a non-Unix platform,
+/// or any error encountered reading.
+impl<R: BufRead> Iterator for FileList<R> {
+    type Item = io::Result<PathBuf>;
+
+    fn next(&mut self) -> Option<io::Result<PathBuf>> {
+        loop {
+            let mut entry = vec![];
+            match self.reader.read_until(self.terminator, &mut entry) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            if entry.last() == Some(&self.terminator) {
+                entry.pop();
+            }
+            if !entry.is_empty() {
+                return Some(path_from_bytes(entry));
+            }
+        }
+    }
+}
+
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> io::Result<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(std::ffi::OsString::from_vec(bytes).into())
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> io::Result<PathBuf> {
+    String::from_utf8(bytes).map(PathBuf::from).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
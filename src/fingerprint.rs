@@ -0,0 +1,116 @@
+//! Renderings of digests for humans to compare, like key fingerprints read out over the phone.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::fingerprint;
+//! let digest = [0xF2, 0xE5, 0xA9, 0xD0, 0x93, 0xD8, 0xAA, 0x23, 0x4E, 0x6C, 0x54, 0x50, 0x61, 0xE8, 0x17, 0xBE];
+//!
+//! assert_eq!(fingerprint::colon_hex(&digest), "f2:e5:a9:d0:93:d8:aa:23:4e:6c:54:50:61:e8:17:be");
+//! assert_eq!(fingerprint::grouped_base32(&digest), "6LS2 TUET 3CVC GTTM KRIG D2AX XY");
+//! ```
+
+
+use super::{BlakeError, Result};
+use super::encoding::{base32_encode, hex_decode};
+
+
+const RANDOMART_WIDTH: usize = 17;
+const RANDOMART_HEIGHT: usize = 9;
+
+/// Symbols for fields visited increasingly many times, then the start and end positions, as in OpenSSH.
+const RANDOMART_SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^SE";
+
+
+/// Format the digest as lower-case hexadecimal, with the bytes separated by colons.
+pub fn colon_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(":")
+}
+
+/// Parse a digest formatted as with `colon_hex()`, normalising it first: hexadecimal digits may be of either case,
+/// and may be separated by any amount of colons, dashes, and whitespace, or not at all.
+///
+/// Returns `Err(BlakeError::BadEncoding)` if anything else is in the string,
+/// or if it doesn't contain an even amount of hexadecimal digits.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::fingerprint;
+/// assert_eq!(fingerprint::parse_colon_hex("f2:E5:a9"), Ok(vec![0xF2, 0xE5, 0xA9]));
+/// assert_eq!(fingerprint::parse_colon_hex(" F2E5 - A9 "), Ok(vec![0xF2, 0xE5, 0xA9]));
+/// assert_eq!(fingerprint::parse_colon_hex("f2:e5:a"), Err(blake::BlakeError::BadEncoding));
+/// ```
+pub fn parse_colon_hex(fingerprint: &str) -> Result<Vec<u8>> {
+    let digits: String = fingerprint.chars().filter(|&c| c != ':' && c != '-' && !c.is_whitespace()).collect();
+    hex_decode(&digits).ok_or(BlakeError::BadEncoding)
+}
+
+/// Format the digest as unpadded upper-case RFC 4648 base32, in space-separated groups of four characters.
+pub fn grouped_base32(digest: &[u8]) -> String {
+    base32_encode(digest).as_bytes().chunks(4).map(|group| String::from_utf8_lossy(group)).collect::<Vec<_>>().join(" ")
+}
+
+/// Draw the digest as OpenSSH's "drunken bishop" randomart, with the specified title at the top
+/// (like the key type, truncated to fit), and the hash function's name at the bottom.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::fingerprint;
+/// # #[cfg(feature = "width-256")] {
+/// let mut digest = [0; 32];
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut digest).unwrap();
+///
+/// let art = fingerprint::randomart(&digest, "ED25519 256");
+/// assert_eq!(art.lines().count(), 11);
+/// assert_eq!(art.lines().next(), Some("+--[ED25519 256]--+"));
+/// assert_eq!(art.lines().last(), Some("+---[BLAKE-256]---+"));
+/// assert!(art.lines().all(|line| line.chars().count() == 19));
+/// # }
+/// ```
+pub fn randomart(digest: &[u8], title: &str) -> String {
+    let mut field = [[0usize; RANDOMART_WIDTH]; RANDOMART_HEIGHT];
+    let (mut x, mut y) = (RANDOMART_WIDTH / 2, RANDOMART_HEIGHT / 2);
+    let visited_max = RANDOMART_SYMBOLS.len() - 3;
+
+    for byte in digest {
+        for step in 0..4 {
+            let bits = byte >> (2 * step);
+            x = if bits & 0x1 != 0 { (x + 1).min(RANDOMART_WIDTH - 1) } else { x.saturating_sub(1) };
+            y = if bits & 0x2 != 0 { (y + 1).min(RANDOMART_HEIGHT - 1) } else { y.saturating_sub(1) };
+
+            if field[y][x] < visited_max {
+                field[y][x] += 1;
+            }
+        }
+    }
+    field[RANDOMART_HEIGHT / 2][RANDOMART_WIDTH / 2] = RANDOMART_SYMBOLS.len() - 2;
+    field[y][x] = RANDOMART_SYMBOLS.len() - 1;
+
+    let mut ret = randomart_border(title);
+    ret.push('\n');
+    for row in &field {
+        ret.push('|');
+        ret.extend(row.iter().map(|&visits| RANDOMART_SYMBOLS[visits] as char));
+        ret.push_str("|\n");
+    }
+    ret.push_str(&randomart_border(&format!("BLAKE-{}", digest.len() * 8)));
+    ret
+}
+
+
+/// A border with the label in brackets, centred.
+fn randomart_border(label: &str) -> String {
+    let label: String = format!("[{}]", label).chars().take(RANDOMART_WIDTH).collect();
+    let label_len = label.chars().count();
+    let left = (RANDOMART_WIDTH - label_len) / 2;
+
+    let mut ret = String::with_capacity(RANDOMART_WIDTH + 2);
+    ret.push('+');
+    ret.extend((0..left).map(|_| '-'));
+    ret.push_str(&label);
+    ret.extend((0..RANDOMART_WIDTH - left - label_len).map(|_| '-'));
+    ret.push('+');
+    ret
+}
@@ -0,0 +1,189 @@
+//! [RFC 9380](https://www.rfc-editor.org/rfc/rfc9380) `expand_message_xmd` and `hash_to_field`, instantiated with BLAKE.
+//!
+//! `expand_message_xmd` stretches a message into a uniformly random byte string of up to 255 digests' length,
+//! bound to a domain-separation tag, as in section 5.3.1; `hash_to_field` reduces such a string into elements of
+//! a prime field, as in section 5.2, with the extension degree being 1.
+//!
+//! The RFC doesn't define any suites using BLAKE, so there are no external test vectors to check against;
+//! use a DST naming this crate's construction, like `"MyProtocol-V01-BLAKE256_XMD"`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::h2c;
+//! # #[cfg(feature = "width-256")] {
+//! let mut uniform = [0; 100];
+//! h2c::expand_message_xmd(256, b"The lazy fox jumps over the lazy dog.", b"MyProtocol-V01-BLAKE256_XMD", &mut uniform)
+//!     .unwrap();
+//!
+//! // The prime 2^61 - 1
+//! let modulus = [0x1F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+//! let elements = h2c::hash_to_field(256, b"The lazy fox jumps over the lazy dog.", b"MyProtocol-V01-BLAKE256_XMD",
+//!                                   &modulus, 128, 2).unwrap();
+//! assert_eq!(elements.len(), 2);
+//! assert!(elements.iter().all(|element| element.len() == 8 && element[..] < modulus[..]));
+//! # }
+//! ```
+
+
+use super::{Blake, BlakeError, Result, digest_size};
+
+
+/// The prefix of the tag, hashed with it, used in place of tags longer than 255 bytes, as in section 5.3.3.
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+
+/// Fill `out` with `expand_message_xmd(msg, DST, out.len())`, with H being the BLAKE hash function of the specified
+/// length.
+///
+/// Tags longer than 255 bytes are replaced with the hash of `"H2C-OVERSIZE-DST-"` and the tag, as in section 5.3.3.
+///
+/// Returns:
+///
+///   * `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+///   * `Err(BlakeError::BadLength)` if `out` is empty, longer than 65535 bytes, or longer than 255 digests, or
+///   * `Ok(())` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::{h2c, BlakeError};
+/// # #[cfg(all(feature = "width-224", feature = "width-512"))] {
+/// let mut short = [0; 32];
+/// let mut long  = [0; 64];
+/// h2c::expand_message_xmd(512, b"message", b"MyProtocol-V01-BLAKE512_XMD", &mut short).unwrap();
+/// h2c::expand_message_xmd(512, b"message", b"MyProtocol-V01-BLAKE512_XMD", &mut long).unwrap();
+/// // The length is bound into the output
+/// assert!(short[..] != long[..32]);
+///
+/// assert_eq!(h2c::expand_message_xmd(224, b"message", b"MyProtocol", &mut [0; 28 * 256]),
+///            Err(BlakeError::BadLength));
+/// # }
+/// ```
+pub fn expand_message_xmd(hashbitlen: i32, msg: &[u8], dst: &[u8], out: &mut [u8]) -> Result<()> {
+    let b_in_bytes = digest_size(hashbitlen)?;
+    let s_in_bytes = if hashbitlen <= 256 { 64 } else { 128 };
+    let ell = out.len().div_ceil(b_in_bytes);
+    if out.is_empty() || out.len() > 65535 || ell > 255 {
+        return Err(BlakeError::BadLength);
+    }
+
+    let mut oversize_dst = vec![0; b_in_bytes];
+    let dst = if dst.len() > 255 {
+        let mut state = Blake::new(hashbitlen)?;
+        state.update(OVERSIZE_DST_PREFIX);
+        state.update(dst);
+        state.finalise(&mut oversize_dst);
+        &oversize_dst[..]
+    } else {
+        dst
+    };
+    let dst_len = [dst.len() as u8];
+
+    let mut b_0 = vec![0; b_in_bytes];
+    let mut state = Blake::new(hashbitlen)?;
+    state.update(vec![0; s_in_bytes]);
+    state.update(msg);
+    state.update((out.len() as u16).to_be_bytes());
+    state.update([0]);
+    state.update(dst);
+    state.update(dst_len);
+    state.finalise(&mut b_0);
+
+    let mut b_i = vec![0; b_in_bytes];
+    for (i, chunk) in out.chunks_mut(b_in_bytes).enumerate() {
+        // b_1 = H(b_0 ‖ 1 ‖ DST′), b_i = H((b_0 ⊕ b_(i-1)) ‖ i ‖ DST′)
+        for (byte, b_0_byte) in b_i.iter_mut().zip(b_0.iter()) {
+            *byte ^= *b_0_byte;
+        }
+
+        let mut state = Blake::new(hashbitlen)?;
+        state.update(&b_i);
+        state.update([i as u8 + 1]);
+        state.update(dst);
+        state.update(dst_len);
+        state.finalise(&mut b_i);
+
+        chunk.copy_from_slice(&b_i[..chunk.len()]);
+    }
+
+    Ok(())
+}
+
+/// Hash the message into `count` elements of the prime field with the specified big-endian modulus, as in section 5.2,
+/// with `expand_message_xmd()` as `expand_message`, and `security_bits` being the target security level *k*.
+///
+/// Each element is returned big-endian, as long as the modulus.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` or `Err(BlakeError::BadLength)` if `expand_message_xmd()` would fail
+/// for the length required.
+///
+/// # Panics
+///
+/// If the modulus is zero.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::h2c;
+/// # #[cfg(feature = "width-256")] {
+/// let elements = h2c::hash_to_field(256, b"message", b"MyProtocol-V01-BLAKE256_XMD", &[0x00, 0x07], 128, 100)
+///     .unwrap();
+/// assert!(elements.iter().all(|element| element[0] == 0 && element[1] < 7));
+/// assert!(elements.iter().any(|element| element[1] != elements[0][1]));
+/// # }
+/// ```
+pub fn hash_to_field(hashbitlen: i32, msg: &[u8], dst: &[u8], modulus: &[u8], security_bits: usize, count: usize)
+                     -> Result<Vec<Vec<u8>>> {
+    let modulus_bits = bit_length(modulus);
+    assert!(modulus_bits != 0, "hash_to_field() modulus is zero");
+    if count == 0 {
+        return Ok(vec![]);
+    }
+
+    let l = (modulus_bits + security_bits).div_ceil(8);
+    let mut uniform = vec![0; l * count];
+    expand_message_xmd(hashbitlen, msg, dst, &mut uniform)?;
+
+    Ok(uniform.chunks(l).map(|tv| reduce(tv, modulus)).collect())
+}
+
+
+/// The amount of significant bits in the big-endian number.
+fn bit_length(number: &[u8]) -> usize {
+    match number.iter().position(|&byte| byte != 0) {
+        Some(idx) => (number.len() - idx) * 8 - number[idx].leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// The big-endian number modulo the big-endian modulus, as long as the modulus, by binary long division.
+fn reduce(number: &[u8], modulus: &[u8]) -> Vec<u8> {
+    // One extra byte for the shifted-out top bit
+    let mut remainder = vec![0u8; modulus.len() + 1];
+    let mut modulus_wide = vec![0u8; modulus.len() + 1];
+    modulus_wide[1..].copy_from_slice(modulus);
+
+    for byte in number {
+        for bit in (0..8).rev() {
+            let mut carry = (byte >> bit) & 1;
+            for digit in remainder.iter_mut().rev() {
+                let shifted_out = *digit >> 7;
+                *digit = *digit << 1 | carry;
+                carry = shifted_out;
+            }
+
+            if remainder >= modulus_wide {
+                let mut borrow = 0i16;
+                for (digit, m) in remainder.iter_mut().zip(modulus_wide.iter()).rev() {
+                    let difference = *digit as i16 - *m as i16 - borrow;
+                    borrow = (difference < 0) as i16;
+                    *digit = difference.rem_euclid(256) as u8;
+                }
+            }
+        }
+    }
+
+    remainder.remove(0);
+    remainder
+}
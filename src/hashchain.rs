@@ -0,0 +1,213 @@
+//! Hash chains, for S/KEY-style one-time authenticators.
+//!
+//! A chain of length *n* starts at a secret seed *h⁰*, each element being the BLAKE-256 of the previous one,
+//! and ends at the anchor *hⁿ*, which is published. The elements are then revealed in reverse order, from *hⁿ⁻¹*
+//! down to the seed, each one proving knowledge of the seed since it hashes to the last one revealed.
+//!
+//! Revealing in reverse order requires either keeping the whole chain, or recomputing it from the start every time.
+//! `HashChain` instead keeps checkpoints every *k* elements (√*n* by default), and recomputes one *k*-element
+//! segment at a time, so it keeps O(√*n*) digests, and hashes each element about twice.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::hashchain::{HashChain, ChainVerifier};
+//! # use blake::b256::Digest;
+//! let mut chain = HashChain::new(Digest::from_bytes([0x42; 32]), 1000);
+//! let mut verifier = ChainVerifier::new(chain.anchor());
+//!
+//! let first = chain.next().unwrap();
+//! assert!(verifier.verify(&first, 1));
+//! assert!(!verifier.verify(&first, 1));  // Already used
+//!
+//! chain.next().unwrap();  // Lost
+//! assert!(verifier.verify(&chain.next().unwrap(), 10));
+//!
+//! assert_eq!(chain.remaining(), 997);
+//! assert_eq!(chain.last(), Some(Digest::from_bytes([0x42; 32])));
+//! ```
+
+
+use super::b256::{self, Digest};
+
+
+/// A hash chain, yielding its elements in reverse order, as described in the module-level documentation.
+#[derive(Debug, Clone)]
+pub struct HashChain {
+    anchor: Digest,
+    interval: u64,
+    checkpoints: Vec<Digest>,
+    segment: Vec<Digest>,
+    remaining: u64,
+}
+
+/// A verifier of elements revealed from a hash chain, remembering the last one revealed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainVerifier {
+    anchor: Digest,
+}
+
+
+impl HashChain {
+    /// Compute the chain of the specified length from the specified seed, with checkpoints every √`length` elements.
+    ///
+    /// A chain of length `0` yields no elements, and its anchor is the seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::hashchain::HashChain;
+    /// # use blake::b256::Digest;
+    /// let seed = Digest::from_bytes([0x42; 32]);
+    /// assert_eq!(HashChain::new(seed, 0).count(), 0);
+    /// assert_eq!(HashChain::new(seed, 0).anchor(), seed);
+    /// ```
+    #[must_use]
+    pub fn new(seed: Digest, length: u64) -> HashChain {
+        let mut interval = ((length as f64).sqrt() as u64).max(1);
+        while interval.saturating_mul(interval) < length {
+            interval += 1;
+        }
+        HashChain::with_interval(seed, length, interval)
+    }
+
+    /// Compute the chain of the specified length from the specified seed, with checkpoints every `interval` elements.
+    ///
+    /// Larger intervals use less memory for the checkpoints, but more for the segment being revealed.
+    ///
+    /// # Panics
+    ///
+    /// If `interval` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::hashchain::HashChain;
+    /// # use blake::b256::Digest;
+    /// let seed = Digest::from_bytes([0x42; 32]);
+    /// let chain: Vec<_> = HashChain::with_interval(seed, 20, 3).collect();
+    /// assert_eq!(chain, HashChain::with_interval(seed, 20, 20).collect::<Vec<_>>());
+    ///
+    /// assert_eq!(chain.len(), 20);
+    /// assert_eq!(chain[18], blake::b256::hash(seed.as_bytes()));
+    /// assert_eq!(chain[19], seed);
+    /// ```
+    #[must_use]
+    pub fn with_interval(seed: Digest, length: u64, interval: u64) -> HashChain {
+        assert!(interval != 0, "HashChain::with_interval(): interval must be positive");
+
+        let mut checkpoints = vec![];
+        let mut value = seed;
+        for i in 0..length {
+            if i % interval == 0 {
+                checkpoints.push(value);
+            }
+            value = step(&value);
+        }
+
+        HashChain {
+            anchor: value,
+            interval,
+            checkpoints,
+            segment: vec![],
+            remaining: length,
+        }
+    }
+
+    /// Get the end of the chain, to be published.
+    pub fn anchor(&self) -> Digest {
+        self.anchor
+    }
+
+    /// Get the number of elements not yet revealed.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+impl Iterator for HashChain {
+    type Item = Digest;
+
+    fn next(&mut self) -> Option<Digest> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        if self.segment.is_empty() {
+            let checkpoint = (self.remaining / self.interval) as usize;
+            let mut value = self.checkpoints[checkpoint];
+            self.checkpoints.truncate(checkpoint);
+
+            for _ in (checkpoint as u64 * self.interval)..self.remaining {
+                let next = step(&value);
+                self.segment.push(value);
+                value = next;
+            }
+            return Some(value);
+        }
+
+        self.segment.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+
+impl ChainVerifier {
+    /// Create a verifier expecting elements of the chain with the specified anchor.
+    #[must_use]
+    pub fn new(anchor: Digest) -> ChainVerifier {
+        ChainVerifier { anchor }
+    }
+
+    /// Get the last element accepted, or the anchor if none were.
+    pub fn anchor(&self) -> Digest {
+        self.anchor
+    }
+
+    /// Check whether the specified value precedes the last element accepted by at most `max_steps` steps,
+    /// as with `verify()`, and, if so, accept it.
+    pub fn verify(&mut self, value: &Digest, max_steps: u64) -> bool {
+        if verify(&self.anchor, value, max_steps).is_some() {
+            self.anchor = *value;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+
+/// Check whether hashing the value between 1 and `max_steps` times yields the anchor, returning the number of times.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::hashchain::{self, HashChain};
+/// # use blake::b256::Digest;
+/// let mut chain = HashChain::new(Digest::from_bytes([0x42; 32]), 100);
+/// let anchor = chain.anchor();
+///
+/// let value = chain.nth(4).unwrap();
+/// assert_eq!(hashchain::verify(&anchor, &value, 10), Some(5));
+/// assert_eq!(hashchain::verify(&anchor, &value, 4), None);
+/// assert_eq!(hashchain::verify(&anchor, &anchor, 10), None);
+/// ```
+pub fn verify(anchor: &Digest, value: &Digest, max_steps: u64) -> Option<u64> {
+    let mut value = *value;
+    for steps in 1..max_steps.saturating_add(1) {
+        value = step(&value);
+        if value == *anchor {
+            return Some(steps);
+        }
+    }
+    None
+}
+
+
+fn step(value: &Digest) -> Digest {
+    b256::hash(value.as_bytes())
+}
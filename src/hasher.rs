@@ -0,0 +1,188 @@
+//! `std::hash::Hasher` and `std::hash::BuildHasher` implementations based on BLAKE-256.
+//!
+//! `BlakeHasher::new()` is unsalted, and so produces the same values on every platform and in every process,
+//! which makes it suitable for persistent hashing of `#[derive(Hash)]` types;
+//! integers are fed in little-endian order regardless of the platform, and `usize`/`isize` are widened to 64 bits.
+//! Bear in mind that the `Hash` implementations of standard types aren't guaranteed to be stable across Rust
+//! versions.
+//!
+//! `RandomBlakeState` salts every hasher with a salt randomly generated on construction,
+//! making it suitable for `HashMap`s exposed to untrusted keys.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::hasher::{BlakeHasher, RandomBlakeState};
+//! # use std::hash::{Hash, Hasher};
+//! # use std::collections::HashMap;
+//! #[derive(Hash)]
+//! struct Point {
+//!     x: u32,
+//!     y: u32,
+//! }
+//!
+//! let mut hasher = BlakeHasher::new();
+//! Point { x: 1, y: 2 }.hash(&mut hasher);
+//! assert_eq!(hasher.finish(), 0xE5AB65F77336FD56);
+//!
+//! let mut map = HashMap::with_hasher(RandomBlakeState::new());
+//! map.insert("Abolish", "the bourgeoisie");
+//! assert_eq!(map.get("Abolish"), Some(&"the bourgeoisie"));
+//! ```
+
+
+use std::hash::{BuildHasher, Hasher};
+use super::stack::StackState;
+
+
+/// A `Hasher` feeding all data into a BLAKE-256 state, returning the first 8 bytes of the digest as a little-endian
+/// integer from `finish()`.
+#[derive(Clone)]
+pub struct BlakeHasher {
+    state: StackState,
+}
+
+/// A `BuildHasher` creating `BlakeHasher`s salted with a salt randomly generated when it's constructed.
+#[derive(Clone, Copy)]
+pub struct RandomBlakeState {
+    salt: [u8; 16],
+}
+
+
+impl BlakeHasher {
+    /// Create an unsalted hasher.
+    #[must_use]
+    pub fn new() -> BlakeHasher {
+        BlakeHasher { state: StackState::new(256).expect("256 is a valid hashbitlen") }
+    }
+
+    /// Create a hasher salted with the specified salt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::hasher::BlakeHasher;
+    /// # use std::hash::Hasher;
+    /// let mut unsalted = BlakeHasher::new();
+    /// let mut salted   = BlakeHasher::with_salt(b"Proletarians of ");
+    ///
+    /// unsalted.write(b"the world, unite!");
+    /// salted  .write(b"the world, unite!");
+    ///
+    /// assert!(unsalted.finish() != salted.finish());
+    /// ```
+    #[must_use]
+    pub fn with_salt(salt: &[u8; 16]) -> BlakeHasher {
+        let mut ret = BlakeHasher::new();
+        ret.state.add_salt(salt).expect("Salting a fresh state");
+        ret
+    }
+}
+
+impl Default for BlakeHasher {
+    fn default() -> BlakeHasher {
+        BlakeHasher::new()
+    }
+}
+
+impl Hasher for BlakeHasher {
+    fn finish(&self) -> u64 {
+        let mut result = [0; 32];
+        self.state.clone().finalise(&mut result).expect("the buffer fits the digest");
+
+        let mut first = [0; 8];
+        first.copy_from_slice(&result[..8]);
+        u64::from_le_bytes(first)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.state.update(bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64)
+    }
+}
+
+
+impl RandomBlakeState {
+    /// Generate a random salt from the operating system's random number generator.
+    ///
+    /// Panics if the random number generator isn't available.
+    #[must_use]
+    pub fn new() -> RandomBlakeState {
+        let mut salt = [0; 16];
+        getrandom::fill(&mut salt).expect("Failed to get randomness for salt");
+        RandomBlakeState { salt }
+    }
+
+    /// Use the specified salt, for reproducibility.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::hasher::{BlakeHasher, RandomBlakeState};
+    /// # use std::hash::{BuildHasher, Hasher};
+    /// let mut built  = RandomBlakeState::with_salt(*b"Proletarians of ").build_hasher();
+    /// let mut direct = BlakeHasher::with_salt(b"Proletarians of ");
+    ///
+    /// built .write(b"the world, unite!");
+    /// direct.write(b"the world, unite!");
+    ///
+    /// assert_eq!(built.finish(), direct.finish());
+    /// ```
+    #[must_use]
+    pub fn with_salt(salt: [u8; 16]) -> RandomBlakeState {
+        RandomBlakeState { salt }
+    }
+}
+
+impl Default for RandomBlakeState {
+    fn default() -> RandomBlakeState {
+        RandomBlakeState::new()
+    }
+}
+
+impl BuildHasher for RandomBlakeState {
+    type Hasher = BlakeHasher;
+
+    fn build_hasher(&self) -> BlakeHasher {
+        BlakeHasher::with_salt(&self.salt)
+    }
+}
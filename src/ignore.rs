@@ -0,0 +1,202 @@
+//! gitignore-style pattern matching, for excluding paths from directory walks.
+
+
+/// An ordered list of patterns, the last matching one of which decides whether a path is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+/// A single parsed pattern, applying to paths under `base`.
+#[derive(Debug, Clone)]
+struct Rule {
+    base: String,
+    glob: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+
+impl IgnoreRules {
+    /// Add the pattern, in gitignore syntax, applying to paths under the specified `/`-terminated prefix
+    /// (or the empty string for the whole tree).
+    ///
+    /// Blank lines and comments are ignored.
+    pub fn add(&mut self, base: &str, pattern: &str) {
+        let mut pattern = pattern.trim_end_matches(['\r', '\n']).trim_end_matches(' ');
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return;
+        }
+
+        let negated = pattern.starts_with('!');
+        if negated {
+            pattern = &pattern[1..];
+        }
+        let dir_only = pattern.ends_with('/');
+        if dir_only {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return;
+        }
+
+        self.rules.push(Rule {
+            base: base.to_string(),
+            glob: pattern.to_string(),
+            negated,
+            dir_only,
+            anchored,
+        });
+    }
+
+    /// Add all patterns from the contents of an ignore file, one per line, as in `add()`.
+    pub fn add_file(&mut self, base: &str, contents: &str) {
+        for line in contents.lines() {
+            self.add(base, line);
+        }
+    }
+
+    /// Get the amount of rules, to `truncate()` back to after leaving a directory.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Drop the rules added after there were the specified amount.
+    pub fn truncate(&mut self, len: usize) {
+        self.rules.truncate(len);
+    }
+
+    /// Check whether the `/`-separated path relative to the root of the walk is ignored.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| r.matches(path, is_dir))
+            .map(|r| !r.negated)
+            .unwrap_or(false)
+    }
+}
+
+impl Rule {
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if !path.starts_with(&self.base[..]) {
+            return false;
+        }
+
+        let path = &path[self.base.len()..];
+        if self.anchored {
+            glob(self.glob.as_bytes(), path.as_bytes())
+        } else {
+            glob(self.glob.as_bytes(), path.rsplit('/').next().unwrap_or(path).as_bytes())
+        }
+    }
+}
+
+
+/// Match the text against the glob, where `*` and `?` don't match `/`, and `**` as a whole path component does.
+///
+/// Components, then the characters within them, are matched with the iterative wildcard match, backtracking only to
+/// the last wildcard, so hostile patterns can't take exponential time.
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    let mut pattern: Vec<&[u8]> = pattern.split(|&c| c == b'/').collect();
+    // A trailing `**` matches everything inside, but not the directory itself
+    if pattern.last() == Some(&&b"**"[..]) {
+        pattern.push(b"*");
+    }
+    let text: Vec<&[u8]> = text.split(|&c| c == b'/').collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if pattern.get(p) == Some(&&b"**"[..]) {
+            p += 1;
+            backtrack = Some((p, t));
+        } else if p < pattern.len() && component(pattern[p], text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `**` match one more component
+            backtrack = Some((star_p, star_t + 1));
+            p = star_p;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b"**")
+}
+
+/// Match a single path component against a component of the glob, as in `glob()`.
+fn component(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            backtrack = Some((p, t));
+        } else if let Some(len) = single(&pattern[p..], text[t]) {
+            p += len;
+            t += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` match one more character
+            backtrack = Some((star_p, star_t + 1));
+            p = star_p;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// If the glob starts with something other than `*` matching the character, get its length.
+fn single(pattern: &[u8], c: u8) -> Option<usize> {
+    let len = match *pattern.first()? {
+        b'?' => 1,
+        b'[' => {
+            match class(&pattern[1..]) {
+                Some((matcher, len)) if matcher(c) => 1 + len,
+                Some(_) => return None,
+                None if c == b'[' => 1,
+                None => return None,
+            }
+        }
+        b'\\' if pattern.len() > 1 && pattern[1] == c => 2,
+        b'\\' if pattern.len() > 1 => return None,
+        literal if literal == c => 1,
+        _ => return None,
+    };
+    Some(len)
+}
+
+/// Parse the character class after a `[`, returning a matcher and the length of the class including the closing `]`,
+/// or `None` if it's unterminated.
+fn class(pattern: &[u8]) -> Option<(impl Fn(u8) -> bool + '_, usize)> {
+    let negated = pattern.first() == Some(&b'!') || pattern.first() == Some(&b'^');
+    let start = negated as usize;
+    // A `]` right at the start is literal
+    let end = start + 1 + pattern.get(start + 1..)?.iter().position(|&c| c == b']')?;
+    let set = &pattern[start..end];
+
+    Some((move |c: u8| {
+              let mut i = 0;
+              let mut found = false;
+              while i < set.len() {
+                  if i + 2 < set.len() && set[i + 1] == b'-' {
+                      found |= set[i] <= c && c <= set[i + 2];
+                      i += 3;
+                  } else {
+                      found |= set[i] == c;
+                      i += 1;
+                  }
+              }
+              found != negated
+          },
+          end + 1))
+}
@@ -0,0 +1,152 @@
+//! A keyed PRF and keystream built directly on the BLAKE-256 compression function.
+//!
+//! **This is not a standard construction**, and has had no cryptanalysis beyond that of BLAKE itself;
+//! it's meant for research, and for generating masks and padding, not for encrypting anything that matters.
+//!
+//! BLAKE-256's compression function mixes its 16-word state with the ChaCha quarter round, over 14 rounds.
+//! Block *i* of the keystream is the compression function's output for:
+//!
+//!   * the chaining value being BLAKE-256's initial value,
+//!   * the message block being the 32-byte key followed by 32 zero bytes,
+//!   * the salt being the 16-byte nonce,
+//!   * the counter being *i*,
+//!
+//! with the words serialised as big-endian, as in BLAKE-256. Each block is 32 bytes long.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::keystream::Keystream;
+//! let key   = [0x42; 32];
+//! let nonce = [0x13; 16];
+//!
+//! let mut data = *b"The lazy fox jumps over the lazy dog.";
+//! Keystream::new(&key, &nonce).apply(&mut data);
+//! assert!(&data[..] != &b"The lazy fox jumps over the lazy dog."[..]);
+//!
+//! Keystream::new(&key, &nonce).apply(&mut data);
+//! assert_eq!(&data[..], &b"The lazy fox jumps over the lazy dog."[..]);
+//! ```
+
+
+use super::portable::{IV256, compress32, be_u32};
+use super::native;
+
+
+/// The size of a keystream block.
+pub const BLOCK_SIZE: usize = 32;
+
+
+/// A keystream, as described in the module-level documentation.
+#[derive(Clone)]
+pub struct Keystream {
+    key: [u8; 32],
+    nonce: [u8; 16],
+    /// The index of the next block, or `None` past the last one
+    counter: Option<u64>,
+    block: [u8; BLOCK_SIZE],
+    position: usize,
+}
+
+
+impl Keystream {
+    /// Start the keystream for the specified key and nonce at block 0.
+    #[must_use]
+    pub fn new(key: &[u8; 32], nonce: &[u8; 16]) -> Keystream {
+        Keystream::with_counter(key, nonce, 0)
+    }
+
+    /// Start the keystream for the specified key and nonce at the specified block.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::keystream::{self, Keystream};
+    /// let mut stream = [0; 64];
+    /// Keystream::new(&[0x42; 32], &[0x13; 16]).fill(&mut stream);
+    ///
+    /// let mut second = [0; 32];
+    /// Keystream::with_counter(&[0x42; 32], &[0x13; 16], 1).fill(&mut second);
+    /// assert_eq!(&stream[32..], &second[..]);
+    /// assert_eq!(second, keystream::prf(&[0x42; 32], &[0x13; 16], 1));
+    ///
+    /// let mut last = [0; 32];
+    /// Keystream::with_counter(&[0x42; 32], &[0x13; 16], u64::MAX).fill(&mut last);
+    /// assert_eq!(last, keystream::prf(&[0x42; 32], &[0x13; 16], u64::MAX));
+    /// ```
+    #[must_use]
+    pub fn with_counter(key: &[u8; 32], nonce: &[u8; 16], counter: u64) -> Keystream {
+        Keystream {
+            key: *key,
+            nonce: *nonce,
+            counter: Some(counter),
+            block: [0; BLOCK_SIZE],
+            position: BLOCK_SIZE,
+        }
+    }
+
+    /// Fill the buffer with the next bytes of the keystream.
+    ///
+    /// # Panics
+    ///
+    /// If a block past block `u64::MAX` is needed.
+    pub fn fill(&mut self, out: &mut [u8]) {
+        for byte in out {
+            *byte = self.next_byte();
+        }
+    }
+
+    /// XOR the data with the next bytes of the keystream.
+    ///
+    /// # Panics
+    ///
+    /// If a block past block `u64::MAX` is needed.
+    pub fn apply(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte ^= self.next_byte();
+        }
+    }
+
+
+    fn next_byte(&mut self) -> u8 {
+        if self.position == BLOCK_SIZE {
+            let counter = self.counter.expect("Keystream ran past its last block");
+            self.block = prf(&self.key, &self.nonce, counter);
+            self.counter = counter.checked_add(1);
+            self.position = 0;
+        }
+
+        self.position += 1;
+        self.block[self.position - 1]
+    }
+}
+
+/// With the `zeroize` feature, the key and the current block are zeroed when dropped.
+impl Drop for Keystream {
+    fn drop(&mut self) {
+        if cfg!(feature = "zeroize") {
+            native::zero_bytes(&mut self.key);
+            native::zero_bytes(&mut self.block);
+        }
+    }
+}
+
+
+/// Compute the keystream block with the specified index, as described in the module-level documentation.
+pub fn prf(key: &[u8; 32], nonce: &[u8; 16], counter: u64) -> [u8; BLOCK_SIZE] {
+    let mut message = [0; 16];
+    for (word, bytes) in message.iter_mut().zip(key.chunks(4)) {
+        *word = be_u32(bytes);
+    }
+    let mut salt = [0; 4];
+    for (word, bytes) in salt.iter_mut().zip(nonce.chunks(4)) {
+        *word = be_u32(bytes);
+    }
+
+    let mut ret = [0; BLOCK_SIZE];
+    for (bytes, word) in ret.chunks_mut(4).zip(compress32(&IV256, &message, &salt, counter).iter()) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    ret
+}
+
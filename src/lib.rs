@@ -3,6 +3,8 @@
 //! For more information about BLAKE visit its [official homepage](http://131002.net/blake).
 //!
 //! There are two APIs provided: one for single-chunk hashing and one for hashing of multiple data segments.
+//! Both are available with the hash function's length chosen at runtime, at the top level,
+//! and with it fixed at compile-time, in the `b224`, `b256`, `b384`, and `b512` modules.
 //!
 //! # Examples
 //!
@@ -11,6 +13,7 @@
 //! ```
 //! # use blake::Blake;
 //! # use std::iter::FromIterator;
+//! # #[cfg(feature = "width-256")] {
 //! let mut result = [0; 32];
 //! blake::hash(256, b"The lazy fox jumps over the lazy dog", &mut result).unwrap();
 //!
@@ -19,6 +22,7 @@
 //!                 0xCC, 0x75, 0x6D, 0x6C, 0x6C, 0x06, 0xA7, 0xF9,
 //!                 0x22, 0x5E, 0x02, 0xBB, 0x0C, 0x02, 0x6E, 0x8B,
 //!                 0xC5, 0xEB, 0x4E, 0xA7, 0x61, 0x0E, 0xBB, 0x9E]);
+//! # }
 //! ```
 //!
 //! Hashing multiple chunks of data with a 512-bit BLAKE hash function, then verifying the result.
@@ -26,6 +30,7 @@
 //! ```
 //! # use blake::Blake;
 //! # use std::iter::FromIterator;
+//! # #[cfg(feature = "width-512")] {
 //! let mut result = [0; 64];
 //! let mut state = Blake::new(512).unwrap();
 //!
@@ -43,6 +48,7 @@
 //!                 0x5C, 0x0B, 0x09, 0x95, 0x6A, 0x1E, 0xEE, 0x3D,
 //!                 0x1F, 0x07, 0x2B, 0x33, 0x64, 0x47, 0x15, 0x68,
 //!                 0x10, 0x9E, 0x43, 0xC4, 0x0C, 0xE1, 0x27, 0xDA]);
+//! # }
 //! ```
 //!
 //! Comparing result of single- and multi-chunk hash methods hashing the same effective message with a 384-bit BLAKE hash
@@ -51,6 +57,7 @@
 //! ```
 //! # use blake::Blake;
 //! # use std::iter::FromIterator;
+//! # #[cfg(feature = "width-384")] {
 //! let mut result_multi  = [0; 48];
 //! let mut result_single = [0; 48];
 //!
@@ -64,6 +71,7 @@
 //!
 //! assert_eq!(Vec::from_iter(result_multi .iter().map(|&i| i)),
 //!            Vec::from_iter(result_single.iter().map(|&i| i)));
+//! # }
 //! ```
 //!
 //! # Special thanks
@@ -72,15 +80,361 @@
 //!
 //!   * ThePhD
 //!   * Embark Studios
+//!
+//! # Features
+//!
+//!   * `width-224`, `width-256`, `width-384`, `width-512` (default) – the hash functions of each length;
+//!     disabling the ones not needed compiles their compression functions out, along with the modules using them.
+//!     Requesting a disabled one returns `Err(BlakeError::BadHashbitlen)`.
+//!   * `zeroize` – zero the hash state when it's reset or dropped, and implement
+//!     [`Zeroize`](https://docs.rs/zeroize/1/zeroize/trait.Zeroize.html) for `Blake`, the digest types, `Midstate`,
+//!     `commit::Opening`, and the `ots` secret keys.
+//!   * `hasher` – the `hasher` module, with `std::hash::Hasher` and `std::hash::BuildHasher` implementations.
+//!   * `serde` – `hash_serialize()`, hashing the canonical encoding of any `Serialize` value.
+//!   * `json` – `dir::write_json_manifest()` and `dir::read_json_manifest()`, storing manifests with the files'
+//!     metadata as JSON.
+//!   * `digest-09` – implement the [`digest`](https://docs.rs/digest/0.9) 0.9 traits for the per-width hashers,
+//!     for use with crates depending on that version, like `hmac` 0.10.
+//!   * `commit` – the `commit` module, with a hash-based commitment scheme.
+//!   * `tracing` – emit [`tracing`](https://docs.rs/tracing) spans and events (with the amount of data hashed,
+//!     the time it took, and the backend used) for one-shot, reader/file, and directory hashing,
+//!     and manifest verification; the spans of directory operations enclose those of the files they hash.
+//!   * `metrics` – record the same via the [`metrics`](https://docs.rs/metrics) facade: the
+//!     `blake_bytes_hashed_total` and `blake_hashes_total` counters, the `blake_hash_duration_seconds` histogram,
+//!     and the `blake_hash_errors_total` counter, labelled with the `operation`, `hashbitlen`, and `backend`.
+//!   * `tokio` – `hash_file_async()` and `hash_file_async_with()`, hashing files via
+//!     [`tokio::fs`](https://docs.rs/tokio/1/tokio/fs) without blocking the runtime's worker threads.
+//!   * `tar` – `archive::hash_tar()`, hashing the members of a tar stream without extracting them.
+//!   * `object_store` – the `object_store` module, hashing objects in S3, GCS, Azure Blob Storage, and the like,
+//!     via [`object_store`](https://docs.rs/object_store), resuming interrupted reads.
+//!   * `reqwest` – `download_verified()`, downloading files with [`reqwest`](https://docs.rs/reqwest),
+//!     and atomically moving them into place only if they hash to the expected digest.
+//!   * `zip` – `archive::hash_zip()`, hashing the decompressed members of a zip archive without extracting them.
+//!   * `watch` – the `watch` module, re-hashing files in a directory tree as they change, via
+//!     [`notify`](https://docs.rs/notify).
+//!   * `bytes` – `Blake::update_buf()`, hashing [`bytes`](https://docs.rs/bytes) buffers, even non-contiguous ones,
+//!     without copying them.
+//!   * `http-body` – the `body` module, hashing [`http-body`](https://docs.rs/http-body) bodies as they're streamed.
+//!   * `tower` – the `middleware` module, a [`tower`](https://docs.rs/tower) layer validating requests'
+//!     and adding responses' `Content-Digest` headers.
+//!   * `uuid` – `uuid::derive()`, deriving name-based [`uuid`](https://docs.rs/uuid)s with BLAKE-256.
+//!   * `rand_core` – `seed_rng()`, seeding any [`rand_core`](https://docs.rs/rand_core) `SeedableRng` from
+//!     arbitrary data.
+//!   * `sqlx`, `diesel` – store the digest types as binary columns with [`sqlx`](https://docs.rs/sqlx)
+//!     and [`diesel`](https://docs.rs/diesel), with any backend.
+//!   * `rkyv` – derive [`rkyv`](https://docs.rs/rkyv)'s `Archive`, `Serialize`, and `Deserialize` for the digest
+//!     types, for zero-copy archives; the archived digests compare equal to the digests.
+//!   * `defmt` – implement [`defmt`](https://docs.rs/defmt)'s `Format` for `BlakeError` and the digest types,
+//!     for logging from embedded targets.
+//!   * `embedded-io` – implement [`embedded-io`](https://docs.rs/embedded-io)'s `Write` for `Blake` and the
+//!     `Hasher`s, like `std::io::Write`.
+//!   * `test-vectors` – the `test_vectors` module, exposing the known-answer tests `selftest()` runs.
+//!   * `bindgen` – generate the FFI declarations and the state layout from the C header at build time
+//!     instead of using the hand-written ones; requires libclang.
+//!   * `static` – link the system libblake in `BLAKE_LIB_DIR` statically, if `BLAKE_STATIC` isn't set; see below.
+//!   * `simd` – `DifferentialBlake::new_simd()`, checking against the portable implementation vectorised with
+//!     `std::simd`, for any target with SIMD, instead of the scalar one; requires a nightly compiler.
+//!   * `force-soft` – pin the scalar implementation: build the bundled one without auto-vectorisation or vector
+//!     registers, where the C compiler supports that, and name the backend `"c-reference-soft"`; for reproducibility
+//!     audits, debugging miscompiles, and targets whose vector units must stay off. Conflicts with `simd`,
+//!     and with linking a system libblake, whose build can't be controlled.
+//!
+//! # Linking
+//!
+//! By default, the bundled reference implementation is built and linked statically. If the `BLAKE_LIB_DIR`
+//! environment variable is set at build time, the libblake in that directory is linked instead; statically if
+//! `BLAKE_STATIC` is set to anything but `0`, dynamically if it's set to `0`, and, if it's unset, statically with the
+//! `static` feature or when targetting musl, and dynamically otherwise. The library must be built from the same
+//! `blake_ref.h` as the bundled one. The `width-*` features then don't strip it, but still gate the modules,
+//! and the lengths accepted.
+//!
+//! With MSVC, the bundled implementation is built with `cl.exe`, against the same C runtime as the crate, and the
+//! system library is `blake.lib`, either static or the import library of `blake.dll`.
+//!
+//! When cross-compiling, the bundled implementation is built for the target with the compiler
+//! [`cc`](https://docs.rs/cc) finds for it, which can be overridden, along with its flags (like `--sysroot`),
+//! with the `CC_<target>` and `CFLAGS_<target>` environment variables; with the `bindgen` feature,
+//! the header is parsed for the target as well, with any extra flags from `BINDGEN_EXTRA_CLANG_ARGS_<target>`.
+
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
-extern crate libc;
+#[cfg(all(feature = "simd", feature = "force-soft"))]
+compile_error!("The simd and force-soft features are mutually exclusive");
 
+#[macro_use]
+mod width;
 mod native;
+mod encoding;
+mod trace;
+mod file;
+mod file_list;
+mod stack;
+mod verify;
+mod ignore;
+mod selftest;
+mod portable;
+#[cfg(feature = "simd")]
+mod simd;
+mod midstate;
+mod multisalt;
+#[cfg(feature = "reqwest")]
+mod download;
+#[cfg(feature = "digest-09")]
+mod digest09;
+
+pub mod sri;
+pub mod content_digest;
+pub mod structured;
+pub mod pool;
+pub mod dir;
+pub mod tree;
+#[cfg(feature = "width-256")]
+pub mod cas;
+#[cfg(feature = "width-512")]
+pub mod transcript;
+#[cfg(feature = "width-256")]
+pub mod ots;
+#[cfg(feature = "width-256")]
+pub mod hashchain;
+#[cfg(feature = "width-256")]
+pub mod audit;
+#[cfg(feature = "width-256")]
+pub mod mmr;
+#[cfg(feature = "width-512")]
+pub mod bloom;
+#[cfg(feature = "width-256")]
+pub mod ring;
+pub mod keystream;
+pub mod bench;
+pub mod differential;
+pub mod h2c;
+#[cfg(feature = "width-256")]
+pub mod pow;
+#[cfg(feature = "width-256")]
+pub mod decred;
+#[cfg(all(feature = "width-256", feature = "width-512"))]
+pub mod multiset;
+pub mod fingerprint;
+#[cfg(feature = "width-256")]
+pub mod mnemonic;
+#[cfg(feature = "width-256")]
+pub mod otp;
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "width-256"))]
+pub mod xattr;
+#[cfg(feature = "hasher")]
+pub mod hasher;
+#[cfg(feature = "commit")]
+pub mod commit;
+#[cfg(any(feature = "tar", feature = "zip"))]
+pub mod archive;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "http-body")]
+pub mod body;
+#[cfg(feature = "tower")]
+pub mod middleware;
+#[cfg(feature = "uuid")]
+pub mod uuid;
+#[cfg(feature = "object_store")]
+pub mod object_store;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+
+/// BLAKE-224, with a 28-byte digest and a 16-byte salt.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::b224;
+/// let mut state = b224::Hasher::new();
+/// state.update(b"The lazy fox ");
+/// state.update(b"jumps over the lazy dog.");
+///
+/// let digest = state.finalise();
+/// assert_eq!(digest, b224::hash(b"The lazy fox jumps over the lazy dog."));
+/// assert_eq!(&digest.as_bytes()[..4], &[0x34, 0x97, 0x89, 0x0F]);
+/// ```
+#[cfg(feature = "width-224")]
+pub mod b224 {
+    width_module!(224, 28, 16, "BLAKE-224");
+}
+
+/// BLAKE-256, with a 32-byte digest and a 16-byte salt.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::b256;
+/// let mut state = b256::Hasher::new();
+/// state.update(b"The lazy fox ");
+/// state.update(b"jumps over the lazy dog.");
+///
+/// let digest = state.finalise();
+/// assert_eq!(digest, b256::hash(b"The lazy fox jumps over the lazy dog."));
+/// assert_eq!(&digest.as_bytes()[..4], &[0xF2, 0xE5, 0xA9, 0xD0]);
+/// ```
+///
+/// Anything that's `AsRef<[u8]>` can be hashed directly:
+///
+/// ```
+/// # use blake::b256;
+/// let owned = String::from("The lazy fox jumps over the lazy dog.");
+/// let bytes = owned.clone().into_bytes();
+///
+/// assert_eq!(b256::hash(&owned), b256::hash(bytes));
+/// assert_eq!(b256::hash("The lazy fox jumps over the lazy dog."), b256::hash_str(&owned));
+///
+/// let names = ["alice", "bob"];
+/// let digests: Vec<_> = names.iter().cloned().map(b256::hash_str).collect();
+/// assert!(digests[0].verify("alice"));
+/// ```
+///
+/// Or, in one expression:
+///
+/// ```
+/// # use blake::b256;
+/// let digest = b256::Hasher::new().chain_update(b"The lazy fox ").chain_update(b"jumps over the lazy dog.")
+///                                  .finalise();
+/// assert_eq!(digest, b256::hash(b"The lazy fox jumps over the lazy dog."));
+/// ```
+///
+/// Digests can be sorted and used as `BTreeMap` keys, and converted from slices of the right length:
+///
+/// ```
+/// # use blake::{BlakeError, b256};
+/// # use std::collections::BTreeMap;
+/// let mut files = BTreeMap::new();
+/// files.insert(b256::hash("Proletarians of all countries, unite!"), "proles.txt");
+/// files.insert(b256::hash("The lazy fox jumps over the lazy dog."), "fox.txt");
+/// assert!(files.keys().next().unwrap() < files.keys().nth(1).unwrap());
+///
+/// let received = b256::hash("The lazy fox jumps over the lazy dog.").as_bytes().to_vec();
+/// assert_eq!(files[&b256::Digest::try_from(&received[..]).unwrap()], "fox.txt");
+/// assert_eq!(b256::Digest::try_from(&received[..31]), Err(BlakeError::BadLength));
+/// ```
+#[cfg(feature = "width-256")]
+pub mod b256 {
+    width_module!(256, 32, 16, "BLAKE-256");
+}
+
+/// BLAKE-384, with a 48-byte digest and a 32-byte salt.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::b384;
+/// let mut state = b384::Hasher::new();
+/// state.update(b"The lazy fox ");
+/// state.update(b"jumps over the lazy dog.");
+///
+/// let digest = state.finalise();
+/// assert_eq!(digest, b384::hash(b"The lazy fox jumps over the lazy dog."));
+/// assert_eq!(&digest.as_bytes()[..4], &[0xDD, 0x68, 0x1E, 0x3B]);
+/// ```
+#[cfg(feature = "width-384")]
+pub mod b384 {
+    width_module!(384, 48, 32, "BLAKE-384");
+}
+
+/// BLAKE-512, with a 64-byte digest and a 32-byte salt.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::b512;
+/// let mut state = b512::Hasher::new();
+/// state.update(b"The lazy fox ");
+/// state.update(b"jumps over the lazy dog.");
+///
+/// let digest = state.finalise();
+/// assert_eq!(digest, b512::hash(b"The lazy fox jumps over the lazy dog."));
+/// assert_eq!(&digest.as_bytes()[..4], &[0x9A, 0xD4, 0x66, 0xCF]);
+/// ```
+#[cfg(feature = "width-512")]
+pub mod b512 {
+    width_module!(512, 64, 32, "BLAKE-512");
+}
+
+/// A BLAKE-224 hash state; `Default` creates a fresh unsalted one.
+#[cfg(feature = "width-224")]
+pub type Blake224 = b224::Hasher;
+
+/// A BLAKE-256 hash state; `Default` creates a fresh unsalted one.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::{Blake256, b256};
+/// #[derive(Default)]
+/// struct Record {
+///     name: String,
+///     hasher: Blake256,
+/// }
+///
+/// let mut record = Record::default();
+/// record.hasher.update(b"The lazy fox jumps over the lazy dog.");
+/// assert_eq!(record.hasher.finalise(), b256::hash(b"The lazy fox jumps over the lazy dog."));
+/// ```
+#[cfg(feature = "width-256")]
+pub type Blake256 = b256::Hasher;
+
+/// A BLAKE-384 hash state; `Default` creates a fresh unsalted one.
+#[cfg(feature = "width-384")]
+pub type Blake384 = b384::Hasher;
+
+/// A BLAKE-512 hash state; `Default` creates a fresh unsalted one.
+#[cfg(feature = "width-512")]
+pub type Blake512 = b512::Hasher;
+
+/// The commonly-used types, for glob-importing.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// use blake::prelude::*;
+///
+/// let digest = b256::hash(b"The lazy fox jumps over the lazy dog.");
+/// assert_eq!(digest.to_string(), "f2e5a9d093d8aa234e6c545061e817be838b57d8998f15df72e1037fbfeb4fc7");
+/// # }
+/// ```
+pub mod prelude {
+    #[cfg(feature = "width-224")]
+    pub use super::b224;
+    #[cfg(feature = "width-256")]
+    pub use super::b256;
+    #[cfg(feature = "width-384")]
+    pub use super::b384;
+    #[cfg(feature = "width-512")]
+    pub use super::b512;
+    #[cfg(feature = "width-224")]
+    pub use super::Blake224;
+    #[cfg(feature = "width-256")]
+    pub use super::Blake256;
+    #[cfg(feature = "width-384")]
+    pub use super::Blake384;
+    #[cfg(feature = "width-512")]
+    pub use super::Blake512;
+    pub use super::{Blake, BlakeError};
+}
 
 use std::error::Error;
 use std::fmt;
 use std::io;
 
+pub use crate::file::{IoOptions, RateLimiter, hash_reader, hash_reader_with, hash_file, hash_file_with, hash_reader_pieces, copy_and_hash,
+               copy_and_hash_with_buffer, readers_equal_by_hash, files_equal_by_hash};
+#[cfg(feature = "tokio")]
+pub use crate::file::{hash_file_async, hash_file_async_with};
+#[cfg(feature = "reqwest")]
+pub use crate::download::{download_verified, download_verified_with};
+pub use crate::file_list::FileList;
+pub use crate::verify::{VerifyingReader, VerifyingWriter};
+pub use crate::midstate::Midstate;
+pub use crate::multisalt::MultiSaltHasher;
+pub use crate::stack::StackState;
+pub use crate::selftest::{selftest, SelfTestError};
+
 
 /// Helper result type containing `BlakeError`.
 pub type Result<T> = std::result::Result<T, BlakeError>;
@@ -90,11 +444,22 @@ pub type Result<T> = std::result::Result<T, BlakeError>;
 ///
 /// Refer to individual functions for extended documentation.
 ///
+/// This, and the other one-shot hashing functions, keep the hash state on the stack, and never allocate,
+/// except to record metrics with the `metrics` feature.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// and `Err(BlakeError::BadLength)` if `hashval` is shorter than the hash function's size.
+///
+/// Don't use this as a MAC by hashing a secret key followed by the message:
+/// a digest of that can be extended into one of the same key followed by a longer message without knowing the key.
+/// Use `keyed_hash()` instead.
+///
 /// # Example
 ///
 /// ```
 /// # use blake::Blake;
 /// # use std::iter::FromIterator;
+/// # #[cfg(all(feature = "width-256", feature = "width-512"))] {
 /// let mut result_256 = [0; 32];
 /// let mut result_512 = [0; 64];
 ///
@@ -115,14 +480,450 @@ pub type Result<T> = std::result::Result<T, BlakeError>;
 ///                 0x00, 0xAC, 0xC9, 0xEA, 0xB7, 0x7B, 0x4D, 0x4C,
 ///                 0x2E, 0x85, 0x2A, 0xAA, 0xA2, 0x5A, 0x63, 0x6D,
 ///                 0x80, 0xAF, 0x3F, 0xC7, 0x91, 0x3E, 0xF5, 0xB8]);
+/// # }
+/// ```
+pub fn hash<D: AsRef<[u8]>>(hashbitlen: i32, data: D, hashval: &mut [u8]) -> Result<()> {
+    let data = data.as_ref();
+    trace::instrumented("hash", hashbitlen, data.len() as u64, || {
+        check_hashval(hashbitlen, hashval)?;
+
+        if !native::fits_one_update(data) {
+            let mut state = stack::StackState::new(hashbitlen)?;
+            state.update(data);
+            return state.finalise(hashval);
+        }
+
+        match unsafe { native::BLAKE_Hash_Hash(hashbitlen, data.as_ptr(), data.len() as u64 * 8, hashval.as_mut_ptr()) } {
+            0 => Ok(()),
+            e => Err(BlakeError::from(e)),
+        }
+    })
+}
+
+/// Hash the data in the context of the specified domain-separation tag.
+///
+/// The tag is hashed first, prefixed with its length as a little-endian `u64`, followed by the data,
+/// so different uses of the same data in one protocol, given different tags, produce unrelated digests.
+/// Tags should be fixed strings, unique for each use, like `"MyProtocol v1 session key"`.
+///
+/// This is equivalent to hashing the data with a state from `Blake::new_domain()`.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// and `Err(BlakeError::BadLength)` if `hashval` is shorter than the hash function's size.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// let mut result_commit = [0; 32];
+/// let mut result_sign   = [0; 32];
+///
+/// blake::hash_domain(256, "MyProtocol v1 commitment", b"The lazy fox jumps over the lazy dog.", &mut result_commit).unwrap();
+/// blake::hash_domain(256, "MyProtocol v1 signature", b"The lazy fox jumps over the lazy dog.", &mut result_sign).unwrap();
+///
+/// assert!(result_commit != result_sign);
+/// assert!(blake::hash_domain(256, "MyProtocol v1 commitment", b"The lazy fox jumps over the lazy dog.", &mut [0; 31]).is_err());
+/// # }
+/// ```
+pub fn hash_domain(hashbitlen: i32, tag: &str, data: &[u8], hashval: &mut [u8]) -> Result<()> {
+    check_hashval(hashbitlen, hashval)?;
+    let mut state = stack::StackState::new_domain(hashbitlen, tag)?;
+    state.update(data);
+    state.finalise(hashval)
+}
+
+/// Hash the data into a digest of the specified length in bits, bound to that length.
+///
+/// This is equivalent to `Blake::finalise_truncated()` on a state from `Blake::new()`;
+/// refer to it for the construction and the security trade-offs.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// and `Err(BlakeError::BadLength)` if `bits` is 0 or above `hashbitlen`, or if `hashval` is too short.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "width-512")] {
+/// let mut result_128 = [0; 16];
+/// let mut result_160 = [0; 20];
+///
+/// blake::hash_truncated(512, 128, b"The lazy fox jumps over the lazy dog.", &mut result_128).unwrap();
+/// blake::hash_truncated(512, 160, b"The lazy fox jumps over the lazy dog.", &mut result_160).unwrap();
+///
+/// assert!(result_128 != result_160[..16]);
+/// # }
 /// ```
-pub fn hash(hashbitlen: i32, data: &[u8], hashval: &mut [u8]) -> Result<()> {
-    match unsafe { native::BLAKE_Hash_Hash(hashbitlen, data.as_ptr(), data.len() as u64 * 8, hashval.as_mut_ptr()) } {
-        0 => Ok(()),
-        e => Err(BlakeError::from(e)),
+pub fn hash_truncated(hashbitlen: i32, bits: u32, data: &[u8], hashval: &mut [u8]) -> Result<()> {
+    let mut state = stack::StackState::new(hashbitlen)?;
+    state.update(data);
+    state.update(truncation_trailer(hashbitlen, bits, hashval.len())?);
+
+    let mut full = [0; 64];
+    state.finalise(&mut full)?;
+    truncate_digest(&full, bits, hashval);
+    Ok(())
+}
+
+/// Compute a tag of the message under the secret key, a MAC for when interoperability with HMAC isn't needed.
+///
+/// The key should be uniformly random and at least 16 bytes long; a password isn't a key.
+/// Unlike `hash()` of the key followed by the message, the tag can't be extended into one of a longer message.
+///
+/// The tag is computed in two passes, each in the context of a domain-separation tag, as in `hash_domain()`:
+/// the inner digest is of the key, prefixed with its length as a little-endian `u64`, followed by the message,
+/// in the context of `"blake-rs keyed_hash inner"`,
+/// and the tag is of the same length-prefixed key followed by the inner digest,
+/// in the context of `"blake-rs keyed_hash outer"`.
+///
+/// Compare tags with `keyed_hash_verify()`, which does so in constant time, not with `==`.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// and `Err(BlakeError::BadLength)` if the key is empty, or if `hashval` is shorter than the hash function's size.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// let mut tag     = [0; 32];
+/// let mut tag_bad = [0; 32];
+///
+/// blake::keyed_hash(256, b"0123456789ABCDEF", b"The lazy fox jumps over the lazy dog.", &mut tag).unwrap();
+/// blake::keyed_hash(256, b"0123456789ABCDEG", b"The lazy fox jumps over the lazy dog.", &mut tag_bad).unwrap();
+/// assert!(tag != tag_bad);
+///
+/// let mut naive = [0; 32];
+/// blake::hash(256, b"0123456789ABCDEFThe lazy fox jumps over the lazy dog.", &mut naive).unwrap();
+/// assert!(tag != naive);
+///
+/// assert!(blake::keyed_hash(256, b"", b"The lazy fox jumps over the lazy dog.", &mut tag).is_err());
+/// assert!(blake::keyed_hash(256, b"0123456789ABCDEF", b"The lazy fox jumps over the lazy dog.", &mut tag[..31]).is_err());
+/// # }
+/// ```
+pub fn keyed_hash(hashbitlen: i32, key: &[u8], msg: &[u8], hashval: &mut [u8]) -> Result<()> {
+    if key.is_empty() {
+        return Err(BlakeError::BadLength);
     }
+    check_hashval(hashbitlen, hashval)?;
+
+    let mut inner = [0; 64];
+    let mut state = stack::StackState::new_domain(hashbitlen, "blake-rs keyed_hash inner")?;
+    state.update((key.len() as u64).to_le_bytes());
+    state.update(key);
+    state.update(msg);
+    state.finalise(&mut inner)?;
+
+    let mut state = stack::StackState::new_domain(hashbitlen, "blake-rs keyed_hash outer")?;
+    state.update((key.len() as u64).to_le_bytes());
+    state.update(key);
+    state.update(&inner[..hashbitlen as usize / 8]);
+    state.finalise(hashval)
+}
+
+/// Check, in constant time, whether the tag is that of the message under the secret key, as computed by `keyed_hash()`.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// and `Err(BlakeError::BadLength)` if the key is empty; a tag of the wrong length doesn't match.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "width-256")] {
+/// let mut tag = [0; 32];
+/// blake::keyed_hash(256, b"0123456789ABCDEF", b"The lazy fox jumps over the lazy dog.", &mut tag).unwrap();
+///
+/// assert!(blake::keyed_hash_verify(256, b"0123456789ABCDEF", b"The lazy fox jumps over the lazy dog.", &tag).unwrap());
+/// assert!(!blake::keyed_hash_verify(256, b"0123456789ABCDEF", b"The lazy dog jumps over the lazy fox.", &tag).unwrap());
+/// # }
+/// ```
+pub fn keyed_hash_verify(hashbitlen: i32, key: &[u8], msg: &[u8], tag: &[u8]) -> Result<bool> {
+    let mut expected = [0; 64];
+    keyed_hash(hashbitlen, key, msg, &mut expected)?;
+    Ok(constant_time_eq(&expected[..hashbitlen as usize / 8], tag))
 }
 
+/// Derive a 256-bit subkey for the purpose described by the context string from the key material.
+///
+/// The context should be a hardcoded, globally unique string describing the purpose,
+/// like `"example.com 2026-10-16 session tokens v1"`, never anything variable;
+/// the same key material yields unrelated subkeys in different contexts.
+///
+/// The subkey is BLAKE-256 of the key material in the context of the domain-separation tag `"blake-rs derive_key"`,
+/// as in `hash_domain()`, preceded by the context, prefixed with its length as a little-endian `u64`.
+/// Note that, unlike BLAKE3's `derive_key()`, this doesn't stretch the key material:
+/// it must already be of high entropy.
+///
+/// # Example
+///
+/// ```
+/// let session = blake::derive_key("example.com 2026-10-16 session tokens v1", b"master key material");
+/// let backups = blake::derive_key("example.com 2026-10-16 backup encryption v1", b"master key material");
+/// assert!(session != backups);
+///
+/// let mut manual = [0; 32];
+/// blake::hash_domain(256,
+///                    "blake-rs derive_key",
+///                    b"\x28\0\0\0\0\0\0\0example.com 2026-10-16 session tokens v1master key material",
+///                    &mut manual)
+///     .unwrap();
+/// assert_eq!(session, manual);
+/// ```
+#[cfg(feature = "width-256")]
+pub fn derive_key(context: &str, key_material: &[u8]) -> [u8; 32] {
+    let mut state = stack::StackState::new_domain(256, "blake-rs derive_key").expect("256 is a valid hashbitlen");
+    state.update((context.len() as u64).to_le_bytes());
+    state.update(context.as_bytes());
+    state.update(key_material);
+
+    let mut subkey = [0; 32];
+    state.finalise(&mut subkey).expect("the buffer fits the digest");
+    subkey
+}
+
+/// Derive a 32-byte RNG seed from arbitrary data, as BLAKE-256 of the data in the context of the domain-separation tag
+/// `"blake-rs seed_from"`, as in `hash_domain()`.
+///
+/// Use this to seed deterministic simulations and tests from human-readable identifiers;
+/// it's no substitute for a seed from the OS when the output must be unpredictable.
+///
+/// # Example
+///
+/// ```
+/// let seed = blake::seed_from(b"simulation run #42");
+/// assert_eq!(seed, blake::seed_from(b"simulation run #42"));
+/// assert!(seed != blake::seed_from(b"simulation run #43"));
+/// ```
+#[cfg(feature = "width-256")]
+pub fn seed_from(data: &[u8]) -> [u8; 32] {
+    let mut seed = [0; 32];
+    hash_domain(256, "blake-rs seed_from", data, &mut seed).expect("256 is a valid hashbitlen");
+    seed
+}
+
+/// Create an RNG seeded from arbitrary data.
+///
+/// Seeds of up to 32 bytes are the prefix of `seed_from()`;
+/// longer ones continue with BLAKE-256 of `seed_from()` followed by the block index (from 1) as a little-endian `u64`,
+/// for every subsequent 32 bytes.
+///
+/// # Example
+///
+/// ```
+/// # use rand_core::SeedableRng;
+/// struct Recorder([u8; 32]);
+/// impl SeedableRng for Recorder {
+///     type Seed = [u8; 32];
+///     fn from_seed(seed: [u8; 32]) -> Recorder {
+///         Recorder(seed)
+///     }
+/// }
+///
+/// # fn main() {
+/// let rng: Recorder = blake::seed_rng(b"simulation run #42");
+/// assert_eq!(rng.0, blake::seed_from(b"simulation run #42"));
+/// # }
+/// ```
+#[cfg(feature = "rand_core")]
+pub fn seed_rng<R: rand_core::SeedableRng>(data: &[u8]) -> R {
+    let first = seed_from(data);
+    let mut seed = R::Seed::default();
+    for (i, chunk) in seed.as_mut().chunks_mut(32).enumerate() {
+        let block = if i == 0 {
+            first
+        } else {
+            let mut block = [0; 32];
+            let mut state = stack::StackState::new(256).expect("256 is a valid hashbitlen");
+            state.update(first);
+            state.update((i as u64).to_le_bytes());
+            state.finalise(&mut block).expect("the buffer fits the digest");
+            block
+        };
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    R::from_seed(seed)
+}
+
+/// Hash a list of byte strings unambiguously, by prefixing each with its length as a little-endian `u64`.
+///
+/// Use this instead of `update()`ing a state with each item, which would make, for example,
+/// `["ab", "c"]` and `["a", "bc"]` hash the same.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// and `Err(BlakeError::BadLength)` if `hashval` is shorter than the hash function's size.
+///
+/// # Example
+///
+/// ```
+/// # use blake::Blake;
+/// # #[cfg(feature = "width-256")] {
+/// let mut result_1 = [0; 32];
+/// let mut result_2 = [0; 32];
+///
+/// blake::hash_items(256, &["ab", "c"], &mut result_1).unwrap();
+/// blake::hash_items(256, vec![b"a".to_vec(), b"bc".to_vec()], &mut result_2).unwrap();
+/// assert!(result_1 != result_2);
+///
+/// let mut state = Blake::new(256).unwrap();
+/// state.update(b"\x02\0\0\0\0\0\0\0ab");
+/// state.update(b"\x01\0\0\0\0\0\0\0c");
+/// state.finalise(&mut result_2);
+/// assert_eq!(result_1, result_2);
+///
+/// assert!(blake::hash_items(256, &["ab", "c"], &mut result_1[..31]).is_err());
+/// # }
+/// ```
+pub fn hash_items<I>(hashbitlen: i32, items: I, hashval: &mut [u8]) -> Result<()>
+    where I: IntoIterator,
+          I::Item: AsRef<[u8]>
+{
+    check_hashval(hashbitlen, hashval)?;
+    let mut state = stack::StackState::new(hashbitlen)?;
+    for item in items {
+        let item = item.as_ref();
+        state.update((item.len() as u64).to_le_bytes());
+        state.update(item);
+    }
+    state.finalise(hashval)
+}
+
+/// Hash the canonical binary encoding of the value without buffering it.
+///
+/// The encoding is [bincode](https://docs.rs/bincode/1)'s with fixed-width little-endian integers,
+/// so it's stable across platforms, but note that types like `HashMap` serialise their contents in an unspecified order.
+///
+/// Returns:
+///
+///   * `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+///   * `Err(BlakeError::BadLength)` if `hashval` is shorter than the hash function's size,
+///   * `Err(BlakeError::SerialisationFailed)` if the value failed to serialise, or
+///   * `Ok(())` otherwise.
+///
+/// # Example
+///
+/// ```
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct Config {
+///     name: String,
+///     retries: u32,
+/// }
+///
+/// let mut result_serialised = [0; 32];
+/// let mut result_manual     = [0; 32];
+///
+/// blake::hash_serialize(256, &Config { name: "proles".to_string(), retries: 3 }, &mut result_serialised).unwrap();
+/// blake::hash(256, b"\x06\0\0\0\0\0\0\0proles\x03\0\0\0", &mut result_manual).unwrap();
+/// assert_eq!(result_serialised, result_manual);
+///
+/// assert_eq!(blake::hash_serialize(256, &Config { name: "proles".to_string(), retries: 3 }, &mut [0; 31]),
+///            Err(blake::BlakeError::BadLength));
+/// ```
+#[cfg(feature = "serde")]
+pub fn hash_serialize<T: ?Sized + serde::Serialize>(hashbitlen: i32, value: &T, hashval: &mut [u8]) -> Result<()> {
+    use bincode::Options;
+
+    check_hashval(hashbitlen, hashval)?;
+    let mut state = stack::StackState::new(hashbitlen)?;
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_little_endian()
+        .serialize_into(&mut state, value)
+        .map_err(|_| BlakeError::SerialisationFailed)?;
+    state.finalise(hashval)
+}
+
+/// Hash the data with BLAKE-256 and return the first 8 bytes of the digest as a little-endian integer.
+///
+/// This is meant for sharding keys, Bloom filter indices and the like, where BLAKE's distribution is wanted, but not its
+/// full output.
+///
+/// # Example
+///
+/// ```
+/// # use blake::Blake;
+/// let mut result = [0; 32];
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+///
+/// assert_eq!(blake::hash64(b"The lazy fox jumps over the lazy dog."), 0x23AAD893D0A9E5F2);
+/// assert_eq!(blake::hash64(b"The lazy fox jumps over the lazy dog.").to_le_bytes(), result[..8]);
+/// ```
+#[cfg(feature = "width-256")]
+pub fn hash64(data: &[u8]) -> u64 {
+    let mut result = [0; 32];
+    hash(256, data, &mut result).expect("256 is a valid hashbitlen");
+
+    let mut first = [0; 8];
+    first.copy_from_slice(&result[..8]);
+    u64::from_le_bytes(first)
+}
+
+/// Hash the data with BLAKE-256 and return the first 16 bytes of the digest as a little-endian integer.
+///
+/// See `hash64()` for the intended use.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(blake::hash128(b"The lazy fox jumps over the lazy dog."), 0xBE17E86150546C4E23AAD893D0A9E5F2);
+/// assert_eq!(blake::hash128(b"The lazy fox jumps over the lazy dog.") as u64,
+///            blake::hash64(b"The lazy fox jumps over the lazy dog."));
+/// ```
+#[cfg(feature = "width-256")]
+pub fn hash128(data: &[u8]) -> u128 {
+    let mut result = [0; 32];
+    hash(256, data, &mut result).expect("256 is a valid hashbitlen");
+
+    let mut first = [0; 16];
+    first.copy_from_slice(&result[..16]);
+    u128::from_le_bytes(first)
+}
+
+/// Get the size of the output of the hash function of the given length, in bytes.
+fn digest_size(hashbitlen: i32) -> Result<usize> {
+    match hashbitlen {
+        224 if cfg!(feature = "width-224") => Ok(28),
+        256 if cfg!(feature = "width-256") => Ok(32),
+        384 if cfg!(feature = "width-384") => Ok(48),
+        512 if cfg!(feature = "width-512") => Ok(64),
+        _ => Err(BlakeError::BadHashbitlen),
+    }
+}
+
+/// Check that `hashval` fits the digest of the hash function of the given length.
+fn check_hashval(hashbitlen: i32, hashval: &[u8]) -> Result<()> {
+    if hashval.len() < digest_size(hashbitlen)? {
+        return Err(BlakeError::BadLength);
+    }
+    Ok(())
+}
+
+/// Validate a truncation to `bits` bits into `hashval_len` bytes and build the trailer hashed before finalising:
+/// `"blake-rs truncated"`, then `bits` as a little-endian `u64`.
+fn truncation_trailer(hashbitlen: i32, bits: u32, hashval_len: usize) -> Result<[u8; 26]> {
+    if bits == 0 || bits as i64 > hashbitlen as i64 || hashval_len < bits.div_ceil(8) as usize {
+        return Err(BlakeError::BadLength);
+    }
+
+    let mut trailer = [0; 26];
+    trailer[..18].copy_from_slice(b"blake-rs truncated");
+    trailer[18..].copy_from_slice(&(bits as u64).to_le_bytes());
+    Ok(trailer)
+}
+
+/// Copy the first `bits` bits of the full digest into `hashval`, zeroing the unused low bits of the last byte.
+fn truncate_digest(full: &[u8], bits: u32, hashval: &mut [u8]) {
+    let len = bits.div_ceil(8) as usize;
+    hashval[..len].copy_from_slice(&full[..len]);
+    if !bits.is_multiple_of(8) {
+        hashval[len - 1] &= 0xFFu8 << (8 - bits % 8);
+    }
+}
+
+/// Compare two byte strings in time depending only on their lengths.
+fn constant_time_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().zip(rhs).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
+
 /// Hashing state for multiple data sets.
 ///
 /// # Example
@@ -132,6 +933,7 @@ pub fn hash(hashbitlen: i32, data: &[u8], hashval: &mut [u8]) -> Result<()> {
 /// ```
 /// # use blake::Blake;
 /// # use std::iter::FromIterator;
+/// # #[cfg(feature = "width-256")] {
 /// let mut state = Blake::new(256).unwrap();
 ///
 /// state.update(b"Abolish ");
@@ -146,6 +948,7 @@ pub fn hash(hashbitlen: i32, data: &[u8], hashval: &mut [u8]) -> Result<()> {
 ///                 0x6A, 0xE7, 0x2C, 0xC9, 0x94, 0x6F, 0x59, 0xBB,
 ///                 0x0B, 0x21, 0xD8, 0xCC, 0x8E, 0x4D, 0xBB, 0x53,
 ///                 0x24, 0xDF, 0x10, 0xB7, 0x11, 0xF9, 0x82, 0x1C]);
+/// # }
 /// ```
 ///
 /// A `Write` implementation is also provided:
@@ -154,6 +957,7 @@ pub fn hash(hashbitlen: i32, data: &[u8], hashval: &mut [u8]) -> Result<()> {
 /// # use std::iter::FromIterator;
 /// # use blake::Blake;
 /// # use std::io;
+/// # #[cfg(feature = "width-256")] {
 /// let mut state = Blake::new(256).unwrap();
 /// io::copy(&mut &b"The lazy fox jumps over the lazy dog."[..], &mut state).unwrap();
 ///
@@ -164,18 +968,39 @@ pub fn hash(hashbitlen: i32, data: &[u8], hashval: &mut [u8]) -> Result<()> {
 ///                 0x4E, 0x6C, 0x54, 0x50, 0x61, 0xE8, 0x17, 0xBE,
 ///                 0x83, 0x8B, 0x57, 0xD8, 0x99, 0x8F, 0x15, 0xDF,
 ///                 0x72, 0xE1, 0x03, 0x7F, 0xBF, 0xEB, 0x4F, 0xC7]);
+/// # }
 /// ```
 pub struct Blake {
     raw_state: native::FFIHashState,
+    hashbitlen: i32,
+    locked: bool,
 }
 
 /// Some functions in the library can fail, this enum represents all the possible ways they can.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum BlakeError {
     /// Generic failure state
     Fail,
     /// `hashbitlen` passed to `Blake::new()` or `hash()` incorrect
     BadHashbitlen,
+    /// A string representation of a digest was malformed
+    BadEncoding,
+    /// Locking the state into memory in `Blake::new_locked()` failed
+    LockFailed,
+    /// The value passed to `hash_serialize()` failed to serialise
+    SerialisationFailed,
+    /// The operation was cancelled via `IoOptions::cancel()` or `dir::DirOptions::cancel()`
+    Cancelled,
+    /// The data read through a `VerifyingReader`, written through a `VerifyingWriter`, or read from a `cas::Store`,
+    /// didn't hash to the expected digest
+    DigestMismatch,
+    /// The backends checked against each other by a `differential::DifferentialBlake` disagreed
+    BackendMismatch,
+    /// An input had an invalid length for the operation, like a digest or key of the wrong size,
+    /// or an out-of-bounds length requested from `h2c::expand_message_xmd()`
+    BadLength,
 }
 
 
@@ -208,13 +1033,22 @@ impl Blake {
     ///
     /// ```
     /// # use blake::Blake;
+    /// # #[cfg(feature = "width-512")] {
     /// Blake::new(512).unwrap();
+    /// # }
     /// ```
     pub fn new(hashbitlen: i32) -> Result<Blake> {
+        digest_size(hashbitlen)?;
         let mut raw_state = native::malloc_hash_state();
 
         match unsafe { native::BLAKE_Hash_Init(raw_state, hashbitlen) } {
-            0 => Ok(Blake { raw_state: raw_state }),
+            0 => {
+                Ok(Blake {
+                    raw_state,
+                    hashbitlen,
+                    locked: false,
+                })
+            }
             e => {
                 native::free_hash_state(&mut raw_state);
                 Err(BlakeError::from(e))
@@ -222,6 +1056,100 @@ impl Blake {
         }
     }
 
+    /// Create a new hash state in page-locked memory, which won't be swapped out, and will be zeroed when dropped.
+    ///
+    /// Use this for hashing with secret salts (or secret data). Each such state occupies at least one full page,
+    /// and counts towards the process' locked memory limit (`RLIMIT_MEMLOCK` on Unices, the working set size on
+    /// Windows).
+    ///
+    /// Returns:
+    ///
+    ///   * `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is not valid, as in `Blake::new()`,
+    ///   * `Err(BlakeError::LockFailed)` if the memory couldn't be allocated or locked, or
+    ///   * `Ok(Blake)` if initialisation succeeds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-256")] {
+    /// let mut result_locked = [0; 32];
+    /// let mut result        = [0; 32];
+    ///
+    /// let mut state = Blake::new_locked(256).unwrap();
+    /// state.add_salt(b"Secret MAC key!!").unwrap();
+    /// state.update(b"The lazy fox jumps over the lazy dog.");
+    /// state.finalise(&mut result_locked);
+    ///
+    /// let mut state = Blake::new(256).unwrap();
+    /// state.add_salt(b"Secret MAC key!!").unwrap();
+    /// state.update(b"The lazy fox jumps over the lazy dog.");
+    /// state.finalise(&mut result);
+    ///
+    /// assert_eq!(result_locked, result);
+    /// # }
+    /// ```
+    pub fn new_locked(hashbitlen: i32) -> Result<Blake> {
+        digest_size(hashbitlen)?;
+        let mut raw_state = native::malloc_locked_hash_state().ok_or(BlakeError::LockFailed)?;
+
+        match unsafe { native::BLAKE_Hash_Init(raw_state, hashbitlen) } {
+            0 => {
+                Ok(Blake {
+                    raw_state,
+                    hashbitlen,
+                    locked: true,
+                })
+            }
+            e => {
+                native::free_locked_hash_state(&mut raw_state);
+                Err(BlakeError::from(e))
+            }
+        }
+    }
+
+    /// Create a new hash state, as in `Blake::new()`, and hash the specified domain-separation tag into it,
+    /// as described in `hash_domain()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-256")] {
+    /// let mut result_streamed = [0; 32];
+    /// let mut result_oneshot  = [0; 32];
+    ///
+    /// let mut state = Blake::new_domain(256, "MyProtocol v1 transcript").unwrap();
+    /// state.update(b"The lazy fox ");
+    /// state.update(b"jumps over the lazy dog.");
+    /// state.finalise(&mut result_streamed);
+    ///
+    /// blake::hash_domain(256, "MyProtocol v1 transcript", b"The lazy fox jumps over the lazy dog.", &mut result_oneshot)
+    ///     .unwrap();
+    /// assert_eq!(result_streamed, result_oneshot);
+    /// # }
+    /// ```
+    pub fn new_domain(hashbitlen: i32, tag: &str) -> Result<Blake> {
+        let mut state = Blake::new(hashbitlen)?;
+        state.update((tag.len() as u64).to_le_bytes());
+        state.update(tag.as_bytes());
+        Ok(state)
+    }
+
+    /// Get the length of the hash function's output, in bits, as passed to `Blake::new()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-384")] {
+    /// assert_eq!(Blake::new(384).unwrap().hashbitlen(), 384);
+    /// # }
+    /// ```
+    pub fn hashbitlen(&self) -> i32 {
+        self.hashbitlen
+    }
+
     /// Add a salt to the hash function.
     ///
     /// Returns:
@@ -243,6 +1171,7 @@ impl Blake {
     /// ```
     /// # use blake::Blake;
     /// # use std::iter::FromIterator;
+    /// # #[cfg(feature = "width-512")] {
     /// let mut result_unsalted = [0; 64];
     /// let mut result_salted   = [0; 64];
     ///
@@ -259,6 +1188,7 @@ impl Blake {
     ///
     /// assert!(Vec::from_iter(result_unsalted.iter().map(|&i| i)) !=
     ///         Vec::from_iter(result_salted  .iter().map(|&i| i)))
+    /// # }
     /// ```
     pub fn add_salt(&mut self, salt: &[u8]) -> Result<()> {
         match unsafe { native::BLAKE_Hash_AddSalt(self.raw_state, salt.as_ptr()) } {
@@ -269,6 +1199,8 @@ impl Blake {
 
     /// Append the provided data to the hash function.
     ///
+    /// Slices too long for their length in bits to fit into 64 bits are transparently split into multiple updates.
+    ///
     /// # Examples
     ///
     /// Hashing a part of [a short story](http://nabijaczleweli.xyz/capitalism/writing/Świat_to_kilka_takich_pokoi/)
@@ -276,6 +1208,7 @@ impl Blake {
     /// ```
     /// # use blake::Blake;
     /// # use std::iter::FromIterator;
+    /// # #[cfg(feature = "width-512")] {
     /// let mut result = [0; 64];
     ///
     /// let mut state = Blake::new(512).unwrap();
@@ -294,10 +1227,70 @@ impl Blake {
     ///                 0x3F, 0xA3, 0xB7, 0x70, 0x5D, 0xFC, 0x14, 0xB9,
     ///                 0xAA, 0xCA, 0xDC, 0x5B, 0x34, 0x96, 0x0B, 0x3C,
     ///                 0x87, 0x1F, 0x69, 0x46, 0xCD, 0xC2, 0xB2, 0x14]);
+    /// # }
     /// ```
-    pub fn update(&mut self, data: &[u8]) {
-        unsafe {
-            native::BLAKE_Hash_Update(self.raw_state, data.as_ptr(), data.len() as u64 * 8);
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) {
+        native::update(self.raw_state, data.as_ref());
+    }
+
+    /// Append the provided data to the hash function, as with `update()`, and return the state,
+    /// so multi-part hashes can be written as one expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-256")] {
+    /// let mut result_chained = [0; 32];
+    /// let mut result         = [0; 32];
+    ///
+    /// Blake::new(256).unwrap().chain_update("The lazy fox ").chain_update("jumps over the lazy dog.")
+    ///     .finalise(&mut result_chained);
+    ///
+    /// blake::hash(256, "The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+    /// assert_eq!(result_chained, result);
+    /// # }
+    /// ```
+    pub fn chain_update<D: AsRef<[u8]>>(mut self, data: D) -> Blake {
+        self.update(data);
+        self
+    }
+
+    /// Shorthand for `chain_update()`.
+    pub fn chain<D: AsRef<[u8]>>(self, data: D) -> Blake {
+        self.chain_update(data)
+    }
+
+    /// Append all data remaining in the buffer to the hash function, chunk by chunk, consuming it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # use bytes::{Buf, Bytes};
+    /// # fn main() {
+    /// let mut result_buf = [0; 32];
+    /// let mut result     = [0; 32];
+    ///
+    /// let mut buf = Bytes::from_static(b"The lazy fox ").chain(Bytes::from_static(b"jumps over the lazy dog."));
+    /// let mut state = Blake::new(256).unwrap();
+    /// state.update_buf(&mut buf);
+    /// state.finalise(&mut result_buf);
+    /// assert!(!buf.has_remaining());
+    ///
+    /// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+    /// assert_eq!(result_buf, result);
+    /// # }
+    /// ```
+    #[cfg(feature = "bytes")]
+    pub fn update_buf<B: bytes::Buf>(&mut self, buf: &mut B) {
+        while buf.has_remaining() {
+            let len = {
+                let chunk = buf.chunk();
+                self.update(chunk);
+                chunk.len()
+            };
+            buf.advance(len);
         }
     }
 
@@ -314,6 +1307,7 @@ impl Blake {
     /// ```
     /// # use blake::Blake;
     /// # use std::iter::FromIterator;
+    /// # #[cfg(all(feature = "width-224", feature = "width-256", feature = "width-384", feature = "width-512"))] {
     /// let mut result_224 = [0; 28];
     /// let mut result_256 = [0; 32];
     /// let mut result_384 = [0; 48];
@@ -360,12 +1354,138 @@ impl Blake {
     ///                 0x5F, 0x30, 0xBE, 0x2E, 0x00, 0xA8, 0x25, 0xD6,
     ///                 0x66, 0x6D, 0x9C, 0x4C, 0x23, 0xA5, 0x23, 0xD3,
     ///                 0x10, 0xA0, 0x58, 0x3F, 0x1E, 0x7C, 0xCC, 0xFE]);
+    /// # }
     /// ```
     pub fn finalise(&mut self, hashval: &mut [u8]) {
         unsafe {
             native::BLAKE_Hash_Final(self.raw_state, hashval.as_mut_ptr());
         }
     }
+
+    /// Finish hashing and return the digest as an array.
+    ///
+    /// # Panics
+    ///
+    /// If the length of the array isn't the hash function's size in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-384")] {
+    /// let digest: [u8; 48] = Blake::new(384).unwrap().chain(b"The lazy fox ").chain(b"jumps over the lazy dog.")
+    ///                                              .finalise_array();
+    /// assert_eq!(&digest[..4], &[0xDD, 0x68, 0x1E, 0x3B]);
+    /// # }
+    /// ```
+    pub fn finalise_array<const N: usize>(mut self) -> [u8; N] {
+        assert_eq!(N, self.hashbitlen as usize / 8, "Blake::finalise_array() length not the hash function's size");
+
+        let mut ret = [0; N];
+        self.finalise(&mut ret);
+        ret
+    }
+
+    /// Finish hashing and return the digest in a `Vec` of the hash function's size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(all(feature = "width-224", feature = "width-256", feature = "width-384", feature = "width-512"))] {
+    /// for &hashbitlen in &[224, 256, 384, 512] {
+    ///     let digest = Blake::new(hashbitlen).unwrap().chain(b"The lazy fox jumps over the lazy dog.").finalise_vec();
+    ///     assert_eq!(digest.len(), hashbitlen as usize / 8);
+    /// }
+    /// # }
+    /// ```
+    pub fn finalise_vec(&mut self) -> Vec<u8> {
+        let mut ret = vec![0; self.hashbitlen as usize / 8];
+        self.finalise(&mut ret);
+        ret
+    }
+
+    /// Finish hashing and return the digest in a boxed slice of the hash function's size.
+    pub fn finalise_boxed(&mut self) -> Box<[u8]> {
+        self.finalise_vec().into_boxed_slice()
+    }
+
+    /// Finish hashing and store the first `bits` bits of a digest bound to that length in the provided space.
+    ///
+    /// Before finalising, the ASCII string `"blake-rs truncated"` and `bits` as a little-endian `u64` are hashed
+    /// after the data, so the output isn't a prefix of the full digest, nor of a truncation to any other length:
+    /// an attacker can't obtain it by chopping off a longer digest they were given, or vice versa.
+    /// `(bits + 7) / 8` bytes are written; if `bits` isn't a multiple of 8, the unused low bits of the last one are zero.
+    ///
+    /// A `bits`-bit digest offers at most `bits / 2` bits of collision resistance and `bits` bits of preimage
+    /// resistance, regardless of the width it was truncated from:
+    /// don't go below 128 bits where collisions matter (content addressing, signatures, deduplication),
+    /// nor below 64 bits for anything at all.
+    ///
+    /// Returns `Err(BlakeError::BadLength)`, leaving the state untouched, if `bits` is 0 or above `hashbitlen`,
+    /// or if `hashval` is too short.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(all(feature = "width-256", feature = "width-512"))] {
+    /// let full      = Blake::new(512).unwrap().chain(b"The lazy fox jumps over the lazy dog.").finalise_vec();
+    /// let mut short = [0; 16];
+    /// Blake::new(512).unwrap().chain(b"The lazy fox jumps over the lazy dog.").finalise_truncated(128, &mut short).unwrap();
+    /// assert!(full[..16] != short);
+    ///
+    /// let mut odd = [0; 2];
+    /// Blake::new(512).unwrap().chain(b"The lazy fox jumps over the lazy dog.").finalise_truncated(12, &mut odd).unwrap();
+    /// assert_eq!(odd[1] & 0x0F, 0);
+    ///
+    /// assert!(Blake::new(256).unwrap().finalise_truncated(257, &mut [0; 64]).is_err());
+    /// assert!(Blake::new(256).unwrap().finalise_truncated(128, &mut [0; 15]).is_err());
+    /// # }
+    /// ```
+    pub fn finalise_truncated(&mut self, bits: u32, hashval: &mut [u8]) -> Result<()> {
+        let trailer = truncation_trailer(self.hashbitlen, bits, hashval.len())?;
+        self.update(trailer);
+
+        let mut full = [0; 64];
+        self.finalise(&mut full);
+        truncate_digest(&full, bits, hashval);
+        Ok(())
+    }
+
+    /// Reset the state to the one freshly returned from `Blake::new()`, discarding all hashed data and the salt.
+    ///
+    /// With the `zeroize` feature, or if the state was created with `Blake::new_locked()`, the state is zeroed before
+    /// being reinitialised.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-256")] {
+    /// let mut result_reused = [0; 32];
+    /// let mut result_fresh  = [0; 32];
+    ///
+    /// let mut state = Blake::new(256).unwrap();
+    /// state.add_salt(b"Proletarians of ").unwrap();
+    /// state.update(b"Cleartext secret");
+    /// state.reset();
+    ///
+    /// state.update(b"The lazy fox jumps over the lazy dog.");
+    /// state.finalise(&mut result_reused);
+    ///
+    /// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result_fresh).unwrap();
+    /// assert_eq!(result_reused, result_fresh);
+    /// # }
+    /// ```
+    pub fn reset(&mut self) {
+        if cfg!(feature = "zeroize") || self.locked {
+            native::zero_hash_state(self.raw_state);
+        }
+        unsafe {
+            native::BLAKE_Hash_Init(self.raw_state, self.hashbitlen);
+        }
+    }
 }
 
 /// The `Write` implementation updates the state with the provided data.
@@ -377,6 +1497,7 @@ impl Blake {
 /// # use std::fs::File;
 /// # use blake::Blake;
 /// # use std::io;
+/// # #[cfg(feature = "width-256")] {
 /// let mut state = Blake::new(256).unwrap();
 /// io::copy(&mut File::open("LICENSE").unwrap(), &mut state).unwrap();
 ///
@@ -387,6 +1508,7 @@ impl Blake {
 ///                 0x06, 0xA4, 0xDC, 0x1C, 0xFD, 0x1B, 0x45, 0x62,
 ///                 0xA4, 0xBD, 0x35, 0x25, 0x76, 0x9B, 0x97, 0xF1,
 ///                 0x9B, 0x21, 0xC8, 0xDF, 0xDC, 0x4A, 0x80, 0xB1]);
+/// # }
 /// ```
 impl io::Write for Blake {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
@@ -399,19 +1521,164 @@ impl io::Write for Blake {
     }
 }
 
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for Blake {
+    type Error = std::convert::Infallible;
+}
+
+/// The `embedded_io::Write` implementation updates the state with the provided data, as the `std::io::Write` one does.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::Blake;
+/// # use embedded_io::Write;
+/// # fn main() {
+/// let mut result_written = [0; 32];
+/// let mut result         = [0; 32];
+///
+/// let mut state = Blake::new(256).unwrap();
+/// state.write_all(b"The lazy fox jumps over the lazy dog.").unwrap();
+/// state.finalise(&mut result_written);
+///
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+/// assert_eq!(result_written, result);
+/// # }
+/// ```
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Write for Blake {
+    fn write(&mut self, buf: &[u8]) -> std::result::Result<usize, std::convert::Infallible> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::result::Result<(), std::convert::Infallible> {
+        Ok(())
+    }
+}
+
+// SAFETY: `raw_state` points to a `hashState` allocated for, and owned exclusively by, this `Blake`,
+// and freed from whichever thread drops it with the allocator it came from, which isn't thread-bound.
+// The `hashState` is plain data, with no thread affinity: the reference implementation only reads and writes
+// through the pointer it's passed, and has no global or thread-local state besides its constant tables.
+unsafe impl Send for Blake {}
+
+// SAFETY: every function mutating the `hashState` takes `&mut self`; those taking `&self`, like `Clone::clone()`
+// and `Blake::midstate()`, only read it, which is safe to do from multiple threads at once.
+unsafe impl Sync for Blake {}
+
+/// Cloning the state forks it: both copies can be updated and finalised independently.
+///
+/// A clone of a state created with `Blake::new_locked()` is also page-locked;
+/// this panics if the memory for it couldn't be locked.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::Blake;
+/// # #[cfg(feature = "width-256")] {
+/// let mut result_forked = [0; 32];
+/// let mut result_whole  = [0; 32];
+///
+/// let mut state = Blake::new(256).unwrap();
+/// state.update(b"The lazy fox ");
+///
+/// let mut fork = state.clone();
+/// fork.update(b"jumps over the lazy dog.");
+/// fork.finalise(&mut result_forked);
+///
+/// state.update(b"jumps over the lazy dog.");
+/// state.finalise(&mut result_whole);
+///
+/// assert_eq!(result_forked, result_whole);
+/// # }
+/// ```
+impl Clone for Blake {
+    fn clone(&self) -> Blake {
+        let raw_state = if self.locked {
+            native::malloc_locked_hash_state().expect("Failed to lock memory for cloned state")
+        } else {
+            native::malloc_hash_state()
+        };
+        native::copy_hash_state(self.raw_state, raw_state);
+
+        Blake {
+            raw_state,
+            hashbitlen: self.hashbitlen,
+            locked: self.locked,
+        }
+    }
+}
+
+/// Zeroising the state resets it, as in `Blake::reset()`.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Blake {
+    fn zeroize(&mut self) {
+        self.reset();
+    }
+}
+
 impl Drop for Blake {
     fn drop(&mut self) {
-        native::free_hash_state(&mut self.raw_state);
+        if self.locked {
+            native::free_locked_hash_state(&mut self.raw_state);
+        } else {
+            native::free_hash_state(&mut self.raw_state);
+        }
     }
 }
 
 
-impl Error for BlakeError {
-    fn description(&self) -> &str {
-        match self {
-            &BlakeError::Fail => "Generic BLAKE fail",
-            &BlakeError::BadHashbitlen => "Incorrect hashbitlen",
-        }
+impl BlakeError {
+    /// Get the `BlakeError` wrapped by the specified `io::Error`, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::BlakeError;
+    /// # use std::io;
+    /// assert_eq!(BlakeError::from_io_error(&io::Error::other(BlakeError::Cancelled)), Some(BlakeError::Cancelled));
+    /// assert_eq!(BlakeError::from_io_error(&io::Error::other("oh no")), None);
+    /// ```
+    pub fn from_io_error(err: &io::Error) -> Option<BlakeError> {
+        err.get_ref().and_then(|e| e.downcast_ref::<BlakeError>()).cloned()
+    }
+}
+
+/// None of the errors have a source.
+impl Error for BlakeError {}
+
+/// The `io::Error` wraps the `BlakeError`, which `BlakeError::from_io_error()` gets back, with kind:
+///
+///   * `io::ErrorKind::InvalidInput` for `BadHashbitlen` and `BadLength`,
+///   * `io::ErrorKind::InvalidData` for `BadEncoding` and `DigestMismatch`, and
+///   * `io::ErrorKind::Other` for the rest.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::{Blake, BlakeError};
+/// # use std::io;
+/// fn hash_stdin(hashbitlen: i32) -> io::Result<Vec<u8>> {
+///     let mut state = Blake::new(hashbitlen)?;
+///     io::copy(&mut io::empty(), &mut state)?;
+///     Ok(state.finalise_vec())
+/// }
+///
+/// let err = hash_stdin(420).unwrap_err();
+/// assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+/// assert_eq!(BlakeError::from_io_error(&err), Some(BlakeError::BadHashbitlen));
+/// ```
+impl From<BlakeError> for io::Error {
+    fn from(err: BlakeError) -> io::Error {
+        let kind = match err {
+            BlakeError::BadHashbitlen | BlakeError::BadLength => io::ErrorKind::InvalidInput,
+            BlakeError::BadEncoding | BlakeError::DigestMismatch => io::ErrorKind::InvalidData,
+            BlakeError::Fail | BlakeError::LockFailed | BlakeError::SerialisationFailed | BlakeError::Cancelled | BlakeError::BackendMismatch => {
+                io::ErrorKind::Other
+            }
+        };
+        io::Error::new(kind, err)
     }
 }
 
@@ -428,7 +1695,17 @@ impl From<i32> for BlakeError {
 }
 
 impl fmt::Display for BlakeError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            BlakeError::Fail => "Generic BLAKE fail",
+            BlakeError::BadHashbitlen => "Incorrect hashbitlen",
+            BlakeError::BadEncoding => "Malformed digest string",
+            BlakeError::LockFailed => "Failed to lock state memory",
+            BlakeError::SerialisationFailed => "Failed to serialise value",
+            BlakeError::Cancelled => "Operation cancelled",
+            BlakeError::DigestMismatch => "Digest mismatch",
+            BlakeError::BackendMismatch => "Backends disagreed",
+            BlakeError::BadLength => "Invalid length",
+        })
     }
 }
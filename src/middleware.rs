@@ -0,0 +1,349 @@
+//! [tower](https://docs.rs/tower) middleware validating request `Content-Digest` headers and adding them to responses.
+//!
+//! `ContentDigestLayer` wraps a service handling `http` requests, and:
+//!
+//!   * if a request has a `Content-Digest` header with a BLAKE digest, validates its body as it's streamed,
+//!     failing the body with an error wrapping `BlakeError::DigestMismatch` at the end if it doesn't match
+//!     (the strongest BLAKE digest is checked if there are multiple; requests with only other algorithms are passed
+//!     through as-is),
+//!   * responds `400 Bad Request` without calling the service if the header is malformed, and
+//!   * adds a `Content-Digest` header to responses that don't have one.
+//!
+//! Since the header has to precede the body, responses are buffered in memory.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::middleware::{ContentDigestLayer, ValidatingBody};
+//! # use http::{Request, Response, StatusCode};
+//! # use std::task::{Context, Poll, Waker};
+//! # use tower::{Layer, Service, service_fn};
+//! # use std::convert::Infallible;
+//! # use http_body_util::Full;
+//! # use std::future::Future;
+//! # use bytes::Bytes;
+//! # use std::future;
+//! # fn main() {
+//! let mut service = ContentDigestLayer::new(256).unwrap().layer(service_fn(|_: Request<ValidatingBody<Full<Bytes>>>| {
+//!     future::ready(Ok::<_, Infallible>(Response::new(Full::new(Bytes::from_static(b"The lazy fox jumps over the lazy dog.")))))
+//! }));
+//!
+//! let mut cx = Context::from_waker(Waker::noop());
+//! let mut response = Box::pin(service.call(Request::new(Full::new(Bytes::new()))));
+//! let response = loop {
+//!     if let Poll::Ready(response) = response.as_mut().poll(&mut cx) {
+//!         break response.unwrap();
+//!     }
+//! };
+//! assert_eq!(response.headers()["content-digest"], "blake-256=:8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=:");
+//!
+//! let request = Request::builder().header("content-digest", "blake-256=:not base64:").body(Full::new(Bytes::new())).unwrap();
+//! let mut response = Box::pin(service.call(request));
+//! let response = loop {
+//!     if let Poll::Ready(response) = response.as_mut().poll(&mut cx) {
+//!         break response.unwrap();
+//!     }
+//! };
+//! assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+//! # }
+//! ```
+
+
+use super::{Blake, BlakeError, Result, digest_size, constant_time_eq};
+use super::content_digest;
+use http::{HeaderName, HeaderValue, Request, Response, StatusCode};
+use http::response::Parts;
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::Full;
+use tower_layer::Layer;
+use tower_service::Service;
+use std::task::{Context, Poll};
+use std::future::Future;
+use std::error::Error;
+use std::pin::Pin;
+use bytes::{Buf, Bytes};
+
+
+/// The error type of the middleware's service and request bodies.
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// A `Layer` wrapping services with `ContentDigestService`.
+#[derive(Debug, Clone, Copy)]
+pub struct ContentDigestLayer {
+    hashbitlen: i32,
+}
+
+/// The service described in the module-level documentation.
+#[derive(Debug, Clone)]
+pub struct ContentDigestService<S> {
+    inner: S,
+    hashbitlen: i32,
+}
+
+/// A request body validated against the digest from its `Content-Digest` header, if any.
+pub struct ValidatingBody<B> {
+    inner: B,
+    state: Option<Blake>,
+    expected: Vec<u8>,
+    mismatch: bool,
+}
+
+/// The future returned by `ContentDigestService`.
+pub struct ResponseFuture<F, B> {
+    state: ResponseState<F, B>,
+    hashbitlen: i32,
+}
+
+enum ResponseState<F, B> {
+    Rejected(Option<Response<Full<Bytes>>>),
+    Calling(Pin<Box<F>>),
+    Buffering(Option<Parts>, B, Vec<u8>),
+}
+
+const CONTENT_DIGEST: &str = "content-digest";
+
+
+impl ContentDigestLayer {
+    /// Create a layer adding `Content-Digest` headers with the hash function of the specified length to responses.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`.
+    pub fn new(hashbitlen: i32) -> Result<ContentDigestLayer> {
+        digest_size(hashbitlen)?;
+        Ok(ContentDigestLayer { hashbitlen })
+    }
+}
+
+impl<S> Layer<S> for ContentDigestLayer {
+    type Service = ContentDigestService<S>;
+
+    fn layer(&self, inner: S) -> ContentDigestService<S> {
+        ContentDigestService {
+            inner,
+            hashbitlen: self.hashbitlen,
+        }
+    }
+}
+
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for ContentDigestService<S>
+    where S: Service<Request<ValidatingBody<ReqBody>>, Response = Response<ResBody>>,
+          S::Error: Into<BoxError>,
+          ReqBody: Body + Unpin,
+          ReqBody::Error: Into<BoxError>,
+          ResBody: Body + Unpin,
+          ResBody::Error: Into<BoxError>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = BoxError;
+    type Future = ResponseFuture<S::Future, ResBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), BoxError>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> ResponseFuture<S::Future, ResBody> {
+        let expected = match request.headers().get(CONTENT_DIGEST) {
+            None => None,
+            Some(value) => {
+                match value.to_str().ok().and_then(|v| content_digest::parse(v).ok()) {
+                    Some(digests) => digests.into_iter().max_by_key(|&(hashbitlen, _)| hashbitlen),
+                    None => {
+                        let mut response = Response::new(Full::new(Bytes::from_static(b"Malformed Content-Digest header")));
+                        *response.status_mut() = StatusCode::BAD_REQUEST;
+                        return ResponseFuture {
+                            state: ResponseState::Rejected(Some(response)),
+                            hashbitlen: self.hashbitlen,
+                        };
+                    }
+                }
+            }
+        };
+
+        let request = request.map(|body| match expected {
+            Some((hashbitlen, expected)) => ValidatingBody::new(body, hashbitlen, &expected).expect("parsed digests are valid"),
+            None => ValidatingBody::unvalidated(body),
+        });
+        ResponseFuture {
+            state: ResponseState::Calling(Box::pin(self.inner.call(request))),
+            hashbitlen: self.hashbitlen,
+        }
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F, B>
+    where F: Future<Output = std::result::Result<Response<B>, E>>,
+          E: Into<BoxError>,
+          B: Body + Unpin,
+          B::Error: Into<BoxError>
+{
+    type Output = std::result::Result<Response<Full<Bytes>>, BoxError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            let next = match this.state {
+                ResponseState::Rejected(ref mut response) => {
+                    return Poll::Ready(Ok(response.take().expect("future polled after completion")));
+                }
+                ResponseState::Calling(ref mut call) => {
+                    match call.as_mut().poll(cx) {
+                        Poll::Ready(Ok(response)) => {
+                            let (parts, body) = response.into_parts();
+                            ResponseState::Buffering(Some(parts), body, vec![])
+                        }
+                        Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                ResponseState::Buffering(ref mut parts, ref mut body, ref mut data) => {
+                    match Pin::new(body).poll_frame(cx) {
+                        Poll::Ready(Some(Ok(frame))) => {
+                            if let Ok(mut chunk) = frame.into_data() {
+                                while chunk.has_remaining() {
+                                    let len = chunk.chunk().len();
+                                    data.extend_from_slice(chunk.chunk());
+                                    chunk.advance(len);
+                                }
+                            }
+                            continue;
+                        }
+                        Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(e.into())),
+                        Poll::Ready(None) => {
+                            let mut parts = parts.take().expect("future polled after completion");
+                            if !parts.headers.contains_key(CONTENT_DIGEST) {
+                                let value = content_digest::header_value(this.hashbitlen, data).expect("valid hashbitlen");
+                                parts.headers.insert(HeaderName::from_static(CONTENT_DIGEST),
+                                                     HeaderValue::from_str(&value).expect("base64 is a valid header value"));
+                            }
+                            return Poll::Ready(Ok(Response::from_parts(parts, Full::new(Bytes::from(std::mem::take(data))))));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+            };
+            this.state = next;
+        }
+    }
+}
+
+
+impl<B: Body + Unpin> ValidatingBody<B> {
+    /// Wrap the specified body, expecting it to hash to the specified digest with the hash function of the specified
+    /// length.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    /// and `Err(BlakeError::BadLength)` if the expected digest isn't of the hash function's size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::middleware::ValidatingBody;
+    /// # use std::task::{Context, Poll, Waker};
+    /// # use http_body_util::Full;
+    /// # use blake::BlakeError;
+    /// # use http_body::Body;
+    /// # use std::pin::Pin;
+    /// # use bytes::Bytes;
+    /// # fn main() {
+    /// let expected = blake::b256::hash(b"The lazy fox jumps over the lazy dog.");
+    /// let mut body = ValidatingBody::new(Full::new(Bytes::from_static(b"The lazy dog jumps over the lazy fox.")),
+    ///                                    256,
+    ///                                    expected.as_bytes())
+    ///     .unwrap();
+    ///
+    /// let mut cx = Context::from_waker(Waker::noop());
+    /// assert!(match Pin::new(&mut body).poll_frame(&mut cx) {
+    ///     Poll::Ready(Some(Ok(_))) => true,
+    ///     _ => false,
+    /// });
+    /// match Pin::new(&mut body).poll_frame(&mut cx) {
+    ///     Poll::Ready(Some(Err(e))) => assert_eq!(e.downcast_ref::<BlakeError>(), Some(&BlakeError::DigestMismatch)),
+    ///     _ => panic!(),
+    /// }
+    /// # }
+    /// ```
+    pub fn new(inner: B, hashbitlen: i32, expected: &[u8]) -> Result<ValidatingBody<B>> {
+        if digest_size(hashbitlen)? != expected.len() {
+            return Err(BlakeError::BadLength);
+        }
+
+        Ok(ValidatingBody {
+            inner,
+            state: Some(Blake::new(hashbitlen)?),
+            expected: expected.to_vec(),
+            mismatch: false,
+        })
+    }
+
+    /// Wrap the specified body without validating it.
+    pub fn unvalidated(inner: B) -> ValidatingBody<B> {
+        ValidatingBody {
+            inner,
+            state: None,
+            expected: vec![],
+            mismatch: false,
+        }
+    }
+
+    fn check(&mut self) {
+        if let Some(mut state) = self.state.take() {
+            let mut digest = vec![0; self.expected.len()];
+            state.finalise(&mut digest);
+            self.mismatch = !constant_time_eq(&digest, &self.expected);
+        }
+    }
+}
+
+impl<B> Body for ValidatingBody<B>
+    where B: Body + Unpin,
+          B::Error: Into<BoxError>
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<std::result::Result<Frame<Bytes>, BoxError>>> {
+        let this = &mut *self;
+        if this.mismatch {
+            this.mismatch = false;
+            return Poll::Ready(Some(Err(Box::new(BlakeError::DigestMismatch))));
+        }
+
+        match Pin::new(&mut this.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => {
+                let state = &mut this.state;
+                let frame = frame.map_data(|mut data| {
+                    let data = data.copy_to_bytes(data.remaining());
+                    if let Some(ref mut state) = *state {
+                        state.update(&data);
+                    }
+                    data
+                });
+                // The consumer may stop polling once is_end_stream() is true, so check now,
+                // and report a mismatch on the next poll
+                if this.inner.is_end_stream() {
+                    this.check();
+                }
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e.into()))),
+            Poll::Ready(None) => {
+                this.check();
+                if this.mismatch {
+                    this.mismatch = false;
+                    Poll::Ready(Some(Err(Box::new(BlakeError::DigestMismatch))))
+                } else {
+                    Poll::Ready(None)
+                }
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.state.is_none() && !self.mismatch && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
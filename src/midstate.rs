@@ -0,0 +1,298 @@
+use super::{Blake, BlakeError, Result, digest_size, native};
+
+
+/// A hash state's chaining value, salt, and counter after a whole amount of blocks, to be exchanged and resumed from.
+///
+/// Anyone holding a midstate can hash further data as if they had hashed the data it was taken after,
+/// without knowing that data, so it's no more secret than the digest; unless the salt is secret, of course.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::{Blake, Midstate};
+/// # #[cfg(feature = "width-256")] {
+/// let mut state = Blake::new(256).unwrap();
+/// state.update(&[0xA5; 128]);
+/// let midstate = state.midstate().unwrap();
+///
+/// let received = Midstate::from_bytes(256, &midstate.to_bytes()).unwrap();
+/// let mut resumed = Blake::from_midstate(&received);
+/// resumed.update(b"The lazy fox jumps over the lazy dog.");
+///
+/// let mut result_resumed = [0; 32];
+/// let mut result         = [0; 32];
+/// resumed.finalise(&mut result_resumed);
+///
+/// let mut data = vec![0xA5; 128];
+/// data.extend_from_slice(b"The lazy fox jumps over the lazy dog.");
+/// blake::hash(256, &data, &mut result).unwrap();
+/// assert_eq!(result_resumed, result);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Midstate {
+    hashbitlen: i32,
+    chain: [u64; 8],
+    salt: [u64; 4],
+    counter: u128,
+}
+
+
+impl Midstate {
+    /// Get the length of the hash function the midstate is of.
+    pub fn hashbitlen(&self) -> i32 {
+        self.hashbitlen
+    }
+
+    /// Get the amount of message bits hashed into the midstate.
+    pub fn counter(&self) -> u128 {
+        self.counter
+    }
+
+    /// Serialise the midstate as the chaining value's, the salt's, and the counter's words, big-endian:
+    /// 56 bytes for BLAKE-224 and BLAKE-256, 112 bytes for BLAKE-384 and BLAKE-512.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(112);
+        if self.hashbitlen < 384 {
+            for word in self.chain.iter().chain(self.salt.iter()) {
+                ret.extend_from_slice(&(*word as u32).to_be_bytes());
+            }
+            ret.extend_from_slice(&(self.counter as u64).to_be_bytes());
+        } else {
+            for word in self.chain.iter().chain(self.salt.iter()) {
+                ret.extend_from_slice(&word.to_be_bytes());
+            }
+            ret.extend_from_slice(&self.counter.to_be_bytes());
+        }
+        ret
+    }
+
+    /// Parse a midstate of the hash function of the specified length, as serialised by `to_bytes()`.
+    ///
+    /// Returns:
+    ///
+    ///   * `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    ///   * `Err(BlakeError::BadEncoding)` if the data is of the wrong length,
+    ///     or the counter isn't a whole amount of blocks, or
+    ///   * `Ok(Midstate)` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::{BlakeError, Midstate};
+    /// # #[cfg(all(feature = "width-256", feature = "width-512"))] {
+    /// assert_eq!(Midstate::from_bytes(512, &[0; 56]), Err(BlakeError::BadEncoding));
+    /// assert_eq!(Midstate::from_bytes(256, &[0; 56]).unwrap().counter(), 0);
+    /// # }
+    /// ```
+    pub fn from_bytes(hashbitlen: i32, data: &[u8]) -> Result<Midstate> {
+        digest_size(hashbitlen)?;
+        let (word_size, block_bits) = if hashbitlen < 384 { (4, 512) } else { (8, 1024) };
+        if data.len() != word_size * 14 {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        let mut words = data.chunks(word_size).map(|bytes| bytes.iter().fold(0u128, |acc, &b| acc << 8 | b as u128));
+        let mut chain = [0; 8];
+        let mut salt = [0; 4];
+        for word in chain.iter_mut().chain(salt.iter_mut()) {
+            *word = words.next().expect("length checked") as u64;
+        }
+        let counter = data[word_size * 12..].iter().fold(0u128, |acc, &b| acc << 8 | b as u128);
+        if counter % block_bits != 0 {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        Ok(Midstate {
+            hashbitlen,
+            chain,
+            salt,
+            counter,
+        })
+    }
+}
+
+/// Zeroising the midstate sets its chaining value, salt, and counter to `0`, keeping the length.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Midstate {
+    fn zeroize(&mut self) {
+        self.chain.zeroize();
+        self.salt.zeroize();
+        self.counter.zeroize();
+    }
+}
+
+
+impl Blake {
+    /// Capture the state's midstate, or `None` if a partial block has been hashed.
+    ///
+    /// Blocks are 64 bytes long for BLAKE-224 and BLAKE-256, and 128 bytes long for BLAKE-384 and BLAKE-512.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-512")] {
+    /// let mut state = Blake::new(512).unwrap();
+    /// assert_eq!(state.midstate().unwrap().counter(), 0);
+    ///
+    /// state.update(&[0; 100]);
+    /// assert_eq!(state.midstate(), None);
+    ///
+    /// state.update(&[0; 28]);
+    /// assert_eq!(state.midstate().unwrap().counter(), 1024);
+    /// # }
+    /// ```
+    pub fn midstate(&self) -> Option<Midstate> {
+        native::get_midstate(self.raw_state).map(|(chain, salt, counter)| {
+            Midstate {
+                hashbitlen: self.hashbitlen,
+                chain,
+                salt,
+                counter,
+            }
+        })
+    }
+
+    /// Get a copy of the current chaining value, the `h32` words for BLAKE-224 and BLAKE-256, zero-extended,
+    /// or the `h64` words for BLAKE-384 and BLAKE-512.
+    ///
+    /// This is the state after the last compressed block; data buffered since isn't reflected until it fills a block.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-256")] {
+    /// let mut state = Blake::new(256).unwrap();
+    /// assert_eq!(state.chain_value()[0], 0x6A09E667);
+    ///
+    /// state.update(&[0; 100]);
+    /// assert_eq!((state.counter(), state.buffered_len()), (512, 36));
+    /// assert!(state.chain_value()[0] != 0x6A09E667);
+    /// # }
+    /// ```
+    pub fn chain_value(&self) -> [u64; 8] {
+        native::get_parts(self.raw_state).0
+    }
+
+    /// Get a copy of the salt, the `salt32` words, zero-extended, or the `salt64` words; all zero if unsalted.
+    pub fn salt(&self) -> [u64; 4] {
+        native::get_parts(self.raw_state).1
+    }
+
+    /// Get the counter, the amount of message bits in the blocks compressed so far, excluding the buffered data.
+    pub fn counter(&self) -> u128 {
+        native::get_parts(self.raw_state).2
+    }
+
+    /// Get the amount of bytes buffered, less than a block, waiting to be compressed.
+    pub fn buffered_len(&self) -> usize {
+        native::get_parts(self.raw_state).3
+    }
+
+    /// Create a new hash state resuming from the specified midstate.
+    #[must_use]
+    pub fn from_midstate(midstate: &Midstate) -> Blake {
+        let state = Blake::new(midstate.hashbitlen).expect("midstates have valid hashbitlens");
+        native::set_midstate(state.raw_state, &midstate.chain, &midstate.salt, midstate.counter);
+        state
+    }
+
+    /// Create a new hash state with the specified initial value in place of the standard IV,
+    /// for protocols personalising the function by it.
+    ///
+    /// **This isn't BLAKE**: the digests are those of a non-standard variant, unrelated to the standard ones,
+    /// and nothing but other implementations with the same IV will reproduce them. Prefer `Blake::new_domain()`
+    /// or a salt for domain separation.
+    ///
+    /// The IV is 8 words, each of which must fit in 32 bits for BLAKE-224 and BLAKE-256. A salt can still be added.
+    ///
+    /// Returns:
+    ///
+    ///   * `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    ///   * `Err(BlakeError::BadLength)` if the IV has the wrong amount of words, or one doesn't fit, or
+    ///   * `Ok(Blake)` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::Blake;
+    /// # #[cfg(feature = "width-256")] {
+    /// let iv = [0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19];
+    /// let mut result_standard = [0; 32];
+    /// let mut result_custom   = [0; 32];
+    ///
+    /// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result_standard).unwrap();
+    ///
+    /// let mut state = Blake::new_nonstandard_iv(256, &[iv[0] ^ 1, iv[1], iv[2], iv[3], iv[4], iv[5], iv[6], iv[7]]).unwrap();
+    /// state.update(b"The lazy fox jumps over the lazy dog.");
+    /// state.finalise(&mut result_custom);
+    ///
+    /// assert!(result_custom != result_standard);
+    /// # }
+    /// ```
+    pub fn new_nonstandard_iv(hashbitlen: i32, iv: &[u64]) -> Result<Blake> {
+        Blake::from_parts(hashbitlen, iv, &[0; 4], 0)
+    }
+
+    /// Create a new hash state from an explicit chaining value, salt, and counter, as persisted by another
+    /// implementation, with no data buffered.
+    ///
+    /// The chaining value is 8 words and the salt 4; for BLAKE-224 and BLAKE-256 each word must fit in 32 bits,
+    /// and the counter, the amount of message bits hashed so far, in 64. The counter must be a whole amount of blocks,
+    /// as in `Blake::midstate()`; feed any partial block to `update()` afterwards.
+    ///
+    /// Nothing checks that the parts came from a real hash state: a chaining value that isn't the IV or the result of
+    /// compressing blocks, or a counter that doesn't match the amount of data hashed, yields a valid-looking,
+    /// but non-standard digest.
+    ///
+    /// Returns:
+    ///
+    ///   * `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    ///   * `Err(BlakeError::BadLength)` if the chaining value or salt has the wrong amount of words,
+    ///     a word or the counter doesn't fit, or the counter isn't a whole amount of blocks, or
+    ///   * `Ok(Blake)` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::{Blake, BlakeError};
+    /// # #[cfg(feature = "width-256")] {
+    /// let iv = [0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19];
+    /// let mut result_parts = [0; 32];
+    /// let mut result       = [0; 32];
+    ///
+    /// let mut state = Blake::from_parts(256, &iv, &[0; 4], 0).unwrap();
+    /// state.update(b"The lazy fox jumps over the lazy dog.");
+    /// state.finalise(&mut result_parts);
+    ///
+    /// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+    /// assert_eq!(result_parts, result);
+    ///
+    /// assert_eq!(Blake::from_parts(256, &iv[..7], &[0; 4], 0).err(), Some(BlakeError::BadLength));
+    /// assert_eq!(Blake::from_parts(256, &iv, &[0; 4], 100).err(), Some(BlakeError::BadLength));
+    /// # }
+    /// ```
+    pub fn from_parts(hashbitlen: i32, chain: &[u64], salt: &[u64], counter: u128) -> Result<Blake> {
+        digest_size(hashbitlen)?;
+        let (word_max, counter_max, block_bits) = if hashbitlen < 384 {
+            (u32::MAX as u64, u64::MAX as u128, 512)
+        } else {
+            (u64::MAX, u128::MAX, 1024)
+        };
+        if chain.len() != 8 || salt.len() != 4 || chain.iter().chain(salt).any(|&word| word > word_max) || counter > counter_max ||
+           !counter.is_multiple_of(block_bits) {
+            return Err(BlakeError::BadLength);
+        }
+
+        let mut chain_words = [0; 8];
+        let mut salt_words = [0; 4];
+        chain_words.copy_from_slice(chain);
+        salt_words.copy_from_slice(salt);
+
+        let state = Blake::new(hashbitlen)?;
+        native::set_midstate(state.raw_state, &chain_words, &salt_words, counter);
+        Ok(state)
+    }
+}
@@ -0,0 +1,408 @@
+//! Merkle Mountain Ranges: append-only authenticated lists, over BLAKE-256.
+//!
+//! An MMR is a list of perfect binary Merkle trees ("mountains") of strictly decreasing heights, one for every bit
+//! set in the amount of leaves. Appending a leaf adds a mountain of height 0, then merges the two rightmost mountains
+//! for as long as they're of the same height, so nodes, once stored, never change.
+//!
+//! Nodes are stored in post-order (every node after both its children), in a `NodeStore`.
+//! The root is committed to by hashing the amount of leaves with the mountains' peaks, "bagged" from right to left.
+//!
+//! |node  |digest                                                                              |
+//! |------|------------------------------------------------------------------------------------|
+//! |leaf  |BLAKE-256 of `0x00`, the data                                                       |
+//! |parent|BLAKE-256 of `0x01`, the left child, the right child                                |
+//! |bag   |the rightmost peak; then BLAKE-256 of `0x02`, the next peak, the bag                |
+//! |root  |BLAKE-256 of `0x03`, the amount of leaves as a little-endian `u64`, the bag (if any)|
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::mmr::Mmr;
+//! let mut mmr = Mmr::new(vec![]).unwrap();
+//! for entry in &["alice", "bob", "carol", "dave", "eve"] {
+//!     mmr.push(entry.as_bytes()).unwrap();
+//! }
+//! assert_eq!(mmr.leaves(), 5);
+//! assert_eq!(mmr.size(), 8);
+//!
+//! let root = mmr.root().unwrap();
+//! let proof = mmr.proof(2).unwrap();
+//! assert!(proof.verify(&root, b"carol"));
+//! assert!(!proof.verify(&root, b"mallory"));
+//!
+//! // Old proofs don't verify against new roots
+//! mmr.push(b"frank").unwrap();
+//! assert!(!proof.verify(&mmr.root().unwrap(), b"carol"));
+//! assert!(mmr.proof(2).unwrap().verify(&mmr.root().unwrap(), b"carol"));
+//! ```
+
+
+use super::b256::{self, Digest, Hasher};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+
+/// Storage for the nodes of an `Mmr`, addressed by their position in post-order.
+pub trait NodeStore {
+    /// Get the amount of nodes stored.
+    fn len(&self) -> io::Result<u64>;
+
+    /// Check whether no nodes are stored.
+    fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Get the node at the specified position, which is less than `len()`.
+    fn get(&self, position: u64) -> io::Result<Digest>;
+
+    /// Store the specified nodes after the ones already stored.
+    fn append(&mut self, nodes: &[Digest]) -> io::Result<()>;
+}
+
+/// A `NodeStore` keeping the nodes in a file, as their concatenation.
+#[derive(Debug)]
+pub struct FileStore {
+    file: File,
+}
+
+/// A Merkle Mountain Range, as described in the module-level documentation.
+#[derive(Debug)]
+pub struct Mmr<S> {
+    store: S,
+    size: u64,
+    leaves: u64,
+}
+
+/// A proof of a leaf's inclusion in an MMR with a specific root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    /// The index of the leaf
+    pub leaf_index: u64,
+    /// The amount of leaves in the MMR
+    pub leaves: u64,
+    /// The siblings of the nodes on the path from the leaf to its mountain's peak, from the bottom
+    pub siblings: Vec<Digest>,
+    /// The other mountains' peaks, from the left
+    pub peaks: Vec<Digest>,
+}
+
+/// The most leaves an MMR can have, so its size fits in a `u64`.
+pub const MAX_LEAVES: u64 = (1 << 63) - 1;
+
+const TAG_LEAF: u8 = 0x00;
+const TAG_PARENT: u8 = 0x01;
+const TAG_BAG: u8 = 0x02;
+const TAG_ROOT: u8 = 0x03;
+
+
+impl NodeStore for Vec<Digest> {
+    fn len(&self) -> io::Result<u64> {
+        Ok(Vec::len(self) as u64)
+    }
+
+    fn get(&self, position: u64) -> io::Result<Digest> {
+        self[..].get(position as usize).cloned().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "MMR position out of range"))
+    }
+
+    fn append(&mut self, nodes: &[Digest]) -> io::Result<()> {
+        self.extend_from_slice(nodes);
+        Ok(())
+    }
+}
+
+impl FileStore {
+    /// Open the store in the file at the specified path, creating it if it doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::mmr::{FileStore, Mmr};
+    /// # use std::io::{self, Write};
+    /// # use std::fs;
+    /// let path = std::env::temp_dir().join("blake-rs-doctest-mmr");
+    /// # let _ = fs::remove_file(&path);
+    /// let mut mmr = Mmr::new(FileStore::open(&path).unwrap()).unwrap();
+    /// mmr.push(b"alice").unwrap();
+    /// mmr.push(b"bob").unwrap();
+    /// let root = mmr.root().unwrap();
+    /// drop(mmr);
+    ///
+    /// let mmr = Mmr::new(FileStore::open(&path).unwrap()).unwrap();
+    /// assert_eq!(mmr.leaves(), 2);
+    /// assert_eq!(mmr.root().unwrap(), root);
+    /// drop(mmr);
+    ///
+    /// // As if appending was interrupted partway through a node
+    /// fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(&[0; 7]).unwrap();
+    /// assert_eq!(Mmr::new(FileStore::open(&path).unwrap()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FileStore> {
+        Ok(FileStore { file: OpenOptions::new().read(true).append(true).create(true).open(path)? })
+    }
+}
+
+impl NodeStore for FileStore {
+    /// Returns an `io::Error` of kind `InvalidData` if the file ends with a partly written node,
+    /// as when appending was interrupted.
+    fn len(&self) -> io::Result<u64> {
+        let len = self.file.metadata()?.len();
+        if len % b256::DIGEST_SIZE as u64 != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Partly written MMR node"));
+        }
+        Ok(len / b256::DIGEST_SIZE as u64)
+    }
+
+    fn get(&self, position: u64) -> io::Result<Digest> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(position * b256::DIGEST_SIZE as u64))?;
+
+        let mut digest = [0; b256::DIGEST_SIZE];
+        file.read_exact(&mut digest)?;
+        Ok(Digest::from_bytes(digest))
+    }
+
+    fn append(&mut self, nodes: &[Digest]) -> io::Result<()> {
+        let data: Vec<u8> = nodes.iter().flat_map(|node| node.as_bytes().iter().cloned()).collect();
+        self.file.write_all(&data)
+    }
+}
+
+
+impl<S: NodeStore> Mmr<S> {
+    /// Open the MMR whose nodes are in the specified store.
+    ///
+    /// Returns an `io::Error` of kind `InvalidData` if the amount of nodes stored isn't the size of any MMR with at most
+    /// `MAX_LEAVES` leaves, as when appending was interrupted, or any error encountered getting it,
+    /// like `FileStore`'s for a partly written node.
+    pub fn new(store: S) -> io::Result<Mmr<S>> {
+        let size = store.len()?;
+        let leaves = leaves_for_size(size).filter(|&leaves| leaves <= MAX_LEAVES)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Not a valid MMR size"))?;
+        Ok(Mmr {
+            store,
+            size,
+            leaves,
+        })
+    }
+
+    /// Append a leaf with the specified data, returning its index.
+    ///
+    /// Returns an `io::Error` of kind `InvalidInput` if the MMR already has `MAX_LEAVES` leaves,
+    /// or any error encountered storing the new nodes, or getting the ones they're merged with.
+    pub fn push(&mut self, data: &[u8]) -> io::Result<u64> {
+        if self.leaves == MAX_LEAVES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "MMR full"));
+        }
+
+        let mut nodes = vec![leaf_hash(data)];
+
+        // The new leaf is merged with the peak of the mountain to its left once for every trailing one in the index
+        let mut position = self.size;
+        let mut height = 0;
+        while (self.leaves >> height) & 1 == 1 {
+            let left = self.store.get(position + 1 - (2 << height))?;
+            let parent = parent_hash(&left, &nodes[nodes.len() - 1]);
+            nodes.push(parent);
+            position += 1;
+            height += 1;
+        }
+
+        self.store.append(&nodes)?;
+        self.size += nodes.len() as u64;
+        self.leaves += 1;
+        Ok(self.leaves - 1)
+    }
+
+    /// Get the commitment to the MMR's current state.
+    ///
+    /// Returns any error encountered getting the peaks.
+    pub fn root(&self) -> io::Result<Digest> {
+        let peaks = mountains(self.leaves).into_iter().map(|(_, peak, _)| self.store.get(peak)).collect::<io::Result<Vec<_>>>()?;
+        Ok(root_hash(self.leaves, &peaks))
+    }
+
+    /// Get a proof of the inclusion of the leaf with the specified index in the MMR with the current root.
+    ///
+    /// Returns an `io::Error` of kind `InvalidInput` if there's no such leaf, or any error encountered getting the
+    /// nodes.
+    pub fn proof(&self, leaf_index: u64) -> io::Result<Proof> {
+        if leaf_index >= self.leaves {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "MMR leaf index out of range"));
+        }
+
+        let mountains = mountains(self.leaves);
+        let (mountain, local_index) = mountain_of(&mountains, leaf_index);
+
+        let mut siblings = vec![];
+        let mut position = mountains[mountain].2 + leaf_position(local_index);
+        for height in 0..mountains[mountain].0 {
+            if (local_index >> height) & 1 == 0 {
+                siblings.push(self.store.get(position + (2 << height) - 1)?);
+                position += 2 << height;
+            } else {
+                siblings.push(self.store.get(position + 1 - (2 << height))?);
+                position += 1;
+            }
+        }
+
+        let mut peaks = vec![];
+        for (i, &(_, peak, _)) in mountains.iter().enumerate() {
+            if i != mountain {
+                peaks.push(self.store.get(peak)?);
+            }
+        }
+
+        Ok(Proof {
+            leaf_index,
+            leaves: self.leaves,
+            siblings,
+            peaks,
+        })
+    }
+
+    /// Get the amount of leaves.
+    pub fn leaves(&self) -> u64 {
+        self.leaves
+    }
+
+    /// Get the amount of nodes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get a reference to the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Unwrap the underlying store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+}
+
+impl Proof {
+    /// Check whether the proof shows the specified data is the leaf with the proof's index in the MMR with the
+    /// specified root.
+    ///
+    /// Proofs for MMRs with more than `MAX_LEAVES` leaves never verify.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::mmr::{Mmr, MAX_LEAVES};
+    /// let mut mmr = Mmr::new(vec![]).unwrap();
+    /// mmr.push(b"alice").unwrap();
+    ///
+    /// let mut proof = mmr.proof(0).unwrap();
+    /// proof.leaves = MAX_LEAVES + 1;
+    /// assert!(!proof.verify(&mmr.root().unwrap(), b"alice"));
+    /// ```
+    pub fn verify(&self, root: &Digest, data: &[u8]) -> bool {
+        if self.leaf_index >= self.leaves || self.leaves > MAX_LEAVES {
+            return false;
+        }
+
+        let mountains = mountains(self.leaves);
+        let (mountain, local_index) = mountain_of(&mountains, self.leaf_index);
+        if self.siblings.len() != mountains[mountain].0 as usize || self.peaks.len() != mountains.len() - 1 {
+            return false;
+        }
+
+        let mut node = leaf_hash(data);
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            node = if (local_index >> height) & 1 == 0 {
+                parent_hash(&node, sibling)
+            } else {
+                parent_hash(sibling, &node)
+            };
+        }
+
+        let mut peaks = self.peaks.clone();
+        peaks.insert(mountain, node);
+        root_hash(self.leaves, &peaks) == *root
+    }
+}
+
+
+fn leaf_hash(data: &[u8]) -> Digest {
+    let mut hasher = Hasher::new();
+    hasher.update([TAG_LEAF]);
+    hasher.update(data);
+    hasher.finalise()
+}
+
+fn parent_hash(left: &Digest, right: &Digest) -> Digest {
+    let mut hasher = Hasher::new();
+    hasher.update([TAG_PARENT]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalise()
+}
+
+fn root_hash(leaves: u64, peaks: &[Digest]) -> Digest {
+    let mut peaks = peaks.iter().rev();
+    let bag = peaks.next().map(|&last| {
+        peaks.fold(last, |bag, peak| {
+            let mut hasher = Hasher::new();
+            hasher.update([TAG_BAG]);
+            hasher.update(peak.as_bytes());
+            hasher.update(bag.as_bytes());
+            hasher.finalise()
+        })
+    });
+
+    let mut hasher = Hasher::new();
+    hasher.update([TAG_ROOT]);
+    hasher.update(leaves.to_le_bytes());
+    if let Some(bag) = bag {
+        hasher.update(bag.as_bytes());
+    }
+    hasher.finalise()
+}
+
+/// The height, the peak's position, and the first node's position of every mountain, from the left,
+/// for at most `MAX_LEAVES` leaves.
+fn mountains(leaves: u64) -> Vec<(u32, u64, u64)> {
+    let mut ret = vec![];
+    let mut start = 0;
+    for height in (0..63).rev() {
+        if (leaves >> height) & 1 == 1 {
+            let size = (2u64 << height) - 1;
+            ret.push((height, start + size - 1, start));
+            start += size;
+        }
+    }
+    ret
+}
+
+/// The index of the mountain containing the leaf, and the leaf's index in that mountain.
+fn mountain_of(mountains: &[(u32, u64, u64)], leaf_index: u64) -> (usize, u64) {
+    let mut first_leaf = 0;
+    for (i, &(height, _, _)) in mountains.iter().enumerate() {
+        if leaf_index < first_leaf + (1 << height) {
+            return (i, leaf_index - first_leaf);
+        }
+        first_leaf += 1 << height;
+    }
+    unreachable!()
+}
+
+/// The position of the leaf with the specified index in a single mountain.
+fn leaf_position(index: u64) -> u64 {
+    2 * index - index.count_ones() as u64
+}
+
+fn leaves_for_size(mut size: u64) -> Option<u64> {
+    let mut leaves = 0;
+    for height in (0..63).rev() {
+        let mountain = (2u64 << height) - 1;
+        if size >= mountain {
+            size -= mountain;
+            leaves += 1 << height;
+        }
+    }
+    if size == 0 { Some(leaves) } else { None }
+}
@@ -0,0 +1,157 @@
+//! Encoding of digests as [PGP word list](https://en.wikipedia.org/wiki/PGP_word_list) words,
+//! for reading fingerprints out loud.
+//!
+//! Bytes in even positions are encoded as two-syllable words, and bytes in odd positions as three-syllable words,
+//! so a dropped, repeated, or swapped word is noticeable. `encode()` appends two checksum words,
+//! encoding the first two bytes of BLAKE-256 of the data, so `decode()` also catches mistranscribed words.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::mnemonic;
+//! let fingerprint = [0xE5, 0x82, 0x94, 0xF2];
+//! let words = mnemonic::encode(&fingerprint);
+//! assert!(words.starts_with("topmost Istanbul Pluto vagabond "));
+//! assert_eq!(words.split(' ').count(), 6);
+//!
+//! assert_eq!(mnemonic::decode(&words.to_uppercase()), Ok(fingerprint.to_vec()));
+//! assert_eq!(mnemonic::decode(&words.replace("Pluto", "Oakland")), Err(blake::BlakeError::BadEncoding));
+//! ```
+
+
+use super::{BlakeError, Result};
+use super::stack::StackState;
+
+
+/// The amount of checksum words appended by `encode()`.
+pub const CHECKSUM_WORDS: usize = 2;
+
+
+/// Encode the data as space-separated words, followed by the checksum words.
+pub fn encode(data: &[u8]) -> String {
+    data.iter()
+        .chain(checksum(data).iter())
+        .enumerate()
+        .map(|(i, &byte)| word_list(i)[byte as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Decode words encoded with `encode()`, separated by any whitespace and of any case, and verify the checksum.
+///
+/// Returns `Err(BlakeError::BadEncoding)` if there are fewer words than the checksum's,
+/// if a word is not in the list for its position, or if the checksum doesn't match.
+pub fn decode(words: &str) -> Result<Vec<u8>> {
+    let mut ret = words.split_whitespace()
+        .enumerate()
+        .map(|(i, word)| {
+            let byte = word_list(i).iter().position(|candidate| candidate.eq_ignore_ascii_case(word))?;
+            Some(byte as u8)
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or(BlakeError::BadEncoding)?;
+    if ret.len() < CHECKSUM_WORDS {
+        return Err(BlakeError::BadEncoding);
+    }
+
+    let data_len = ret.len() - CHECKSUM_WORDS;
+    if checksum(&ret[..data_len])[..] != ret[data_len..] {
+        return Err(BlakeError::BadEncoding);
+    }
+    ret.truncate(data_len);
+    Ok(ret)
+}
+
+
+fn word_list(position: usize) -> &'static [&'static str; 256] {
+    if position.is_multiple_of(2) { &EVEN_WORDS } else { &ODD_WORDS }
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_WORDS] {
+    let mut digest = [0; 32];
+    let mut state = StackState::new(256).expect("256 is a valid hashbitlen");
+    state.update(data);
+    state.finalise(&mut digest).expect("the buffer fits the digest");
+
+    let mut ret = [0; CHECKSUM_WORDS];
+    ret.copy_from_slice(&digest[..CHECKSUM_WORDS]);
+    ret
+}
+
+
+/// The PGP word list's two-syllable words, for bytes in even positions.
+const EVEN_WORDS: [&str; 256] = ["aardvark", "absurd", "accrue", "acme", "adrift", "adult", "afflict", "ahead",
+                                 "aimless", "Algol", "allow", "alone", "ammo", "ancient", "apple", "artist", "assume",
+                                 "Athens", "atlas", "Aztec", "baboon", "backfield", "backward", "banjo", "beaming",
+                                 "bedlamp", "beehive", "beeswax", "befriend", "Belfast", "berserk", "billiard", "bison",
+                                 "blackjack", "blockade", "blowtorch", "bluebird", "bombast", "bookshelf", "brackish",
+                                 "breadline", "breakup", "brickyard", "briefcase", "Burbank", "button", "buzzard",
+                                 "cement", "chairlift", "chatter", "checkup", "chisel", "choking", "chopper",
+                                 "Christmas", "clamshell", "classic", "classroom", "cleanup", "clockwork", "cobra",
+                                 "commence", "concert", "cowbell", "crackdown", "cranky", "crowfoot", "crucial",
+                                 "crumpled", "crusade", "cubic", "dashboard", "deadbolt", "deckhand", "dogsled",
+                                 "dragnet", "drainage", "dreadful", "drifter", "dropper", "drumbeat", "drunken",
+                                 "Dupont", "dwelling", "eating", "edict", "egghead", "eightball", "endorse", "endow",
+                                 "enlist", "erase", "escape", "exceed", "eyeglass", "eyetooth", "facial", "fallout",
+                                 "flagpole", "flatfoot", "flytrap", "fracture", "framework", "freedom", "frighten",
+                                 "gazelle", "Geiger", "glitter", "glucose", "goggles", "goldfish", "gremlin",
+                                 "guidance", "hamlet", "highchair", "hockey", "indoors", "indulge", "inverse",
+                                 "involve", "island", "jawbone", "keyboard", "kickoff", "kiwi", "klaxon", "locale",
+                                 "lockup", "merit", "minnow", "miser", "Mohawk", "mural", "music", "necklace",
+                                 "Neptune", "newborn", "nightbird", "Oakland", "obtuse", "offload", "optic", "orca",
+                                 "payday", "peachy", "pheasant", "physique", "playhouse", "Pluto", "preclude", "prefer",
+                                 "preshrunk", "printer", "prowler", "pupil", "puppy", "python", "quadrant", "quiver",
+                                 "quota", "ragtime", "ratchet", "rebirth", "reform", "regain", "reindeer", "rematch",
+                                 "repay", "retouch", "revenge", "reward", "rhythm", "ribcage", "ringbolt", "robust",
+                                 "rocker", "ruffled", "sailboat", "sawdust", "scallion", "scenic", "scorecard",
+                                 "Scotland", "seabird", "select", "sentence", "shadow", "shamrock", "showgirl",
+                                 "skullcap", "skydive", "slingshot", "slowdown", "snapline", "snapshot", "snowcap",
+                                 "snowslide", "solo", "southward", "soybean", "spaniel", "spearhead", "spellbind",
+                                 "spheroid", "spigot", "spindle", "spyglass", "stagehand", "stagnate", "stairway",
+                                 "standard", "stapler", "steamship", "sterling", "stockman", "stopwatch", "stormy",
+                                 "sugar", "surmount", "suspense", "sweatband", "swelter", "tactics", "talon",
+                                 "tapeworm", "tempest", "tiger", "tissue", "tonic", "topmost", "tracker", "transit",
+                                 "trauma", "treadmill", "Trojan", "trouble", "tumor", "tunnel", "tycoon", "uncut",
+                                 "unearth", "unwind", "uproot", "upset", "upshot", "vapor", "village", "virus",
+                                 "Vulcan", "waffle", "wallet", "watchword", "wayside", "willow", "woodlark", "Zulu"];
+
+/// The PGP word list's three-syllable words, for bytes in odd positions.
+const ODD_WORDS: [&str; 256] = ["adroitness", "adviser", "aftermath", "aggregate", "alkali", "almighty", "amulet",
+                                "amusement", "antenna", "applicant", "Apollo", "armistice", "article", "asteroid",
+                                "Atlantic", "atmosphere", "autopsy", "Babylon", "backwater", "barbecue", "belowground",
+                                "bifocals", "bodyguard", "bookseller", "borderline", "bottomless", "Bradbury",
+                                "bravado", "Brazilian", "breakaway", "Burlington", "businessman", "butterfat",
+                                "Camelot", "candidate", "cannonball", "Capricorn", "caravan", "caretaker", "celebrate",
+                                "cellulose", "certify", "chambermaid", "Cherokee", "Chicago", "clergyman", "coherence",
+                                "combustion", "commando", "company", "component", "concurrent", "confidence",
+                                "conformist", "congregate", "consensus", "consulting", "corporate", "corrosion",
+                                "councilman", "crossover", "crucifix", "cumbersome", "customer", "Dakota", "decadence",
+                                "December", "decimal", "designing", "detector", "detergent", "determine", "dictator",
+                                "dinosaur", "direction", "disable", "disbelief", "disruptive", "distortion", "document",
+                                "embezzle", "enchanting", "enrollment", "enterprise", "equation", "equipment",
+                                "escapade", "Eskimo", "everyday", "examine", "existence", "exodus", "fascinate",
+                                "filament", "finicky", "forever", "fortitude", "frequency", "gadgetry", "Galveston",
+                                "getaway", "glossary", "gossamer", "graduate", "gravity", "guitarist", "hamburger",
+                                "Hamilton", "handiwork", "hazardous", "headwaters", "hemisphere", "hesitate",
+                                "hideaway", "holiness", "hurricane", "hydraulic", "impartial", "impetus", "inception",
+                                "indigo", "inertia", "infancy", "inferno", "informant", "insincere", "insurgent",
+                                "integrate", "intention", "inventive", "Istanbul", "Jamaica", "Jupiter", "leprosy",
+                                "letterhead", "liberty", "maritime", "matchmaker", "maverick", "Medusa", "megaton",
+                                "microscope", "microwave", "midsummer", "millionaire", "miracle", "misnomer",
+                                "molasses", "molecule", "Montana", "monument", "mosquito", "narrative", "nebula",
+                                "newsletter", "Norwegian", "October", "Ohio", "onlooker", "opulent", "Orlando",
+                                "outfielder", "Pacific", "pandemic", "Pandora", "paperweight", "paragon", "paragraph",
+                                "paramount", "passenger", "pedigree", "Pegasus", "penetrate", "perceptive",
+                                "performance", "pharmacy", "phonetic", "photograph", "pioneer", "pocketful",
+                                "politeness", "positive", "potato", "processor", "provincial", "proximity", "puberty",
+                                "publisher", "pyramid", "quantity", "racketeer", "rebellion", "recipe", "recover",
+                                "repellent", "replica", "reproduce", "resistor", "responsive", "retraction",
+                                "retrieval", "retrospect", "revenue", "revival", "revolver", "sandalwood", "sardonic",
+                                "Saturday", "savagery", "scavenger", "sensation", "sociable", "souvenir", "specialist",
+                                "speculate", "stethoscope", "stupendous", "supportive", "surrender", "suspicious",
+                                "sympathy", "tambourine", "telephone", "therapist", "tobacco", "tolerance", "tomorrow",
+                                "torpedo", "tradition", "travesty", "trombonist", "truncated", "typewriter", "ultimate",
+                                "undaunted", "underfoot", "unicorn", "unify", "universe", "unravel", "upcoming",
+                                "vacancy", "vagabond", "vertigo", "Virginia", "visitor", "vocalist", "voyager",
+                                "warranty", "Waterloo", "whimsical", "Wichita", "Wilmington", "Wyoming", "yesteryear",
+                                "Yucatan"];
@@ -0,0 +1,100 @@
+use super::{Blake, BlakeError, Result};
+use std::io;
+
+
+/// How much of each update is fed to every state before moving on to the next piece,
+/// so the data is read from memory once, rather than once per salt.
+const PIECE_SIZE: usize = 16 * 1024;
+
+
+/// A set of hash states of the same length, one per salt, all fed the same data,
+/// for deriving differently-salted digests of one stream while only reading it once.
+///
+/// Each digest is the same as that of a `Blake` salted with the corresponding salt and fed the same data.
+///
+/// # Examples
+///
+/// Per-recipient tags of one payload.
+///
+/// ```
+/// # use blake::{Blake, MultiSaltHasher};
+/// # #[cfg(feature = "width-512")] {
+/// let salts = [b"Recipient number one, Leningrad.", b"Recipient number two, Stalingrad"];
+///
+/// let mut state = MultiSaltHasher::new(512, &salts).unwrap();
+/// state.update(b"The lazy fox ");
+/// state.update(b"jumps over the lazy dog.");
+/// let tags = state.finalise();
+///
+/// assert_eq!(tags.len(), 2);
+/// assert!(tags[0] != tags[1]);
+/// for (salt, tag) in salts.iter().zip(&tags) {
+///     let mut single = Blake::new(512).unwrap();
+///     single.add_salt(&salt[..]).unwrap();
+///     single.update(b"The lazy fox jumps over the lazy dog.");
+///     assert_eq!(&single.finalise_vec(), tag);
+/// }
+/// # }
+/// ```
+pub struct MultiSaltHasher {
+    states: Vec<Blake>,
+}
+
+
+impl MultiSaltHasher {
+    /// Create a state of the given bit length for each of the salts, as with `Blake::new()` and `Blake::add_salt()`.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    /// and `Err(BlakeError::Fail)` if any salt is shorter than the hash function's salt length.
+    pub fn new<S: AsRef<[u8]>>(hashbitlen: i32, salts: &[S]) -> Result<MultiSaltHasher> {
+        let salt_size = if hashbitlen < 384 { 16 } else { 32 };
+        let states = salts.iter()
+            .map(|salt| {
+                let salt = salt.as_ref();
+                let mut state = Blake::new(hashbitlen)?;
+                if salt.len() < salt_size {
+                    return Err(BlakeError::Fail);
+                }
+                state.add_salt(salt)?;
+                Ok(state)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(MultiSaltHasher { states })
+    }
+
+    /// Get the number of salts, and hence of digests produced by `finalise()`.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Check whether this was created with no salts at all.
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Append the provided data to all the states.
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) {
+        for piece in data.as_ref().chunks(PIECE_SIZE) {
+            for state in &mut self.states {
+                state.update(piece);
+            }
+        }
+    }
+
+    /// Finish hashing and return the digests, in the order of the salts passed to `MultiSaltHasher::new()`.
+    pub fn finalise(&mut self) -> Vec<Vec<u8>> {
+        self.states.iter_mut().map(Blake::finalise_vec).collect()
+    }
+}
+
+/// The `Write` implementation updates all the states with the provided data, as in `MultiSaltHasher::update()`.
+impl io::Write for MultiSaltHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
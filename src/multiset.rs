@@ -0,0 +1,230 @@
+//! Order-independent hashes of multisets, updated incrementally.
+//!
+//! Each item is expanded with BLAKE-512 into a vector of 64 64-bit lanes, and a `SetHash` is the lane-wise sum,
+//! modulo 2<sup>64</sup>, of the vectors of all the items it holds. Since addition commutes, two replicas holding the
+//! same items have equal hashes regardless of the order they were added and removed in, and removing an item
+//! subtracts its vector, so a hash can be kept up to date alongside the data, instead of being recomputed.
+//!
+//! Items are multiset members, not set members: adding an item twice requires removing it twice.
+//!
+//! An `LtHash` is the same construction with 1024 16-bit lanes, modulo 2<sup>16</sup>, as LtHash16 from
+//! [Facebook's anti-entropy work](https://eprint.iacr.org/2019/227): a 2048-byte checksum of, for example,
+//! a key-value store's entries, maintained as they're written and deleted.
+//!
+//! The vector of an item is the concatenation of BLAKE-512 of its seed followed by the block index
+//! (from 0) as a little-endian `u64`, where the seed is BLAKE-512 of the item in the context of the
+//! domain-separation tag (`"blake-rs SetHash"` or `"blake-rs LtHash16"`), as in `hash_domain()`;
+//! lanes are read little-endian.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::multiset::SetHash;
+//! let mut replica_a = SetHash::new();
+//! replica_a.add(b"alice");
+//! replica_a.add(b"bob");
+//! replica_a.add(b"eve");
+//! replica_a.remove(b"eve");
+//!
+//! let mut replica_b = SetHash::new();
+//! replica_b.add(b"bob");
+//! replica_b.add(b"alice");
+//! assert_eq!(replica_a, replica_b);
+//!
+//! replica_b.add(b"carol");
+//! assert!(replica_a != replica_b);
+//! ```
+
+
+use super::{BlakeError, Result, constant_time_eq};
+use super::b256::{self, Digest};
+use super::stack::StackState;
+use std::{fmt, mem};
+
+
+/// The amount of 64-bit lanes in a `SetHash`.
+pub const LANES: usize = 64;
+
+/// The amount of 16-bit lanes in an `LtHash`.
+pub const LT_LANES: usize = 1024;
+
+
+/// Generate a lane-wise sum of item vectors, as described in the module-level documentation, with the specified amount
+/// of lanes of the specified type, serialised to the specified amount of bytes, and items expanded in the context of
+/// the specified domain-separation tag.
+macro_rules! lane_sum {
+    ($(#[$attr:meta])* $name:ident, $lane:ty, $lanes:expr, $bytes:literal, $tag:expr) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            lanes: [$lane; $lanes],
+        }
+
+
+        impl $name {
+            /// Create the hash of the empty multiset.
+            #[must_use]
+            pub fn new() -> $name {
+                $name { lanes: [0; $lanes] }
+            }
+
+            /// Add an item to the multiset.
+            pub fn add(&mut self, item: &[u8]) {
+                let vector = $name::item_vector(item);
+                for (lane, v) in self.lanes.iter_mut().zip(vector.iter()) {
+                    *lane = lane.wrapping_add(*v);
+                }
+            }
+
+            /// Remove an item from the multiset.
+            ///
+            /// Removing an item that was never added isn't detected: the result is the hash of a multiset with the
+            /// item having a negative multiplicity, which becomes the expected one again once it's added.
+            pub fn remove(&mut self, item: &[u8]) {
+                let vector = $name::item_vector(item);
+                for (lane, v) in self.lanes.iter_mut().zip(vector.iter()) {
+                    *lane = lane.wrapping_sub(*v);
+                }
+            }
+
+            /// Add all items in the other multiset to this one.
+            pub fn union(&mut self, other: &$name) {
+                for (lane, v) in self.lanes.iter_mut().zip(other.lanes.iter()) {
+                    *lane = lane.wrapping_add(*v);
+                }
+            }
+
+            /// Remove all items in the other multiset from this one, as with `remove()`.
+            pub fn difference(&mut self, other: &$name) {
+                for (lane, v) in self.lanes.iter_mut().zip(other.lanes.iter()) {
+                    *lane = lane.wrapping_sub(*v);
+                }
+            }
+
+            /// Get BLAKE-256 of the serialised hash, for comparing hashes compactly.
+            pub fn digest(&self) -> Digest {
+                b256::hash(self.to_bytes())
+            }
+
+            /// Serialise the hash as its lanes, little-endian.
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.lanes.iter().flat_map(|lane| lane.to_le_bytes()).collect()
+            }
+
+            /// Deserialise a hash serialised with `to_bytes()`.
+            ///
+            #[doc = concat!("Returns `Err(BlakeError::BadEncoding)` if the data isn't ", stringify!($bytes), " bytes long.")]
+            pub fn from_bytes(data: &[u8]) -> Result<$name> {
+                if data.len() != $bytes {
+                    return Err(BlakeError::BadEncoding);
+                }
+
+                let mut ret = $name::new();
+                for (lane, bytes) in ret.lanes.iter_mut().zip(data.chunks(mem::size_of::<$lane>())) {
+                    *lane = <$lane>::from_le_bytes(bytes.try_into().expect("chunks of the lane's size"));
+                }
+                Ok(ret)
+            }
+
+            /// Expand the item into its vector.
+            fn item_vector(item: &[u8]) -> [$lane; $lanes] {
+                let mut bytes = [0; $bytes];
+                expand($tag, item, &mut bytes);
+
+                let mut ret = [0; $lanes];
+                for (lane, bytes) in ret.iter_mut().zip(bytes.chunks(mem::size_of::<$lane>())) {
+                    *lane = <$lane>::from_le_bytes(bytes.try_into().expect("chunks of the lane's size"));
+                }
+                ret
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name::new()
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool {
+                constant_time_eq(&self.to_bytes(), &other.to_bytes())
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!(stringify!($name), "({})"), self.digest())
+            }
+        }
+    };
+}
+
+
+lane_sum! {
+    /// A multiset hash, as described in the module-level documentation.
+    ///
+    /// Equality is checked in constant time.
+    ///
+    /// # Examples
+    ///
+    /// Combining the hashes of shards, and sending them serialised.
+    ///
+    /// ```
+    /// # use blake::multiset::SetHash;
+    /// let mut shard_1 = SetHash::new();
+    /// shard_1.add(b"alice");
+    /// let mut shard_2 = SetHash::new();
+    /// shard_2.add(b"bob");
+    /// shard_1.union(&shard_2);
+    ///
+    /// let mut whole = SetHash::new();
+    /// whole.add(b"bob");
+    /// whole.add(b"alice");
+    /// assert_eq!(shard_1, whole);
+    /// assert_eq!(SetHash::from_bytes(&whole.to_bytes()), Ok(whole));
+    /// ```
+    SetHash, u64, LANES, 512, "blake-rs SetHash"
+}
+
+lane_sum! {
+    /// An LtHash16 checksum, as described in the module-level documentation.
+    ///
+    /// Equality is checked in constant time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::multiset::LtHash;
+    /// let mut store = LtHash::new();
+    /// store.add(b"key1=old");
+    /// store.add(b"key2=value");
+    ///
+    /// // Update key1
+    /// store.remove(b"key1=old");
+    /// store.add(b"key1=new");
+    ///
+    /// let mut replica = LtHash::new();
+    /// replica.add(b"key2=value");
+    /// replica.add(b"key1=new");
+    /// assert_eq!(store, replica);
+    /// ```
+    LtHash, u16, LT_LANES, 2048, "blake-rs LtHash16"
+}
+
+
+/// Expand the item into `out`, in `tag`'s context, as described in the module-level documentation.
+fn expand(tag: &str, item: &[u8], out: &mut [u8]) {
+    let mut seed = [0; 64];
+    let mut state = StackState::new_domain(512, tag).expect("512 is a valid hashbitlen");
+    state.update(item);
+    state.finalise(&mut seed).expect("the buffer fits the digest");
+
+    for (i, block) in out.chunks_mut(64).enumerate() {
+        let mut state = StackState::new(512).expect("512 is a valid hashbitlen");
+        state.update(seed);
+        state.update((i as u64).to_le_bytes());
+        state.finalise(block).expect("the buffer fits the digest");
+    }
+}
@@ -1,14 +1,18 @@
-use libc::{c_void, malloc, free, c_int};
-use std::ptr::null_mut;
-use std::mem::size_of;
+use libc::{c_void, c_int};
+use std::sync::atomic::{Ordering, compiler_fence};
+use std::ptr::{self, null_mut};
+use std::mem::{self, size_of};
+use std::{cmp, slice};
 
 
 pub type FFIHashState = *mut c_void;
 
+#[cfg(not(feature = "bindgen"))]
 type BitSequence = u8;
 
+#[cfg(not(feature = "bindgen"))]
 #[repr(C)]
-struct hashState {
+pub struct hashState {
     hashbitlen: libc::c_int,
     datalen: libc::c_int,
     init: libc::c_int,
@@ -23,6 +27,18 @@ struct hashState {
     salt64: [u64; 4],
 }
 
+impl hashState {
+    /// An uninitialised state, to be passed to `BLAKE_Hash_Init()`.
+    pub fn zeroed() -> hashState {
+        unsafe { mem::zeroed() }
+    }
+
+    pub fn as_ffi(&mut self) -> FFIHashState {
+        self as *mut hashState as FFIHashState
+    }
+}
+
+#[cfg(not(feature = "bindgen"))]
 #[link(name = "blake")]
 extern "C" {
     pub fn BLAKE_Hash_Init(state: FFIHashState, hashbitlen: c_int) -> c_int;
@@ -33,12 +49,235 @@ extern "C" {
     pub fn BLAKE_Hash_Hash(hashbitlen: c_int, data: *const u8, databitlen: u64, hashval: *mut u8) -> c_int;
 }
 
+/// Declarations generated by bindgen from `ext/blake/blake_ref.h`, in place of the ones above.
+#[cfg(feature = "bindgen")]
+#[allow(non_camel_case_types, non_upper_case_globals, dead_code)]
+mod bindings {
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+}
+
+#[cfg(feature = "bindgen")]
+pub use self::bindings::hashState;
+
+#[cfg(feature = "bindgen")]
+#[allow(non_snake_case)]
+pub unsafe fn BLAKE_Hash_Init(state: FFIHashState, hashbitlen: c_int) -> c_int {
+    bindings::BLAKE_Hash_Init(state as *mut hashState, hashbitlen) as c_int
+}
+
+#[cfg(feature = "bindgen")]
+#[allow(non_snake_case)]
+pub unsafe fn BLAKE_Hash_AddSalt(state: FFIHashState, salt: *const u8) -> c_int {
+    bindings::BLAKE_Hash_AddSalt(state as *mut hashState, salt) as c_int
+}
+
+#[cfg(feature = "bindgen")]
+#[allow(non_snake_case)]
+pub unsafe fn BLAKE_Hash_Update(state: FFIHashState, data: *const u8, databitlen: u64) -> c_int {
+    bindings::BLAKE_Hash_Update(state as *mut hashState, data, databitlen) as c_int
+}
+
+#[cfg(feature = "bindgen")]
+#[allow(non_snake_case)]
+pub unsafe fn BLAKE_Hash_Final(state: FFIHashState, hashval: *mut u8) -> c_int {
+    bindings::BLAKE_Hash_Final(state as *mut hashState, hashval) as c_int
+}
+
+#[cfg(feature = "bindgen")]
+#[allow(non_snake_case)]
+pub unsafe fn BLAKE_Hash_Hash(hashbitlen: c_int, data: *const u8, databitlen: u64, hashval: *mut u8) -> c_int {
+    bindings::BLAKE_Hash_Hash(hashbitlen, data, databitlen, hashval) as c_int
+}
 
+#[cfg(windows)]
+extern "system" {
+    fn VirtualAlloc(address: *mut c_void, size: usize, allocation_type: u32, protect: u32) -> *mut c_void;
+    fn VirtualFree(address: *mut c_void, size: usize, free_type: u32) -> i32;
+    fn VirtualLock(address: *mut c_void, size: usize) -> i32;
+    fn VirtualUnlock(address: *mut c_void, size: usize) -> i32;
+}
+
+#[cfg(windows)]
+const MEM_COMMIT: u32 = 0x1000;
+#[cfg(windows)]
+const MEM_RESERVE: u32 = 0x2000;
+#[cfg(windows)]
+const MEM_RELEASE: u32 = 0x8000;
+#[cfg(windows)]
+const PAGE_READWRITE: u32 = 0x04;
+
+
+/// The largest amount of bytes whose length in bits fits into a `DataLength`, rounded down to a whole amount of blocks.
+const MAX_UPDATE_BYTES: u64 = (u64::MAX / 8) & !127;
+
+
+/// Check whether `BLAKE_Hash_Update()` (or `BLAKE_Hash_Hash()`) can take the specified data in one call.
+pub fn fits_one_update(data: &[u8]) -> bool {
+    data.len() as u64 <= MAX_UPDATE_BYTES
+}
+
+/// Update the state with the specified data, split into multiple `BLAKE_Hash_Update()` calls if its length in bits
+/// would overflow.
+pub fn update(state: FFIHashState, mut data: &[u8]) {
+    loop {
+        let len = cmp::min(data.len() as u64, MAX_UPDATE_BYTES) as usize;
+        let bitlen = (len as u64).checked_mul(8).expect("Chunk length in bits overflowed");
+        unsafe { BLAKE_Hash_Update(state, data.as_ptr(), bitlen) };
+
+        data = &data[len..];
+        if data.is_empty() {
+            break;
+        }
+    }
+}
+
+
+/// Allocate a zeroed state with the global allocator.
 pub fn malloc_hash_state() -> FFIHashState {
-    unsafe { malloc(size_of::<hashState>()) }
+    Box::into_raw(Box::new(hashState::zeroed())) as FFIHashState
 }
 
+/// Free a state allocated with `malloc_hash_state()`.
 pub fn free_hash_state(state: &mut FFIHashState) {
-    unsafe { free(*state) };
+    if cfg!(feature = "zeroize") {
+        zero_hash_state(*state);
+    }
+    drop(unsafe { Box::from_raw(*state as *mut hashState) });
+    *state = null_mut();
+}
+
+/// Allocate a state in its own page-locked mapping, or `None` if that's impossible.
+#[cfg(unix)]
+pub fn malloc_locked_hash_state() -> Option<FFIHashState> {
+    unsafe {
+        let state = libc::mmap(null_mut(),
+                               size_of::<hashState>(),
+                               libc::PROT_READ | libc::PROT_WRITE,
+                               libc::MAP_PRIVATE | libc::MAP_ANON,
+                               -1,
+                               0);
+        if state == libc::MAP_FAILED {
+            return None;
+        }
+
+        if libc::mlock(state, size_of::<hashState>()) != 0 {
+            libc::munmap(state, size_of::<hashState>());
+            return None;
+        }
+
+        Some(state)
+    }
+}
+
+/// Allocate a state in its own page-locked mapping, or `None` if that's impossible.
+#[cfg(windows)]
+pub fn malloc_locked_hash_state() -> Option<FFIHashState> {
+    unsafe {
+        let state = VirtualAlloc(null_mut(), size_of::<hashState>(), MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE);
+        if state.is_null() {
+            return None;
+        }
+
+        if VirtualLock(state, size_of::<hashState>()) == 0 {
+            VirtualFree(state, 0, MEM_RELEASE);
+            return None;
+        }
+
+        Some(state)
+    }
+}
+
+/// Allocate a state in its own page-locked mapping, or `None` if that's impossible.
+#[cfg(not(any(unix, windows)))]
+pub fn malloc_locked_hash_state() -> Option<FFIHashState> {
+    None
+}
+
+/// Zero, unlock, and unmap a state allocated with `malloc_locked_hash_state()`.
+pub fn free_locked_hash_state(state: &mut FFIHashState) {
+    zero_hash_state(*state);
+    unsafe { unmap_locked(*state) };
     *state = null_mut();
 }
+
+#[cfg(unix)]
+unsafe fn unmap_locked(state: FFIHashState) {
+    libc::munlock(state, size_of::<hashState>());
+    libc::munmap(state, size_of::<hashState>());
+}
+
+#[cfg(windows)]
+unsafe fn unmap_locked(state: FFIHashState) {
+    VirtualUnlock(state, size_of::<hashState>());
+    VirtualFree(state, 0, MEM_RELEASE);
+}
+
+#[cfg(not(any(unix, windows)))]
+unsafe fn unmap_locked(_: FFIHashState) {}
+
+/// Copy the whole state from one allocation to another.
+pub fn copy_hash_state(from: FFIHashState, to: FFIHashState) {
+    unsafe { ptr::copy_nonoverlapping(from as *const u8, to as *mut u8, size_of::<hashState>()) };
+}
+
+/// Get the chaining value, salt, and counter of a state if it holds no partial block, widened to the 64-bit words.
+pub fn get_midstate(state: FFIHashState) -> Option<([u64; 8], [u64; 4], u128)> {
+    match get_parts(state) {
+        (chain, salt, counter, 0) => Some((chain, salt, counter)),
+        _ => None,
+    }
+}
+
+/// Get the chaining value, salt, and counter of a state, widened to the 64-bit words, and the amount of bytes buffered.
+pub fn get_parts(state: FFIHashState) -> ([u64; 8], [u64; 4], u128, usize) {
+    let state = unsafe { &*(state as *const hashState) };
+
+    let mut chain = [0; 8];
+    let mut salt = [0; 4];
+    let counter;
+    if state.hashbitlen < 384 {
+        for (word, h) in chain.iter_mut().zip(state.h32.iter()) {
+            *word = *h as u64;
+        }
+        for (word, s) in salt.iter_mut().zip(state.salt32.iter()) {
+            *word = *s as u64;
+        }
+        counter = (state.t32[1] as u128) << 32 | state.t32[0] as u128;
+    } else {
+        chain = state.h64;
+        salt = state.salt64;
+        counter = (state.t64[1] as u128) << 64 | state.t64[0] as u128;
+    }
+    (chain, salt, counter, state.datalen as usize / 8)
+}
+
+/// Overwrite the chaining value, salt, and counter of a freshly initialised state, as returned by `get_midstate()`.
+pub fn set_midstate(state: FFIHashState, chain: &[u64; 8], salt: &[u64; 4], counter: u128) {
+    let state = unsafe { &mut *(state as *mut hashState) };
+    if state.hashbitlen < 384 {
+        for (h, word) in state.h32.iter_mut().zip(chain.iter()) {
+            *h = *word as u32;
+        }
+        for (s, word) in state.salt32.iter_mut().zip(salt.iter()) {
+            *s = *word as u32;
+        }
+        state.t32 = [counter as u32, (counter >> 32) as u32];
+    } else {
+        state.h64 = *chain;
+        state.salt64 = *salt;
+        state.t64 = [counter as u64, (counter >> 64) as u64];
+    }
+}
+
+/// Overwrite the state with zeroes in a way that won't be optimised out.
+pub fn zero_hash_state(state: FFIHashState) {
+    zero_bytes(unsafe { slice::from_raw_parts_mut(state as *mut u8, size_of::<hashState>()) });
+}
+
+/// Overwrite the bytes with zeroes in a way that won't be optimised out.
+pub fn zero_bytes(bytes: &mut [u8]) {
+    for byte in bytes {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,229 @@
+//! Hashing of objects in object storage, like S3, GCS, or Azure Blob Storage, via
+//! [`object_store`](https://docs.rs/object_store), streaming them through the hash function without buffering them.
+//!
+//! If the stream fails partway through, with a transient error or by ending early, the rest of the object is requested
+//! with a ranged read, starting where the stream stopped, so a dropped connection doesn't restart a multi-gigabyte
+//! download. Resumed reads are conditional on the object's ETag, if the store provides one, so a digest can't mix
+//! different versions of the object.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::object_store::ObjectOptions;
+//! # use object_store::memory::InMemory;
+//! # use object_store::path::Path;
+//! # use object_store::ObjectStoreExt;
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let store = InMemory::new();
+//! let location = Path::from("bundles/fox.txt");
+//! store.put(&location, "The lazy fox jumps over the lazy dog.".into()).await.unwrap();
+//!
+//! let mut result_object = [0; 32];
+//! let mut result        = [0; 32];
+//! assert_eq!(blake::object_store::hash_object(256, &store, &location, &mut result_object).await.unwrap(), 37);
+//! blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+//! assert_eq!(result_object, result);
+//! assert_eq!(blake::object_store::hash_object(256, &store, &location, &mut result_object[..31]).await.unwrap_err().kind(),
+//!            std::io::ErrorKind::InvalidInput);
+//!
+//! let mut reports = vec![];
+//! let mut progress = |done, total, retries| reports.push((done, total, retries));
+//! blake::object_store::hash_object_with(256, &store, &location, &mut result_object,
+//!                                       &mut ObjectOptions::new().range(4..12).progress(&mut progress, 1))
+//!     .await
+//!     .unwrap();
+//! blake::hash(256, b"lazy fox", &mut result).unwrap();
+//! assert_eq!(result_object, result);
+//! assert_eq!(reports, vec![(8, 8, 0)]);
+//! # }
+//! ```
+
+
+use super::stack::StackState;
+use super::check_hashval;
+use ::object_store::{Error, GetOptions, GetRange, ObjectStore};
+use ::object_store::path::Path;
+use futures_util::StreamExt;
+use std::time::Duration;
+use std::ops::Range;
+use std::io;
+
+
+/// A callback receiving the amount of bytes hashed so far, the total amount to hash, and the amount of retries so far,
+/// set with `ObjectOptions::progress()`.
+type ProgressFn<'a> = dyn FnMut(u64, u64, u32) + Send + 'a;
+
+
+/// Options for hashing objects with `hash_object_with()`.
+pub struct ObjectOptions<'a> {
+    range: Option<Range<u64>>,
+    retries: u32,
+    progress: Option<&'a mut ProgressFn<'a>>,
+    progress_granularity: u64,
+}
+
+/// How long to wait before the first retry; every subsequent one waits twice as long as the previous one.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+
+impl<'a> ObjectOptions<'a> {
+    /// Create options hashing the whole object, retrying up to 3 times, with no progress reporting.
+    #[must_use]
+    pub fn new() -> ObjectOptions<'a> {
+        ObjectOptions {
+            range: None,
+            retries: 3,
+            progress: None,
+            progress_granularity: 0,
+        }
+    }
+
+    /// Hash only the specified range of bytes of the object, which must be non-empty.
+    ///
+    /// If the range extends past the end of the object, the rest of the object is hashed.
+    pub fn range(mut self, range: Range<u64>) -> ObjectOptions<'a> {
+        self.range = Some(range);
+        self
+    }
+
+    /// Resume reading up to the specified amount of times after transient errors,
+    /// waiting 100ms before the first retry, and twice as long before each subsequent one.
+    ///
+    /// Errors that won't go away by themselves, like the object not existing or changing, are never retried.
+    pub fn retries(mut self, retries: u32) -> ObjectOptions<'a> {
+        self.retries = retries;
+        self
+    }
+
+    /// Call the specified function with the amount of bytes hashed so far, the total amount of bytes to hash,
+    /// and the amount of retries so far, every time at least `granularity` more bytes have been hashed,
+    /// before every retry, and at the end, unless the last call already reported all bytes.
+    pub fn progress(mut self, callback: &'a mut (dyn FnMut(u64, u64, u32) + Send), granularity: u64) -> ObjectOptions<'a> {
+        self.progress = Some(callback);
+        self.progress_granularity = granularity;
+        self
+    }
+}
+
+impl Default for ObjectOptions<'_> {
+    fn default() -> Self {
+        ObjectOptions::new()
+    }
+}
+
+
+/// Hash the object at the specified location in the store, returning the amount of bytes hashed.
+///
+/// Refer to `hash_object_with()` for details.
+pub async fn hash_object<S: ObjectStore + ?Sized>(hashbitlen: i32, store: &S, location: &Path, hashval: &mut [u8]) -> io::Result<u64> {
+    hash_object_with(hashbitlen, store, location, hashval, &mut ObjectOptions::new()).await
+}
+
+/// Hash the object at the specified location in the store with the specified options,
+/// returning the amount of bytes hashed.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// if `hashval` is shorter than the hash function's size, or if the range is empty, before fetching anything,
+/// one with kind `io::ErrorKind::NotFound` if the object doesn't exist,
+/// one with kind `io::ErrorKind::UnexpectedEof` if the stream kept ending early after all retries,
+/// or one wrapping the `object_store::Error` otherwise,
+/// like `object_store::Error::Precondition` if the object changed between retries.
+pub async fn hash_object_with<S: ObjectStore + ?Sized>(hashbitlen: i32, store: &S, location: &Path, hashval: &mut [u8],
+                                                       options: &mut ObjectOptions<'_>)
+                                                       -> io::Result<u64> {
+    check_hashval(hashbitlen, hashval)?;
+    let mut state = StackState::new(hashbitlen)?;
+    let start = match options.range {
+        Some(ref range) if range.is_empty() => return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty range")),
+        Some(ref range) => range.start,
+        None => 0,
+    };
+
+    let mut done = 0u64;
+    let mut reported = None;
+    let mut next_report = options.progress_granularity;
+    let mut retried = 0;
+    let mut e_tag = None;
+    let mut end = None;
+    loop {
+        let range = match (end, &options.range) {
+            (Some(end), _) => Some(GetRange::Bounded(start + done..end)),
+            (None, Some(range)) => Some(GetRange::Bounded(range.clone())),
+            (None, None) => None,
+        };
+        let request = GetOptions {
+            range,
+            if_match: e_tag.clone(),
+            ..GetOptions::default()
+        };
+
+        let error = match store.get_opts(location, request).await {
+            Ok(result) => {
+                if end.is_none() {
+                    e_tag = result.meta.e_tag.clone();
+                    end = Some(result.range.end);
+                }
+                let total = end.unwrap_or(0) - start;
+
+                let mut stream = result.into_stream();
+                let mut error = None;
+                while let Some(chunk) = stream.next().await {
+                    match chunk {
+                        Ok(chunk) => {
+                            state.update(&chunk);
+                            done += chunk.len() as u64;
+
+                            if let Some(ref mut progress) = options.progress {
+                                if done >= next_report {
+                                    progress(done, total, retried);
+                                    reported = Some(done);
+                                    next_report = done.saturating_add(options.progress_granularity.max(1));
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            error = Some(e);
+                            break;
+                        }
+                    }
+                }
+
+                match error {
+                    Some(e) => e,
+                    None if done >= total => break,
+                    None => {
+                        Error::Generic {
+                            store: "blake",
+                            source: Box::new(io::Error::new(io::ErrorKind::UnexpectedEof, "object stream ended early")),
+                        }
+                    }
+                }
+            }
+            Err(e) => e,
+        };
+
+        if retried == options.retries || !matches!(error, Error::Generic { .. }) {
+            return Err(match error {
+                Error::Generic { source, .. } if source.is::<io::Error>() => *source.downcast::<io::Error>().expect("just checked"),
+                e => e.into(),
+            });
+        }
+
+        if let Some(ref mut progress) = options.progress {
+            progress(done, end.unwrap_or(0).saturating_sub(start), retried + 1);
+            reported = Some(done);
+        }
+        tokio::time::sleep(INITIAL_BACKOFF * 2u32.saturating_pow(retried)).await;
+        retried += 1;
+    }
+
+    if let Some(ref mut progress) = options.progress {
+        if reported != Some(done) {
+            progress(done, done, retried);
+        }
+    }
+
+    state.finalise(hashval).expect("the buffer fits the digest");
+    Ok(done)
+}
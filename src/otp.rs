@@ -0,0 +1,254 @@
+//! HOTP and TOTP one-time passwords, as in RFC 4226 and RFC 6238, with HMAC-BLAKE-256 as the PRF.
+//!
+//! HMAC-BLAKE-256 is HMAC, as in RFC 2104, over BLAKE-256, whose block size is 64 bytes.
+//! The 32-byte MAC of the counter, as a big-endian `u64`, is dynamically truncated as in RFC 4226:
+//! the low nibble of its last byte is the offset of the 31-bit big-endian number reduced modulo 10<sup>digits</sup>.
+//!
+//! The codes aren't interoperable with authenticator apps using HMAC-SHA-1 or HMAC-SHA-256;
+//! both sides need to use this module, or another implementation of the same construction.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::otp::Totp;
+//! let totp = Totp::new(b"12345678901234567890", 6, 30).unwrap();
+//!
+//! let code = totp.generate_at(59);
+//! assert_eq!(code.len(), 6);
+//! assert_eq!(totp.verify_at(&code, 59), Some(1));
+//!
+//! // One step of clock skew is tolerated by default
+//! assert_eq!(totp.verify_at(&code, 89), Some(1));
+//! assert_eq!(totp.verify_at(&code, 119), None);
+//! ```
+
+
+use super::b256::{self, Digest, Hasher};
+use super::{BlakeError, Result, constant_time_eq, native};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+
+/// The block size of BLAKE-256, to which HMAC pads the key.
+pub const BLOCK_SIZE: usize = 64;
+
+/// The shortest key accepted, as required by RFC 4226; 20 bytes or more are recommended.
+pub const MIN_KEY_SIZE: usize = 16;
+
+
+/// An HOTP generator and validator, with a secret key and the amount of digits in the codes.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::otp::Hotp;
+/// let hotp = Hotp::new(b"12345678901234567890", 8).unwrap();
+///
+/// let code = hotp.generate(5);
+/// assert_eq!(code.len(), 8);
+/// assert!(code.bytes().all(|b| b.is_ascii_digit()));
+///
+/// // The client may have generated a few codes the server never saw
+/// assert_eq!(hotp.verify(&code, 3, 2), Some(5));
+/// assert_eq!(hotp.verify(&code, 3, 1), None);
+/// ```
+#[derive(Clone)]
+pub struct Hotp {
+    key: Vec<u8>,
+    digits: u32,
+}
+
+/// A TOTP generator and validator, deriving HOTP counters from the Unix time, in steps of a period in seconds.
+///
+/// Codes from up to `skew` steps before or after the current one are accepted, one by default,
+/// to make up for clock drift and the time taken to type the code in.
+#[derive(Clone)]
+pub struct Totp {
+    hotp: Hotp,
+    period: u64,
+    skew: u8,
+}
+
+
+impl Hotp {
+    /// Create an HOTP generator for the specified key, producing codes of the specified amount of digits.
+    ///
+    /// Returns `Err(BlakeError::BadLength)` if the key is shorter than `MIN_KEY_SIZE`,
+    /// or if `digits` is outside of `6..=9`, as in RFC 4226.
+    pub fn new(key: &[u8], digits: u32) -> Result<Hotp> {
+        if key.len() < MIN_KEY_SIZE || !(6..=9).contains(&digits) {
+            return Err(BlakeError::BadLength);
+        }
+        Ok(Hotp {
+            key: key.to_vec(),
+            digits,
+        })
+    }
+
+    /// The amount of digits in the codes.
+    pub fn digits(&self) -> u32 {
+        self.digits
+    }
+
+    /// Generate the code for the specified counter, zero-padded to the amount of digits.
+    pub fn generate(&self, counter: u64) -> String {
+        let mac = hmac(&self.key, &counter.to_be_bytes());
+        let mac = mac.as_bytes();
+
+        let offset = (mac[b256::DIGEST_SIZE - 1] & 0x0F) as usize;
+        let number = u32::from_be_bytes([mac[offset], mac[offset + 1], mac[offset + 2], mac[offset + 3]]) & 0x7FFF_FFFF;
+        format!("{:01$}", number % 10u32.pow(self.digits), self.digits as usize)
+    }
+
+    /// Check the code against the codes for the specified counter and up to `look_ahead` counters after it,
+    /// returning the one it matched.
+    ///
+    /// The caller should store the counter after the one returned, so the code can't be used again,
+    /// and limit the amount of failed attempts, since the codes are short enough to be guessed.
+    ///
+    /// Every candidate is generated and compared in constant time, so the time taken doesn't depend on which matched;
+    /// it grows linearly with `look_ahead`, each candidate costing an HMAC, so keep it small.
+    pub fn verify(&self, code: &str, counter: u64, look_ahead: u16) -> Option<u64> {
+        let mut matched = None;
+        for candidate in counter..=counter.saturating_add(look_ahead.into()) {
+            if constant_time_eq(self.generate(candidate).as_bytes(), code.as_bytes()) && matched.is_none() {
+                matched = Some(candidate);
+            }
+        }
+        matched
+    }
+}
+
+impl Totp {
+    /// Create a TOTP generator for the specified key, producing codes of the specified amount of digits,
+    /// changing every `period` seconds, RFC 6238 recommending 30, accepting codes up to one step off.
+    ///
+    /// Returns `Err(BlakeError::BadLength)` if the key or `digits` are invalid, as in `Hotp::new()`,
+    /// or if `period` is `0`.
+    pub fn new(key: &[u8], digits: u32, period: u64) -> Result<Totp> {
+        if period == 0 {
+            return Err(BlakeError::BadLength);
+        }
+        Ok(Totp {
+            hotp: Hotp::new(key, digits)?,
+            period,
+            skew: 1,
+        })
+    }
+
+    /// Accept codes from up to the specified amount of steps before or after the current one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::otp::Totp;
+    /// let totp = Totp::new(b"12345678901234567890", 6, 30).unwrap().skew(0);
+    /// let code = totp.generate_at(1_111_111_110);
+    /// assert_eq!(totp.verify_at(&code, 1_111_111_110 + 29), Some(37_037_037));
+    /// assert_eq!(totp.verify_at(&code, 1_111_111_110 + 30), None);
+    /// ```
+    pub fn skew(mut self, steps: u8) -> Totp {
+        self.skew = steps;
+        self
+    }
+
+    /// The amount of digits in the codes.
+    pub fn digits(&self) -> u32 {
+        self.hotp.digits
+    }
+
+    /// The length of a step, in seconds.
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// The step the specified Unix time, in seconds, falls into, used as the HOTP counter.
+    pub fn time_step(&self, unix_time: u64) -> u64 {
+        unix_time / self.period
+    }
+
+    /// Generate the code for the current time.
+    pub fn generate(&self) -> String {
+        self.generate_at(now())
+    }
+
+    /// Generate the code for the specified Unix time, in seconds.
+    pub fn generate_at(&self, unix_time: u64) -> String {
+        self.hotp.generate(self.time_step(unix_time))
+    }
+
+    /// Check the code against those for the current time, returning the time step it matched.
+    ///
+    /// Refer to `verify_at()` for details.
+    pub fn verify(&self, code: &str) -> Option<u64> {
+        self.verify_at(code, now())
+    }
+
+    /// Check the code against those for the specified Unix time, in seconds, and up to `skew` steps around it,
+    /// returning the time step it matched.
+    ///
+    /// The caller should store the last step accepted and reject codes for it or earlier ones,
+    /// so a code can't be used twice, as RFC 6238 requires, and limit the amount of failed attempts.
+    pub fn verify_at(&self, code: &str, unix_time: u64) -> Option<u64> {
+        let step = self.time_step(unix_time);
+        let first = step.saturating_sub(self.skew.into());
+        // At most twice a u8
+        self.hotp.verify(code, first, (step.saturating_add(self.skew.into()) - first) as u16)
+    }
+}
+
+/// With the `zeroize` feature, the key is zeroed when dropped.
+impl Drop for Hotp {
+    fn drop(&mut self) {
+        if cfg!(feature = "zeroize") {
+            native::zero_bytes(&mut self.key);
+        }
+    }
+}
+
+
+/// Compute HMAC-BLAKE-256, as in RFC 2104, of the message under the key.
+///
+/// Keys longer than `BLOCK_SIZE` are hashed first.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::otp;
+/// let tag = otp::hmac(b"key", b"The lazy fox jumps over the lazy dog.");
+/// assert!(tag != otp::hmac(b"yek", b"The lazy fox jumps over the lazy dog."));
+///
+/// let long_key = [0x42; 100];
+/// assert_eq!(otp::hmac(&long_key, b"data"), otp::hmac(blake::b256::hash(&long_key[..]).as_bytes(), b"data"));
+/// ```
+pub fn hmac(key: &[u8], message: &[u8]) -> Digest {
+    let mut padded = [0; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        padded[..b256::DIGEST_SIZE].copy_from_slice(b256::hash(key).as_bytes());
+    } else {
+        padded[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner_pad = padded.map(|b| b ^ 0x36);
+    let mut outer_pad = padded.map(|b| b ^ 0x5C);
+
+    let mut inner = Hasher::new();
+    inner.update(&inner_pad[..]);
+    inner.update(message);
+    let inner = inner.finalise();
+
+    let mut outer = Hasher::new();
+    outer.update(&outer_pad[..]);
+    outer.update(inner.as_bytes());
+
+    if cfg!(feature = "zeroize") {
+        native::zero_bytes(&mut padded);
+        native::zero_bytes(&mut inner_pad);
+        native::zero_bytes(&mut outer_pad);
+    }
+    outer.finalise()
+}
+
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
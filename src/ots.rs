@@ -0,0 +1,341 @@
+//! Hash-based one-time signatures over BLAKE-256: Lamport and WOTS+.
+//!
+//! Each secret key may sign *one* message: signing consumes it, and signing a second message with a key derived from
+//! the same seed reveals enough of it to forge signatures.
+//!
+//! Secret keys are derived deterministically from a 32-byte seed, which must be secret and uniformly random,
+//! and messages are hashed with BLAKE-256 before signing.
+//!
+//! Lamport keys are large (16 KiB for either half) but simple:
+//! the secret key is a pair of random values for every bit of the message digest, the public key is their hashes,
+//! and a signature reveals one value of each pair, as selected by the bit.
+//!
+//! WOTS+ (with the Winternitz parameter *w* = 16, as in SPHINCS+) trades size for hashing:
+//! the digest is split into 64 nibbles, followed by a 3-nibble checksum, and the signature for each nibble *d* is
+//! *d* steps along a hash chain of length 15 starting at a secret value, the public key being the chains' ends.
+//! Each step is BLAKE-256 of the public seed, the chain index and step as big-endian `u32`s, and the previous value.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::ots::WotsSecretKey;
+//! let secret_key = WotsSecretKey::from_seed(&[0x42; 32]);
+//! let public_key = secret_key.public_key();
+//!
+//! let signature = secret_key.sign(b"The lazy fox jumps over the lazy dog.");
+//! assert!(public_key.verify(b"The lazy fox jumps over the lazy dog.", &signature));
+//! assert!(!public_key.verify(b"The lazy dog jumps over the lazy fox.", &signature));
+//! ```
+
+
+use super::{BlakeError, Result, constant_time_eq};
+use super::b256::{self, Hasher};
+
+
+/// The size of every value in keys and signatures.
+pub const VALUE_SIZE: usize = b256::DIGEST_SIZE;
+
+/// The number of values in a Lamport signature: one for each bit of the digest.
+pub const LAMPORT_VALUES: usize = VALUE_SIZE * 8;
+
+/// The number of chains in WOTS+ keys and signatures: 64 for the digest and 3 for the checksum.
+pub const WOTS_CHAINS: usize = WOTS_MESSAGE_CHAINS + 3;
+
+const WOTS_MESSAGE_CHAINS: usize = VALUE_SIZE * 2;
+const WOTS_W: u32 = 16;
+
+
+/// A Lamport secret key.
+pub struct LamportSecretKey {
+    values: Vec<[u8; VALUE_SIZE]>,
+}
+
+/// A Lamport public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LamportPublicKey {
+    hashes: Vec<[u8; VALUE_SIZE]>,
+}
+
+/// A Lamport signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LamportSignature {
+    values: Vec<[u8; VALUE_SIZE]>,
+}
+
+/// A WOTS+ secret key.
+pub struct WotsSecretKey {
+    seed: [u8; VALUE_SIZE],
+    public_seed: [u8; VALUE_SIZE],
+}
+
+/// A WOTS+ public key, consisting of the public seed and the chains' ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WotsPublicKey {
+    public_seed: [u8; VALUE_SIZE],
+    ends: Vec<[u8; VALUE_SIZE]>,
+}
+
+/// A WOTS+ signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WotsSignature {
+    values: Vec<[u8; VALUE_SIZE]>,
+}
+
+
+impl LamportSecretKey {
+    /// Derive the secret key from the specified seed.
+    #[must_use]
+    pub fn from_seed(seed: &[u8; VALUE_SIZE]) -> LamportSecretKey {
+        LamportSecretKey { values: (0..LAMPORT_VALUES as u32 * 2).map(|i| prf(b"lamport", seed, i)).collect() }
+    }
+
+    /// Get the public key corresponding to this secret key.
+    pub fn public_key(&self) -> LamportPublicKey {
+        LamportPublicKey { hashes: self.values.iter().map(|value| h(&[value])).collect() }
+    }
+
+    /// Sign the specified message, consuming the key.
+    pub fn sign(self, message: &[u8]) -> LamportSignature {
+        let digest = b256::hash(message);
+        LamportSignature { values: (0..LAMPORT_VALUES).map(|i| self.values[2 * i + bit(digest.as_bytes(), i)]).collect() }
+    }
+}
+
+/// Zeroising the secret key sets all its values to `0`.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for LamportSecretKey {
+    fn zeroize(&mut self) {
+        self.values.iter_mut().for_each(zeroize::Zeroize::zeroize);
+    }
+}
+
+/// With the `zeroize` feature, the secret key is zeroed when dropped.
+impl Drop for LamportSecretKey {
+    fn drop(&mut self) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+impl LamportPublicKey {
+    /// Check whether the signature was made for the specified message with the corresponding secret key.
+    pub fn verify(&self, message: &[u8], signature: &LamportSignature) -> bool {
+        let digest = b256::hash(message);
+        let mut ok = true;
+        for (i, value) in signature.values.iter().enumerate() {
+            ok &= constant_time_eq(&h(&[value]), &self.hashes[2 * i + bit(digest.as_bytes(), i)]);
+        }
+        ok
+    }
+
+    /// Serialise the public key as the concatenation of the hashes of both values for each bit, in order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        concat(&self.hashes)
+    }
+
+    /// Deserialise a public key serialised with `to_bytes()`.
+    ///
+    /// Returns `Err(BlakeError::BadEncoding)` if the data isn't of the right size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::ots::{LamportSecretKey, LamportPublicKey, LamportSignature};
+    /// let secret_key = LamportSecretKey::from_seed(&[0x42; 32]);
+    /// let public_key = LamportPublicKey::from_bytes(&secret_key.public_key().to_bytes()).unwrap();
+    ///
+    /// let signature = secret_key.sign(b"The lazy fox jumps over the lazy dog.").to_bytes();
+    /// assert_eq!(signature.len(), 8192);
+    ///
+    /// let signature = LamportSignature::from_bytes(&signature).unwrap();
+    /// assert!(public_key.verify(b"The lazy fox jumps over the lazy dog.", &signature));
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Result<LamportPublicKey> {
+        Ok(LamportPublicKey { hashes: split(data, LAMPORT_VALUES * 2)? })
+    }
+}
+
+impl LamportSignature {
+    /// Serialise the signature as the concatenation of the revealed values.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        concat(&self.values)
+    }
+
+    /// Deserialise a signature serialised with `to_bytes()`.
+    ///
+    /// Returns `Err(BlakeError::BadEncoding)` if the data isn't of the right size.
+    pub fn from_bytes(data: &[u8]) -> Result<LamportSignature> {
+        Ok(LamportSignature { values: split(data, LAMPORT_VALUES)? })
+    }
+}
+
+
+impl WotsSecretKey {
+    /// Derive the secret key from the specified seed.
+    #[must_use]
+    pub fn from_seed(seed: &[u8; VALUE_SIZE]) -> WotsSecretKey {
+        WotsSecretKey {
+            seed: prf(b"wots-secret", seed, 0),
+            public_seed: prf(b"wots-public", seed, 0),
+        }
+    }
+
+    /// Get the public key corresponding to this secret key.
+    pub fn public_key(&self) -> WotsPublicKey {
+        WotsPublicKey {
+            public_seed: self.public_seed,
+            ends: (0..WOTS_CHAINS).map(|i| chain(&self.public_seed, i, &prf(b"wots-chain", &self.seed, i as u32), 0, WOTS_W - 1)).collect(),
+        }
+    }
+
+    /// Sign the specified message, consuming the key.
+    pub fn sign(self, message: &[u8]) -> WotsSignature {
+        WotsSignature {
+            values: wots_digits(message)
+                .iter()
+                .enumerate()
+                .map(|(i, &digit)| chain(&self.public_seed, i, &prf(b"wots-chain", &self.seed, i as u32), 0, digit))
+                .collect(),
+        }
+    }
+}
+
+/// Zeroising the secret key sets its seeds to `0`.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for WotsSecretKey {
+    fn zeroize(&mut self) {
+        self.seed.zeroize();
+        self.public_seed.zeroize();
+    }
+}
+
+/// With the `zeroize` feature, the secret key is zeroed when dropped.
+impl Drop for WotsSecretKey {
+    fn drop(&mut self) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+impl WotsPublicKey {
+    /// Check whether the signature was made for the specified message with the corresponding secret key.
+    pub fn verify(&self, message: &[u8], signature: &WotsSignature) -> bool {
+        let mut ok = true;
+        for (i, (&digit, value)) in wots_digits(message).iter().zip(&signature.values).enumerate() {
+            ok &= constant_time_eq(&chain(&self.public_seed, i, value, digit, WOTS_W - 1 - digit), &self.ends[i]);
+        }
+        ok
+    }
+
+    /// Serialise the public key as the public seed followed by the concatenation of the chains' ends.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ret = self.public_seed.to_vec();
+        ret.extend(concat(&self.ends));
+        ret
+    }
+
+    /// Deserialise a public key serialised with `to_bytes()`.
+    ///
+    /// Returns `Err(BlakeError::BadEncoding)` if the data isn't of the right size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::ots::{WotsSecretKey, WotsPublicKey, WotsSignature};
+    /// let secret_key = WotsSecretKey::from_seed(&[0x42; 32]);
+    /// let public_key = secret_key.public_key().to_bytes();
+    /// assert_eq!(public_key.len(), 32 + 67 * 32);
+    ///
+    /// let public_key = WotsPublicKey::from_bytes(&public_key).unwrap();
+    /// let signature = WotsSignature::from_bytes(&secret_key.sign(b"The lazy fox jumps over the lazy dog.").to_bytes())
+    ///     .unwrap();
+    /// assert!(public_key.verify(b"The lazy fox jumps over the lazy dog.", &signature));
+    ///
+    /// assert_eq!(WotsPublicKey::from_bytes(b"The lazy fox"), Err(blake::BlakeError::BadEncoding));
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Result<WotsPublicKey> {
+        if data.len() < VALUE_SIZE {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        let mut public_seed = [0; VALUE_SIZE];
+        public_seed.copy_from_slice(&data[..VALUE_SIZE]);
+        Ok(WotsPublicKey {
+            public_seed,
+            ends: split(&data[VALUE_SIZE..], WOTS_CHAINS)?,
+        })
+    }
+}
+
+impl WotsSignature {
+    /// Serialise the signature as the concatenation of the chains' values.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        concat(&self.values)
+    }
+
+    /// Deserialise a signature serialised with `to_bytes()`.
+    ///
+    /// Returns `Err(BlakeError::BadEncoding)` if the data isn't of the right size.
+    pub fn from_bytes(data: &[u8]) -> Result<WotsSignature> {
+        Ok(WotsSignature { values: split(data, WOTS_CHAINS)? })
+    }
+}
+
+
+fn h(parts: &[&[u8]]) -> [u8; VALUE_SIZE] {
+    let mut hasher = Hasher::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    *hasher.finalise().as_bytes()
+}
+
+fn prf(domain: &[u8], seed: &[u8; VALUE_SIZE], index: u32) -> [u8; VALUE_SIZE] {
+    h(&[domain, seed, &index.to_be_bytes()])
+}
+
+/// Take `steps` steps along the chain with the specified index, from the value at step `start`.
+fn chain(public_seed: &[u8; VALUE_SIZE], index: usize, value: &[u8; VALUE_SIZE], start: u32, steps: u32) -> [u8; VALUE_SIZE] {
+    let mut value = *value;
+    for step in start..start + steps {
+        value = h(&[public_seed, &(index as u32).to_be_bytes(), &step.to_be_bytes(), &value]);
+    }
+    value
+}
+
+/// The base-16 digits of the message's digest, followed by those of the checksum.
+fn wots_digits(message: &[u8]) -> Vec<u32> {
+    let digest = b256::hash(message);
+
+    let mut digits = Vec::with_capacity(WOTS_CHAINS);
+    for byte in digest.as_bytes() {
+        digits.push((byte >> 4) as u32);
+        digits.push((byte & 0xF) as u32);
+    }
+
+    let checksum: u32 = digits.iter().map(|digit| WOTS_W - 1 - digit).sum();
+    digits.extend(&[(checksum >> 8) & 0xF, (checksum >> 4) & 0xF, checksum & 0xF]);
+    digits
+}
+
+fn bit(digest: &[u8], i: usize) -> usize {
+    ((digest[i / 8] >> (7 - i % 8)) & 1) as usize
+}
+
+fn concat(values: &[[u8; VALUE_SIZE]]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.iter().cloned()).collect()
+}
+
+fn split(data: &[u8], count: usize) -> Result<Vec<[u8; VALUE_SIZE]>> {
+    if data.len() != count * VALUE_SIZE {
+        return Err(BlakeError::BadEncoding);
+    }
+
+    Ok(data.chunks(VALUE_SIZE)
+        .map(|chunk| {
+            let mut value = [0; VALUE_SIZE];
+            value.copy_from_slice(chunk);
+            value
+        })
+        .collect())
+}
@@ -0,0 +1,156 @@
+//! Reusable hash states, for hot paths hashing many small messages.
+//!
+//! A `StatePool` keeps states around after they're used, so they need only be reset,
+//! instead of being allocated and freed every time. A pool can't be shared between threads,
+//! so getting and returning states never contends with other threads; keep one per thread, in a `thread_local!`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::pool::StatePool;
+//! # #[cfg(feature = "width-256")] {
+//! thread_local! {
+//!     static POOL: StatePool = StatePool::new();
+//! }
+//!
+//! let mut result_pooled = [0; 32];
+//! let mut result        = [0; 32];
+//! for msg in &[&b"Abolish"[..], b"the", b"bourgeoisie"] {
+//!     POOL.with(|pool| pool.hash(256, msg, &mut result_pooled)).unwrap();
+//!
+//!     blake::hash(256, msg, &mut result).unwrap();
+//!     assert_eq!(result_pooled, result);
+//! }
+//! # }
+//! ```
+
+
+use super::{Blake, Result, check_hashval, digest_size};
+use std::ops::{Deref, DerefMut};
+use std::cell::RefCell;
+
+
+/// A pool of hash states of all lengths, for use on a single thread.
+pub struct StatePool {
+    idle: RefCell<[Vec<Blake>; 4]>,
+    capacity: usize,
+}
+
+/// A state borrowed from a `StatePool`, returned to it when dropped.
+pub struct PooledState<'p> {
+    state: Option<Blake>,
+    pool: &'p StatePool,
+}
+
+
+impl StatePool {
+    /// Create a pool keeping at most 64 idle states of each length.
+    #[must_use]
+    pub fn new() -> StatePool {
+        StatePool::with_capacity(64)
+    }
+
+    /// Create a pool keeping at most the specified amount of idle states of each length.
+    ///
+    /// States returned to a full pool are freed.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> StatePool {
+        StatePool {
+            idle: RefCell::new([vec![], vec![], vec![], vec![]]),
+            capacity,
+        }
+    }
+
+    /// Get a fresh unsalted state of the specified length, reusing an idle one if available.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::pool::StatePool;
+    /// # #[cfg(feature = "width-512")] {
+    /// let pool = StatePool::new();
+    ///
+    /// let mut result = [0; 64];
+    /// let mut state = pool.get(512).unwrap();
+    /// state.update(b"The lazy fox ");
+    /// state.update(b"jumps over the lazy dog.");
+    /// state.finalise(&mut result);
+    /// assert_eq!(&result[..4], &[0x9A, 0xD4, 0x66, 0xCF]);
+    /// # }
+    /// ```
+    pub fn get<'p>(&'p self, hashbitlen: i32) -> Result<PooledState<'p>> {
+        let index = idle_index(hashbitlen)?;
+        let idle = self.idle.borrow_mut()[index].pop();
+        let state = match idle {
+            Some(state) => state,
+            None => Blake::new(hashbitlen)?,
+        };
+
+        Ok(PooledState {
+            state: Some(state),
+            pool: self,
+        })
+    }
+
+    /// Hash the data, as with `blake::hash()`, with a state from the pool.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    /// and `Err(BlakeError::BadLength)` if `hashval` is shorter than the hash function's size.
+    pub fn hash(&self, hashbitlen: i32, data: &[u8], hashval: &mut [u8]) -> Result<()> {
+        check_hashval(hashbitlen, hashval)?;
+
+        let mut state = self.get(hashbitlen)?;
+        state.update(data);
+        state.finalise(hashval);
+        Ok(())
+    }
+}
+
+impl Default for StatePool {
+    fn default() -> StatePool {
+        StatePool::new()
+    }
+}
+
+
+impl<'p> Deref for PooledState<'p> {
+    type Target = Blake;
+
+    fn deref(&self) -> &Blake {
+        self.state.as_ref().expect("state present until drop")
+    }
+}
+
+impl<'p> DerefMut for PooledState<'p> {
+    fn deref_mut(&mut self) -> &mut Blake {
+        self.state.as_mut().expect("state present until drop")
+    }
+}
+
+/// The state is reset, and so, with the `zeroize` feature, zeroed, before it's returned to the pool.
+impl<'p> Drop for PooledState<'p> {
+    fn drop(&mut self) {
+        let mut state = self.state.take().expect("state present until drop");
+        if let Ok(index) = idle_index(state.hashbitlen()) {
+            let idle = &mut self.pool.idle.borrow_mut()[index];
+            if idle.len() < self.pool.capacity {
+                state.reset();
+                idle.push(state);
+            }
+        }
+    }
+}
+
+
+/// Get the index of the idle states of the specified length.
+fn idle_index(hashbitlen: i32) -> Result<usize> {
+    digest_size(hashbitlen)?;
+    Ok(match hashbitlen {
+        224 => 0,
+        256 => 1,
+        384 => 2,
+        _ => 3,
+    })
+}
@@ -0,0 +1,312 @@
+//! A portable, pure-Rust implementation of BLAKE, transcribed from the reference implementation,
+//! for cross-checking the C one.
+
+
+use super::{BlakeError, Result, digest_size};
+#[cfg(feature = "simd")]
+use super::simd;
+
+
+pub const IV224: [u32; 8] = [0xC1059ED8, 0x367CD507, 0x3070DD17, 0xF70E5939, 0xFFC00B31, 0x68581511, 0x64F98FA7, 0xBEFA4FA4];
+pub const IV256: [u32; 8] = [0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19];
+
+const IV384: [u64; 8] = [0xCBBB9D5DC1059ED8, 0x629A292A367CD507, 0x9159015A3070DD17, 0x152FECD8F70E5939, 0x67332667FFC00B31,
+                         0x8EB44A8768581511, 0xDB0C2E0D64F98FA7, 0x47B5481DBEFA4FA4];
+const IV512: [u64; 8] = [0x6A09E667F3BCC908, 0xBB67AE8584CAA73B, 0x3C6EF372FE94F82B, 0xA54FF53A5F1D36F1, 0x510E527FADE682D1,
+                         0x9B05688C2B3E6C1F, 0x1F83D9ABFB41BD6B, 0x5BE0CD19137E2179];
+
+pub const C32: [u32; 16] = [0x243F6A88, 0x85A308D3, 0x13198A2E, 0x03707344, 0xA4093822, 0x299F31D0, 0x082EFA98, 0xEC4E6C89,
+                        0x452821E6, 0x38D01377, 0xBE5466CF, 0x34E90C6C, 0xC0AC29B7, 0xC97C50DD, 0x3F84D5B5, 0xB5470917];
+pub const C64: [u64; 16] = [0x243F6A8885A308D3, 0x13198A2E03707344, 0xA4093822299F31D0, 0x082EFA98EC4E6C89, 0x452821E638D01377,
+                        0xBE5466CF34E90C6C, 0xC0AC29B7C97C50DD, 0x3F84D5B5B5470917, 0x9216D5D98979FB1B, 0xD1310BA698DFB5AC,
+                        0x2FFD72DBD01ADFB7, 0xB8E1AFED6A267E96, 0xBA7C9045F12C7F99, 0x24A19947B3916CF7, 0x0801F2E2858EFC16,
+                        0x636920D871574E69];
+
+pub const SIGMA: [[usize; 16]; 10] = [[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+                                  [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+                                  [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+                                  [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+                                  [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+                                  [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+                                  [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+                                  [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+                                  [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+                                  [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0]];
+
+pub const ROUNDS32: usize = 14;
+pub const ROUNDS64: usize = 16;
+
+/// Name of this backend.
+pub const BACKEND: &str = "portable";
+
+
+/// A hash state, as `hashState` in the reference implementation, for whole bytes only.
+#[derive(Clone)]
+pub enum Portable {
+    Narrow {
+        hashbitlen: i32,
+        h: [u32; 8],
+        salt: [u32; 4],
+        /// Message bits compressed so far
+        t: u64,
+        buffer: [u8; 64],
+        buffered: usize,
+        compress: Compress32,
+    },
+    Wide {
+        hashbitlen: i32,
+        h: [u64; 8],
+        salt: [u64; 4],
+        /// Message bits compressed so far
+        t: u128,
+        buffer: [u8; 128],
+        buffered: usize,
+        compress: Compress64,
+    },
+}
+
+/// A compression function for BLAKE-224 and BLAKE-256, scalar or vectorised.
+pub type Compress32 = fn(&[u32; 8], &[u32; 16], &[u32; 4], u64) -> [u32; 8];
+
+/// A compression function for BLAKE-384 and BLAKE-512, scalar or vectorised.
+pub type Compress64 = fn(&[u64; 8], &[u64; 16], &[u64; 4], u128) -> [u64; 8];
+
+
+impl Portable {
+    pub fn new(hashbitlen: i32) -> Result<Portable> {
+        Portable::with_compression(hashbitlen, compress32, compress64)
+    }
+
+    /// Create a state using the `std::simd` compression functions.
+    #[cfg(feature = "simd")]
+    pub fn new_simd(hashbitlen: i32) -> Result<Portable> {
+        Portable::with_compression(hashbitlen, simd::compress32, simd::compress64)
+    }
+
+    fn with_compression(hashbitlen: i32, compress_narrow: Compress32, compress_wide: Compress64) -> Result<Portable> {
+        digest_size(hashbitlen)?;
+        let narrow = |iv| {
+            Portable::Narrow {
+                hashbitlen,
+                h: iv,
+                salt: [0; 4],
+                t: 0,
+                buffer: [0; 64],
+                buffered: 0,
+                compress: compress_narrow,
+            }
+        };
+        let wide = |iv| {
+            Portable::Wide {
+                hashbitlen,
+                h: iv,
+                salt: [0; 4],
+                t: 0,
+                buffer: [0; 128],
+                buffered: 0,
+                compress: compress_wide,
+            }
+        };
+
+        match hashbitlen {
+            224 => Ok(narrow(IV224)),
+            256 => Ok(narrow(IV256)),
+            384 => Ok(wide(IV384)),
+            512 => Ok(wide(IV512)),
+            _ => Err(BlakeError::BadHashbitlen),
+        }
+    }
+
+    /// As `BLAKE_Hash_AddSalt()`, but panicking if the salt's too short instead of reading out of bounds.
+    pub fn add_salt(&mut self, salt_bytes: &[u8]) {
+        match *self {
+            Portable::Narrow { ref mut salt, .. } => {
+                for (word, bytes) in salt.iter_mut().zip(salt_bytes[..16].chunks(4)) {
+                    *word = be_u32(bytes);
+                }
+            }
+            Portable::Wide { ref mut salt, .. } => {
+                for (word, bytes) in salt.iter_mut().zip(salt_bytes[..32].chunks(8)) {
+                    *word = be_u64(bytes);
+                }
+            }
+        }
+    }
+
+    /// As `BLAKE_Hash_Update()`: blocks are compressed as soon as they're full.
+    pub fn update(&mut self, mut data: &[u8]) {
+        match *self {
+            Portable::Narrow { ref mut h, ref salt, ref mut t, ref mut buffer, ref mut buffered, compress, .. } => {
+                while !data.is_empty() {
+                    let taken = (buffer.len() - *buffered).min(data.len());
+                    buffer[*buffered..*buffered + taken].copy_from_slice(&data[..taken]);
+                    *buffered += taken;
+                    data = &data[taken..];
+
+                    if *buffered == buffer.len() {
+                        *t += 512;
+                        *h = compress(h, &words32(buffer), salt, *t);
+                        *buffered = 0;
+                    }
+                }
+            }
+            Portable::Wide { ref mut h, ref salt, ref mut t, ref mut buffer, ref mut buffered, compress, .. } => {
+                while !data.is_empty() {
+                    let taken = (buffer.len() - *buffered).min(data.len());
+                    buffer[*buffered..*buffered + taken].copy_from_slice(&data[..taken]);
+                    *buffered += taken;
+                    data = &data[taken..];
+
+                    if *buffered == buffer.len() {
+                        *t += 1024;
+                        *h = compress(h, &words64(buffer), salt, *t);
+                        *buffered = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    /// As `BLAKE_Hash_Final()`, without consuming the state.
+    pub fn finalise(&self, hashval: &mut [u8]) {
+        match *self {
+            Portable::Narrow { hashbitlen, mut h, ref salt, t, ref buffer, buffered, compress } => {
+                let total = t + buffered as u64 * 8;
+                let mut block = [0; 64];
+                block[..buffered].copy_from_slice(&buffer[..buffered]);
+                block[buffered] = 0x80;
+
+                // A block holding only padding is compressed with a counter of 0
+                if buffered > 55 {
+                    h = compress(&h, &words32(&block), salt, total);
+                    block = [0; 64];
+                }
+                if hashbitlen == 256 {
+                    block[55] |= 0x01;
+                }
+                block[56..].copy_from_slice(&total.to_be_bytes());
+                h = compress(&h, &words32(&block), salt, if buffered == 0 || buffered > 55 { 0 } else { total });
+
+                for (bytes, word) in hashval.chunks_mut(4).zip(h.iter()).take(hashbitlen as usize / 32) {
+                    bytes.copy_from_slice(&word.to_be_bytes());
+                }
+            }
+            Portable::Wide { hashbitlen, mut h, ref salt, t, ref buffer, buffered, compress } => {
+                let total = t + buffered as u128 * 8;
+                let mut block = [0; 128];
+                block[..buffered].copy_from_slice(&buffer[..buffered]);
+                block[buffered] = 0x80;
+
+                if buffered > 111 {
+                    h = compress(&h, &words64(&block), salt, total);
+                    block = [0; 128];
+                }
+                if hashbitlen == 512 {
+                    block[111] |= 0x01;
+                }
+                block[112..].copy_from_slice(&total.to_be_bytes());
+                h = compress(&h, &words64(&block), salt, if buffered == 0 || buffered > 111 { 0 } else { total });
+
+                for (bytes, word) in hashval.chunks_mut(8).zip(h.iter()).take(hashbitlen as usize / 64) {
+                    bytes.copy_from_slice(&word.to_be_bytes());
+                }
+            }
+        }
+    }
+}
+
+
+/// BLAKE-224 and BLAKE-256's compression function, as `compress32()` in the reference implementation.
+pub fn compress32(chain: &[u32; 8], message: &[u32; 16], salt: &[u32; 4], counter: u64) -> [u32; 8] {
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(chain);
+    for i in 0..4 {
+        v[8 + i] = salt[i] ^ C32[i];
+    }
+    v[12] = counter as u32 ^ C32[4];
+    v[13] = counter as u32 ^ C32[5];
+    v[14] = (counter >> 32) as u32 ^ C32[6];
+    v[15] = (counter >> 32) as u32 ^ C32[7];
+
+    for round in 0..ROUNDS32 {
+        let sigma = &SIGMA[round % 10];
+        for (i, &(a, b, c, d)) in COLUMNS_DIAGONALS.iter().enumerate() {
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(message[sigma[2 * i]] ^ C32[sigma[2 * i + 1]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(12);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(message[sigma[2 * i + 1]] ^ C32[sigma[2 * i]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(8);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(7);
+        }
+    }
+
+    let mut ret = *chain;
+    for i in 0..8 {
+        ret[i] ^= v[i] ^ v[i + 8] ^ salt[i % 4];
+    }
+    ret
+}
+
+/// BLAKE-384 and BLAKE-512's compression function, as `compress64()` in the reference implementation.
+pub fn compress64(chain: &[u64; 8], message: &[u64; 16], salt: &[u64; 4], counter: u128) -> [u64; 8] {
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(chain);
+    for i in 0..4 {
+        v[8 + i] = salt[i] ^ C64[i];
+    }
+    v[12] = counter as u64 ^ C64[4];
+    v[13] = counter as u64 ^ C64[5];
+    v[14] = (counter >> 64) as u64 ^ C64[6];
+    v[15] = (counter >> 64) as u64 ^ C64[7];
+
+    for round in 0..ROUNDS64 {
+        let sigma = &SIGMA[round % 10];
+        for (i, &(a, b, c, d)) in COLUMNS_DIAGONALS.iter().enumerate() {
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(message[sigma[2 * i]] ^ C64[sigma[2 * i + 1]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(32);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(25);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(message[sigma[2 * i + 1]] ^ C64[sigma[2 * i]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(11);
+        }
+    }
+
+    let mut ret = *chain;
+    for i in 0..8 {
+        ret[i] ^= v[i] ^ v[i + 8] ^ salt[i % 4];
+    }
+    ret
+}
+
+/// The state words each of the G function's eight applications per round mixes.
+const COLUMNS_DIAGONALS: [(usize, usize, usize, usize); 8] =
+    [(0, 4, 8, 12), (1, 5, 9, 13), (2, 6, 10, 14), (3, 7, 11, 15), (0, 5, 10, 15), (1, 6, 11, 12), (2, 7, 8, 13), (3, 4, 9, 14)];
+
+
+pub fn be_u32(bytes: &[u8]) -> u32 {
+    (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32
+}
+
+fn be_u64(bytes: &[u8]) -> u64 {
+    (be_u32(&bytes[..4]) as u64) << 32 | be_u32(&bytes[4..]) as u64
+}
+
+fn words32(block: &[u8; 64]) -> [u32; 16] {
+    let mut ret = [0; 16];
+    for (word, bytes) in ret.iter_mut().zip(block.chunks(4)) {
+        *word = be_u32(bytes);
+    }
+    ret
+}
+
+fn words64(block: &[u8; 128]) -> [u64; 16] {
+    let mut ret = [0; 16];
+    for (word, bytes) in ret.iter_mut().zip(block.chunks(8)) {
+        *word = be_u64(bytes);
+    }
+    ret
+}
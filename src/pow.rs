@@ -0,0 +1,177 @@
+//! Hashcash-style proof of work, for anti-spam tokens.
+//!
+//! A nonce is valid for a prefix (the challenge, like a message and the recipient) at a difficulty if
+//! BLAKE-256 of the prefix followed by the nonce as a little-endian `u64` starts with at least that many zero bits.
+//! Finding one takes 2<sup>difficulty</sup> hashes on average, checking it takes one.
+//!
+//! # Examples
+//!
+//! ```
+//! let nonce = blake::pow::search(b"alice@example.com 2026-10-16", 12, 0..u64::MAX).unwrap();
+//! assert!(blake::pow::verify(b"alice@example.com 2026-10-16", 12, nonce));
+//! ```
+
+
+use super::{BlakeError, Result};
+use super::stack::StackState;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::ops::Range;
+use std::thread;
+
+
+/// Options for `search_with()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchOptions<'a> {
+    threads: usize,
+    cancel: Option<&'a AtomicBool>,
+}
+
+/// How many nonces each thread tries between checking whether to stop.
+const BATCH_SIZE: u64 = 1024;
+
+
+impl<'a> SearchOptions<'a> {
+    /// Create options searching on as many threads as there are cores, with no cancellation.
+    #[must_use]
+    pub fn new() -> SearchOptions<'a> {
+        SearchOptions::default()
+    }
+
+    /// Search on the specified amount of threads; `0` uses as many as there are cores, `1` the calling thread.
+    pub fn threads(mut self, threads: usize) -> SearchOptions<'a> {
+        self.threads = threads;
+        self
+    }
+
+    /// Stop searching soon after the specified flag is set, returning `Err(BlakeError::Cancelled)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::pow::{self, SearchOptions};
+    /// # use blake::BlakeError;
+    /// # use std::sync::atomic::AtomicBool;
+    /// let cancel = AtomicBool::new(true);
+    /// assert_eq!(pow::search_with(b"prefix", 256, 0..u64::MAX, &SearchOptions::new().cancel(&cancel)),
+    ///            Err(BlakeError::Cancelled));
+    /// ```
+    pub fn cancel(mut self, flag: &'a AtomicBool) -> SearchOptions<'a> {
+        self.cancel = Some(flag);
+        self
+    }
+}
+
+
+/// Find the smallest nonce in the range valid for the prefix at the specified difficulty, on all cores.
+///
+/// # Panics
+///
+/// If `difficulty_bits` is over 256.
+pub fn search(prefix: &[u8], difficulty_bits: u32, range: Range<u64>) -> Option<u64> {
+    search_with(prefix, difficulty_bits, range, &SearchOptions::new()).expect("search not cancellable")
+}
+
+/// Find the smallest nonce in the range valid for the prefix at the specified difficulty, as configured.
+///
+/// Threads try interleaved nonces, and stop once every nonce below the smallest valid one found has been tried,
+/// so the result doesn't depend on the amount of threads.
+///
+/// Returns `Err(BlakeError::Cancelled)` if cancelled via `SearchOptions::cancel()`.
+///
+/// # Panics
+///
+/// If `difficulty_bits` is over 256.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::pow::{self, SearchOptions};
+/// assert_eq!(pow::search_with(b"prefix", 8, 0..u64::MAX, &SearchOptions::new().threads(1)),
+///            pow::search_with(b"prefix", 8, 0..u64::MAX, &SearchOptions::new().threads(4)));
+///
+/// // Nothing this hard in a range this small
+/// assert_eq!(pow::search(b"prefix", 64, 0..100), None);
+/// ```
+pub fn search_with(prefix: &[u8], difficulty_bits: u32, range: Range<u64>, options: &SearchOptions<'_>) -> Result<Option<u64>> {
+    assert!(difficulty_bits <= 256, "pow::search() difficulty over 256 bits");
+
+    let mut midstate = StackState::new(256).expect("256 is a valid hashbitlen");
+    midstate.update(prefix);
+
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        threads => threads,
+    } as u64;
+    let found = AtomicU64::new(u64::MAX);
+    let search_from = |first: u64| {
+        let midstate = midstate.clone();
+        let mut nonce = first;
+        'batches: loop {
+            if options.cancel.map(|c| c.load(Ordering::Relaxed)).unwrap_or(false) {
+                return Err(BlakeError::Cancelled);
+            }
+            for _ in 0..BATCH_SIZE {
+                if nonce >= range.end || nonce >= found.load(Ordering::Relaxed) {
+                    break 'batches;
+                }
+                if valid(&midstate, difficulty_bits, nonce) {
+                    found.fetch_min(nonce, Ordering::Relaxed);
+                    break 'batches;
+                }
+                nonce = match nonce.checked_add(threads) {
+                    Some(next) => next,
+                    None => break 'batches,
+                };
+            }
+        }
+        Ok(())
+    };
+
+    if threads <= 1 {
+        search_from(range.start)?;
+    } else {
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .filter_map(|i| range.start.checked_add(i))
+                .map(|first| scope.spawn(move || search_from(first)))
+                .collect();
+            workers.into_iter().try_for_each(|worker| worker.join().expect("searching thread panicked"))
+        })?;
+    }
+
+    match found.into_inner() {
+        u64::MAX => Ok(None),
+        nonce => Ok(Some(nonce)),
+    }
+}
+
+/// Check whether the nonce is valid for the prefix at the specified difficulty.
+///
+/// # Examples
+///
+/// ```
+/// assert!(blake::pow::verify(b"prefix", 0, 1234));
+/// assert!(!blake::pow::verify(b"prefix", 256, 1234));
+/// ```
+pub fn verify(prefix: &[u8], difficulty_bits: u32, nonce: u64) -> bool {
+    let mut state = StackState::new(256).expect("256 is a valid hashbitlen");
+    state.update(prefix);
+    valid(&state, difficulty_bits, nonce)
+}
+
+
+fn valid(midstate: &StackState, difficulty_bits: u32, nonce: u64) -> bool {
+    let mut state = midstate.clone();
+    state.update(nonce.to_le_bytes());
+
+    let mut digest = [0; 32];
+    state.finalise(&mut digest).expect("the buffer fits the digest");
+    leading_zero_bits(&digest) >= difficulty_bits
+}
+
+fn leading_zero_bits(digest: &[u8]) -> u32 {
+    match digest.iter().position(|&byte| byte != 0) {
+        Some(idx) => idx as u32 * 8 + digest[idx].leading_zeros(),
+        None => digest.len() as u32 * 8,
+    }
+}
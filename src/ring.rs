@@ -0,0 +1,168 @@
+//! Consistent hashing and rendezvous hashing, over BLAKE-256.
+//!
+//! `HashRing` places each node at several points ("virtual replicas") on a ring of 64-bit positions, and assigns each
+//! key to the node at the first point at or after the key's position, wrapping around, so adding or removing a node
+//! only moves the keys between it and its neighbours.
+//! A node's *i*th point is the first 8 bytes, as a big-endian `u64`, of the BLAKE-256 of the length of the node's name
+//! as a little-endian `u64`, the name, and *i* as a little-endian `u32`; a key's is that of the BLAKE-256 of the key.
+//!
+//! `rendezvous()` (highest random weight hashing) instead scores every node for the key, and picks the highest;
+//! it needs no state, but is linear in the amount of nodes. A node's score for a key is the first 8 bytes,
+//! as a big-endian `u64`, of the BLAKE-256 of the length of the node's name as a little-endian `u64`, the name,
+//! and the key.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::ring::HashRing;
+//! let mut ring = HashRing::new(100);
+//! ring.add("alpha");
+//! ring.add("beta");
+//! ring.add("gamma");
+//!
+//! let owner = *ring.get(b"user:1312").unwrap();
+//! ring.remove(&"beta");
+//! if owner != "beta" {
+//!     // Keys of other nodes stay put
+//!     assert_eq!(*ring.get(b"user:1312").unwrap(), owner);
+//! }
+//! ```
+
+
+use super::b256::Hasher;
+use std::collections::BTreeMap;
+
+
+/// A consistent hashing ring, as described in the module-level documentation.
+#[derive(Debug, Clone)]
+pub struct HashRing<N> {
+    replicas: u32,
+    points: BTreeMap<u64, N>,
+}
+
+
+impl<N: AsRef<[u8]> + Clone + PartialEq> HashRing<N> {
+    /// Create an empty ring, placing every node at the specified amount of points.
+    ///
+    /// # Panics
+    ///
+    /// If `replicas` is 0.
+    #[must_use]
+    pub fn new(replicas: u32) -> HashRing<N> {
+        assert!(replicas != 0, "HashRing::new(): replicas must be positive");
+        HashRing {
+            replicas,
+            points: BTreeMap::new(),
+        }
+    }
+
+    /// Add a node to the ring.
+    ///
+    /// In the unlikely event of a collision, the point is kept by the node added earlier.
+    pub fn add(&mut self, node: N) {
+        for replica in 0..self.replicas {
+            self.points.entry(position(node_hasher(node.as_ref()), &replica.to_le_bytes())).or_insert_with(|| node.clone());
+        }
+    }
+
+    /// Remove a node from the ring.
+    pub fn remove(&mut self, node: &N) {
+        self.points.retain(|_, point| point != node);
+    }
+
+    /// Get the node owning the specified key, or `None` if the ring is empty.
+    pub fn get(&self, key: &[u8]) -> Option<&N> {
+        self.successors(key).next()
+    }
+
+    /// Get up to `count` distinct nodes for the specified key: its owner, followed by the next nodes on the ring,
+    /// as for replicating the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::ring::HashRing;
+    /// let mut ring = HashRing::new(100);
+    /// for node in &["alpha", "beta", "gamma"] {
+    ///     ring.add(*node);
+    /// }
+    ///
+    /// let replicas = ring.get_n(b"user:1312", 2);
+    /// assert_eq!(replicas.len(), 2);
+    /// assert_eq!(replicas[0], ring.get(b"user:1312").unwrap());
+    /// assert!(replicas[0] != replicas[1]);
+    ///
+    /// assert_eq!(ring.get_n(b"user:1312", 5).len(), 3);
+    /// ```
+    pub fn get_n(&self, key: &[u8], count: usize) -> Vec<&N> {
+        let mut ret: Vec<&N> = Vec::with_capacity(count);
+        for node in self.successors(key) {
+            if ret.len() == count {
+                break;
+            }
+            if !ret.contains(&node) {
+                ret.push(node);
+            }
+        }
+        ret
+    }
+
+    /// Get the amount of points on the ring.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Check whether the ring has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+
+    /// The nodes at all points, starting from the key's position and wrapping around.
+    fn successors<'a>(&'a self, key: &[u8]) -> Box<dyn Iterator<Item = &'a N> + 'a> {
+        let key = position(Hasher::new(), key);
+        Box::new(self.points.range(key..).chain(self.points.range(..key)).map(|(_, node)| node))
+    }
+}
+
+
+/// Get the node with the highest score for the key, or `None` if there are none, as described in the module-level
+/// documentation.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::ring;
+/// let nodes = ["alpha", "beta", "gamma"];
+/// let owner = *ring::rendezvous(&nodes, b"user:1312").unwrap();
+///
+/// // Removing any other node doesn't change the owner
+/// let other = if owner == "alpha" { "beta" } else { "alpha" };
+/// let remaining: Vec<_> = nodes.iter().cloned().filter(|&node| node != other).collect();
+/// assert_eq!(*ring::rendezvous(&remaining, b"user:1312").unwrap(), owner);
+/// ```
+pub fn rendezvous<'n, N: AsRef<[u8]>>(nodes: &'n [N], key: &[u8]) -> Option<&'n N> {
+    nodes.iter().max_by_key(|node| position(node_hasher(node.as_ref()), key))
+}
+
+/// Get all nodes, in decreasing order of score for the key, as for replicating the key to the first few.
+pub fn rendezvous_ranked<'n, N: AsRef<[u8]>>(nodes: &'n [N], key: &[u8]) -> Vec<&'n N> {
+    let mut ret: Vec<_> = nodes.iter().map(|node| (position(node_hasher(node.as_ref()), key), node)).collect();
+    ret.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    ret.into_iter().map(|(_, node)| node).collect()
+}
+
+
+fn node_hasher(node: &[u8]) -> Hasher {
+    let mut hasher = Hasher::new();
+    hasher.update((node.len() as u64).to_le_bytes());
+    hasher.update(node);
+    hasher
+}
+
+fn position(mut hasher: Hasher, data: &[u8]) -> u64 {
+    hasher.update(data);
+    let mut position = [0; 8];
+    position.copy_from_slice(&hasher.finalise().as_bytes()[..8]);
+    u64::from_be_bytes(position)
+}
@@ -0,0 +1,138 @@
+use super::{Blake, Result as BlakeResult, hash, digest_size};
+use super::portable::{self, Portable};
+#[cfg(feature = "simd")]
+use super::simd;
+use super::bench::BACKEND;
+use super::encoding::hex_decode;
+use std::error::Error;
+use std::fmt;
+
+
+/// A known-answer test that failed in `selftest()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestError {
+    /// The backend the test was run on
+    pub backend: &'static str,
+    /// The length of the hash function tested
+    pub hashbitlen: i32,
+    /// The amount of zero bytes hashed
+    pub message_length: usize,
+    /// The interface exercised, `"one-shot"` or `"streaming"`
+    pub mode: &'static str,
+    /// The digest from the BLAKE specification
+    pub expected: Vec<u8>,
+    /// The digest computed
+    pub actual: Vec<u8>,
+}
+
+
+/// The examples from Appendix A of the BLAKE specification: each hash function's digests of one and of two blocks'
+/// worth, less the padding, of zero bytes.
+pub const VECTORS: &[(i32, usize, &str)] =
+    &[(224, 1, "4504CB0314FB2A4F7A692E696E487912FE3F2468FE312C73A5278EC5"),
+      (224, 72, "F5AA00DD1CB847E3140372AF7B5C46B4888D82C8C0A917913CFB5D04"),
+      (256, 1, "0CE8D4EF4DD7CD8D62DFDED9D4EDB0A774AE6A41929A74DA23109E8F11139C87"),
+      (256, 72, "D419BAD32D504FB7D44D460C42C5593FE544FA4C135DEC31E21BD9ABDCC22D41"),
+      (384, 1, "10281F67E135E90AE8E882251A355510A719367AD70227B137343E1BC122015C29391E8545B5272D13A7C2879DA3D807"),
+      (384, 144, "0B9845DD429566CDAB772BA195D271EFFE2D0211F16991D766BA749447C5CDE569780B2DAA66C4B224A2EC2E5D09174C"),
+      (512,
+       1,
+       "97961587F6D970FABA6D2478045DE6D1FABD09B61AE50932054D52BC29D31BE4FF9102B9F69E2BBDB83BE13D4B9C06091E5FA0B48BD081B634058BE0EC49BEB3"),
+      (512,
+       144,
+       "313717D608E9CF758DCB1EB0F0C3CF9FC150B2D500FB33F51C52AFC99D358A2F1374B8A38BBA7974E7F6EF79CAB16F22CE1E649D6E01AD9589C213045D545DDE")];
+
+
+/// Run known-answer tests for every hash length compiled in, through both the one-shot and the streaming interfaces,
+/// on every backend compiled in (the C one, the portable Rust one, and, with the `simd` feature, its vectorised version),
+/// returning the first failure.
+///
+/// This is meant as a power-up self-test, for environments requiring the implementation to check itself before use.
+///
+/// # Examples
+///
+/// ```
+/// blake::selftest().unwrap();
+/// ```
+pub fn selftest() -> Result<(), SelfTestError> {
+    let compiled_in = VECTORS.iter().filter(|&&(hashbitlen, ..)| digest_size(hashbitlen).is_ok());
+    for &(hashbitlen, message_length, expected) in compiled_in {
+        let expected = hex_decode(expected).expect("test vectors are valid hex");
+        let message = vec![0; message_length];
+        let mut actual = vec![0; digest_size(hashbitlen).expect("test vectors have valid hashbitlens")];
+
+        hash(hashbitlen, &message, &mut actual).expect("test vectors have valid hashbitlens");
+        check(BACKEND, hashbitlen, message_length, "one-shot", &expected, &actual)?;
+
+        let mut state = Blake::new(hashbitlen).expect("test vectors have valid hashbitlens");
+        for byte in message.chunks(1) {
+            state.update(byte);
+        }
+        state.finalise(&mut actual);
+        check(BACKEND, hashbitlen, message_length, "streaming", &expected, &actual)?;
+
+        check_portable(portable::BACKEND, Portable::new, hashbitlen, message_length, &expected)?;
+        #[cfg(feature = "simd")]
+        check_portable(simd::BACKEND, Portable::new_simd, hashbitlen, message_length, &expected)?;
+    }
+
+    Ok(())
+}
+
+/// Run the known-answer test through a portable state from the specified constructor, whole and byte by byte.
+fn check_portable(backend: &'static str, new: fn(i32) -> BlakeResult<Portable>, hashbitlen: i32, message_length: usize, expected: &[u8])
+                  -> Result<(), SelfTestError> {
+    let state = new(hashbitlen).expect("test vectors have valid hashbitlens");
+    let message = vec![0; message_length];
+    let mut actual = vec![0; expected.len()];
+
+    let mut whole = state.clone();
+    whole.update(&message);
+    whole.finalise(&mut actual);
+    check(backend, hashbitlen, message_length, "one-shot", expected, &actual)?;
+
+    let mut streamed = state;
+    for byte in message.chunks(1) {
+        streamed.update(byte);
+    }
+    streamed.finalise(&mut actual);
+    check(backend, hashbitlen, message_length, "streaming", expected, &actual)
+}
+
+fn check(backend: &'static str, hashbitlen: i32, message_length: usize, mode: &'static str, expected: &[u8], actual: &[u8])
+         -> Result<(), SelfTestError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(SelfTestError {
+            backend,
+            hashbitlen,
+            message_length,
+            mode,
+            expected: expected.to_vec(),
+            actual: actual.to_vec(),
+        })
+    }
+}
+
+
+impl Error for SelfTestError {}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,
+               "BLAKE-{} {} known-answer test for {} zero bytes failed on the {} backend: expected ",
+               self.hashbitlen,
+               self.mode,
+               self.message_length,
+               self.backend)?;
+        for byte in &self.expected {
+            write!(f, "{:02x}", byte)?;
+        }
+        f.write_str(", got ")?;
+        for byte in &self.actual {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! The portable implementation's compression functions, vectorised with `std::simd`,
+//! for any target with SIMD registers, without per-ISA intrinsics.
+//!
+//! The state is held as four rows of four words; the G function is applied to all columns at once,
+//! then to all diagonals, by rotating the rows' lanes to line the diagonals up into columns and back.
+
+
+use super::portable::{C32, C64, SIGMA, ROUNDS32, ROUNDS64};
+use std::simd::{u32x4, u64x4};
+
+
+/// Name of this backend.
+pub const BACKEND: &str = "portable-simd";
+
+
+/// Generate a compression function over the specified vector and word types,
+/// with the specified constants, amount of rounds, and G function rotations.
+macro_rules! simd_compress {
+    ($name:ident, $vector:ident, $word:ident, $counter:ident, $constants:expr, $rounds:expr, $rotations:expr) => {
+        /// As the scalar compression function of the same name in the `portable` module.
+        pub fn $name(chain: &[$word; 8], message: &[$word; 16], salt: &[$word; 4], counter: $counter) -> [$word; 8] {
+            let constants = $constants;
+            let bits = $word::BITS;
+            let counter_lo = counter as $word;
+            let counter_hi = (counter >> bits) as $word;
+
+            let mut a = $vector::from_slice(&chain[..4]);
+            let mut b = $vector::from_slice(&chain[4..]);
+            let mut c = $vector::from_array(*salt) ^ $vector::from_slice(&constants[..4]);
+            let mut d = $vector::from_array([counter_lo, counter_lo, counter_hi, counter_hi]) ^
+                        $vector::from_slice(&constants[4..8]);
+
+            let rotate = |x: $vector, n: $word| (x >> $vector::splat(n)) | (x << $vector::splat(bits as $word - n));
+            let g = |a: &mut $vector, b: &mut $vector, c: &mut $vector, d: &mut $vector, m0: $vector, m1: $vector| {
+                *a += *b + m0;
+                *d = rotate(*d ^ *a, $rotations[0]);
+                *c += *d;
+                *b = rotate(*b ^ *c, $rotations[1]);
+                *a += *b + m1;
+                *d = rotate(*d ^ *a, $rotations[2]);
+                *c += *d;
+                *b = rotate(*b ^ *c, $rotations[3]);
+            };
+            let inputs = |sigma: &[usize; 16], first: usize| {
+                let pairs = [0, 1, 2, 3].map(|i| (sigma[2 * (first + i)], sigma[2 * (first + i) + 1]));
+                ($vector::from_array(pairs.map(|(x, y)| message[x] ^ constants[y])),
+                 $vector::from_array(pairs.map(|(x, y)| message[y] ^ constants[x])))
+            };
+
+            for round in 0..$rounds {
+                let sigma = &SIGMA[round % 10];
+
+                let (m0, m1) = inputs(sigma, 0);
+                g(&mut a, &mut b, &mut c, &mut d, m0, m1);
+
+                b = b.rotate_elements_left::<1>();
+                c = c.rotate_elements_left::<2>();
+                d = d.rotate_elements_left::<3>();
+                let (m0, m1) = inputs(sigma, 4);
+                g(&mut a, &mut b, &mut c, &mut d, m0, m1);
+                b = b.rotate_elements_right::<1>();
+                c = c.rotate_elements_right::<2>();
+                d = d.rotate_elements_right::<3>();
+            }
+
+            let salt = $vector::from_array(*salt);
+            let lo = $vector::from_slice(&chain[..4]) ^ a ^ c ^ salt;
+            let hi = $vector::from_slice(&chain[4..]) ^ b ^ d ^ salt;
+
+            let mut ret = [0; 8];
+            ret[..4].copy_from_slice(lo.as_array());
+            ret[4..].copy_from_slice(hi.as_array());
+            ret
+        }
+    }
+}
+
+simd_compress!(compress32, u32x4, u32, u64, C32, ROUNDS32, [16, 12, 8, 7]);
+simd_compress!(compress64, u64x4, u64, u128, C64, ROUNDS64, [32, 25, 16, 11]);
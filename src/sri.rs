@@ -0,0 +1,197 @@
+//! [Subresource Integrity](https://www.w3.org/TR/SRI)-style strings, like `blake256-<base64 digest>`.
+//!
+//! # Examples
+//!
+//! Pinning some content, then verifying it later.
+//!
+//! ```
+//! # use blake::sri::Sri;
+//! # #[cfg(feature = "width-256")] {
+//! let pin = Sri::new(256, b"The lazy fox jumps over the lazy dog.").unwrap().to_string();
+//! assert_eq!(pin, "blake256-8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=");
+//!
+//! assert_eq!(blake::sri::verify(&pin, b"The lazy fox jumps over the lazy dog."), Ok(true));
+//! assert_eq!(blake::sri::verify(&pin, b"The lazy fox jumps over the lazy cat."), Ok(false));
+//! # }
+//! ```
+
+
+use super::{BlakeError, Result, hash, digest_size, constant_time_eq};
+use super::encoding::{base64_encode, base64_decode};
+use std::str::FromStr;
+use std::fmt;
+
+
+/// A single parsed SRI hash expression, consisting of the hash function's length and the digest.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Sri {
+    hashbitlen: i32,
+    digest: Vec<u8>,
+}
+
+
+impl Sri {
+    /// Hash the specified data with the hash function of the given length and wrap the result.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` isn't a valid BLAKE length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::sri::Sri;
+    /// # #[cfg(feature = "width-224")] {
+    /// let sri = Sri::new(224, b"").unwrap();
+    /// assert_eq!(sri.hashbitlen(), 224);
+    /// assert_eq!(sri.to_string(), "blake224-fcUxOxwEUSoXS9ZQO4lgeuy+4JA9QKilaclO7Q==");
+    /// # }
+    /// ```
+    pub fn new(hashbitlen: i32, data: &[u8]) -> Result<Sri> {
+        let mut digest = vec![0; digest_size(hashbitlen)?];
+        hash(hashbitlen, data, &mut digest)?;
+        Ok(Sri {
+            hashbitlen,
+            digest,
+        })
+    }
+
+    /// Wrap an already-computed digest.
+    ///
+    /// Returns:
+    ///
+    ///   * `Err(BlakeError::BadHashbitlen)` if `hashbitlen` isn't a valid BLAKE length,
+    ///   * `Err(BlakeError::BadEncoding)` if `digest` isn't `hashbitlen / 8` bytes long, or
+    ///   * `Ok(Sri)` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::sri::Sri;
+    /// # #[cfg(all(feature = "width-256", feature = "width-512"))] {
+    /// let mut digest = [0; 32];
+    /// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut digest).unwrap();
+    ///
+    /// assert_eq!(Sri::from_digest(256, &digest), Sri::new(256, b"The lazy fox jumps over the lazy dog."));
+    /// assert_eq!(Sri::from_digest(512, &digest), Err(blake::BlakeError::BadEncoding));
+    /// # }
+    /// ```
+    pub fn from_digest(hashbitlen: i32, digest: &[u8]) -> Result<Sri> {
+        if digest.len() != digest_size(hashbitlen)? {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        Ok(Sri {
+            hashbitlen,
+            digest: digest.to_vec(),
+        })
+    }
+
+    /// The length of the hash function used, in bits.
+    pub fn hashbitlen(&self) -> i32 {
+        self.hashbitlen
+    }
+
+    /// The raw digest.
+    pub fn digest(&self) -> &[u8] {
+        &self.digest[..]
+    }
+
+    /// Check whether the specified data hashes to the wrapped digest.
+    ///
+    /// The comparison is done in constant time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::sri::Sri;
+    /// # #[cfg(feature = "width-256")] {
+    /// let sri: Sri = "blake256-8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=".parse().unwrap();
+    /// assert!(sri.verify(b"The lazy fox jumps over the lazy dog."));
+    /// assert!(!sri.verify(b"The lazy fox jumps over the lazy dog"));
+    /// # }
+    /// ```
+    pub fn verify(&self, data: &[u8]) -> bool {
+        let mut digest = vec![0; self.digest.len()];
+        hash(self.hashbitlen, data, &mut digest).is_ok() && constant_time_eq(&digest, &self.digest)
+    }
+}
+
+/// Parse a single hash expression, ignoring any trailing `?`-options.
+///
+/// Returns:
+///
+///   * `Err(BlakeError::BadHashbitlen)` if the algorithm isn't a BLAKE one, or
+///   * `Err(BlakeError::BadEncoding)` if the digest isn't correctly-encoded or has the wrong length.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::sri::Sri;
+/// # use blake::BlakeError;
+/// # #[cfg(all(feature = "width-256", feature = "width-512"))] {
+/// assert_eq!("blake256-8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=?ct=text/plain".parse::<Sri>().map(|s| s.hashbitlen()),
+///            Ok(256));
+/// assert_eq!("sha256-8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=".parse::<Sri>(), Err(BlakeError::BadHashbitlen));
+/// assert_eq!("blake512-8uWp0JPYqiNObFRQYegXvoOLV9iZjxXfcuEDf7/rT8c=".parse::<Sri>(), Err(BlakeError::BadEncoding));
+/// assert_eq!("blake256-8uWp0JPYqiNObFRQYegX".parse::<Sri>(), Err(BlakeError::BadEncoding));
+/// # }
+/// ```
+impl FromStr for Sri {
+    type Err = BlakeError;
+
+    fn from_str(s: &str) -> Result<Sri> {
+        let s = s.split('?').next().unwrap_or("");
+        let dash = s.find('-').ok_or(BlakeError::BadEncoding)?;
+
+        let hashbitlen = match &s[..dash] {
+            "blake224" => 224,
+            "blake256" => 256,
+            "blake384" => 384,
+            "blake512" => 512,
+            _ => return Err(BlakeError::BadHashbitlen),
+        };
+
+        Sri::from_digest(hashbitlen, &base64_decode(&s[dash + 1..]).ok_or(BlakeError::BadEncoding)?)
+    }
+}
+
+impl fmt::Display for Sri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "blake{}-{}", self.hashbitlen, base64_encode(&self.digest))
+    }
+}
+
+
+/// Verify the data against a full integrity metadata string, consisting of whitespace-separated hash expressions.
+///
+/// As in the SRI specification, expressions that don't parse, like those with unknown algorithms, no `-`,
+/// or malformed digests, are ignored, only the strongest of the specified hash functions is considered,
+/// and the data matches if it matches any of the strongest expressions.
+///
+/// Returns:
+///
+///   * `Err(BlakeError::BadEncoding)` if there are no well-formed BLAKE expressions at all, or
+///   * `Ok(matched)` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::BlakeError;
+/// # #[cfg(all(feature = "width-224", feature = "width-384"))] {
+/// let data = b"The lazy fox jumps over the lazy dog.";
+/// let integrity = format!("sha384-whatever {} {}",
+///                         blake::sri::Sri::new(224, b"other data").unwrap(),
+///                         blake::sri::Sri::new(384, data).unwrap());
+///
+/// assert_eq!(blake::sri::verify(&integrity, data), Ok(true));
+/// assert_eq!(blake::sri::verify(&integrity, b"other data"), Ok(false));
+/// assert_eq!(blake::sri::verify(&format!("garbage {}", integrity), data), Ok(true));
+/// assert_eq!(blake::sri::verify("sha256-whatever", data), Err(BlakeError::BadEncoding));
+/// # }
+/// ```
+pub fn verify(integrity: &str, data: &[u8]) -> Result<bool> {
+    let candidates: Vec<Sri> = integrity.split_whitespace().filter_map(|expr| expr.parse().ok()).collect();
+
+    let strongest = candidates.iter().map(|c| c.hashbitlen).max().ok_or(BlakeError::BadEncoding)?;
+    Ok(candidates.iter().filter(|c| c.hashbitlen == strongest).any(|c| c.verify(data)))
+}
+
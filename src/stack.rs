@@ -0,0 +1,168 @@
+use super::{BlakeError, Result, digest_size, native};
+use std::mem::MaybeUninit;
+use std::{io, ptr};
+
+
+/// A hash state stored inline, instead of on the heap like `Blake`'s, for targets without an allocator.
+///
+/// It can live on the stack, in a `static`, or anywhere else; `StackState::new_in()` initialises one in place,
+/// without it ever being moved. The one-shot hashing functions use one internally.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::StackState;
+/// # use std::mem::MaybeUninit;
+/// # #[cfg(feature = "width-256")] {
+/// let mut result_inline = [0; 32];
+/// let mut result        = [0; 32];
+///
+/// let mut storage = MaybeUninit::uninit();
+/// let state = StackState::new_in(&mut storage, 256).unwrap();
+/// state.update(b"The lazy fox jumps over the lazy dog.");
+/// state.finalise(&mut result_inline).unwrap();
+///
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut result).unwrap();
+/// assert_eq!(result_inline, result);
+/// # }
+/// ```
+pub struct StackState {
+    raw_state: native::hashState,
+    hashbitlen: i32,
+}
+
+
+impl StackState {
+    /// Create a new hash state of the specified length, as in `Blake::new()`.
+    pub fn new(hashbitlen: i32) -> Result<StackState> {
+        digest_size(hashbitlen)?;
+        let mut raw_state = native::hashState::zeroed();
+
+        match unsafe { native::BLAKE_Hash_Init(raw_state.as_ffi(), hashbitlen) } {
+            0 => Ok(StackState { raw_state, hashbitlen }),
+            e => Err(BlakeError::from(e)),
+        }
+    }
+
+    /// Initialise a new hash state of the specified length in the provided storage, as in `Blake::new()`,
+    /// returning a reference to it.
+    ///
+    /// The state isn't dropped with the storage, so, with the `zeroize` feature, it's not zeroed either,
+    /// unless it's `reset()` or dropped with `MaybeUninit::assume_init_drop()`.
+    pub fn new_in(storage: &mut MaybeUninit<StackState>, hashbitlen: i32) -> Result<&mut StackState> {
+        digest_size(hashbitlen)?;
+        let state = storage.as_mut_ptr();
+        unsafe {
+            // All-zero is a valid hashState, and a valid StackState
+            ptr::write_bytes(state, 0, 1);
+            match native::BLAKE_Hash_Init((*state).raw_state.as_ffi(), hashbitlen) {
+                0 => {
+                    (*state).hashbitlen = hashbitlen;
+                    Ok(storage.assume_init_mut())
+                }
+                e => Err(BlakeError::from(e)),
+            }
+        }
+    }
+
+    /// Create a new state and hash the domain-separation tag into it, as in `Blake::new_domain()`.
+    pub fn new_domain(hashbitlen: i32, tag: &str) -> Result<StackState> {
+        let mut state = StackState::new(hashbitlen)?;
+        state.update((tag.len() as u64).to_le_bytes());
+        state.update(tag.as_bytes());
+        Ok(state)
+    }
+
+    /// Get the length of the hash function's output, in bits, as passed to `StackState::new()`.
+    pub fn hashbitlen(&self) -> i32 {
+        self.hashbitlen
+    }
+
+    /// Add a salt to the hash function, as in `Blake::add_salt()`.
+    ///
+    /// Returns `Err(BlakeError::Fail)` if the salt is shorter than the hash function's salt length,
+    /// or if called after `update()`.
+    pub fn add_salt(&mut self, salt: &[u8]) -> Result<()> {
+        let salt_size = if self.hashbitlen < 384 { 16 } else { 32 };
+        if salt.len() < salt_size {
+            return Err(BlakeError::Fail);
+        }
+
+        match unsafe { native::BLAKE_Hash_AddSalt(self.raw_state.as_ffi(), salt.as_ptr()) } {
+            0 => Ok(()),
+            e => Err(BlakeError::from(e)),
+        }
+    }
+
+    /// Append the provided data to the hash function, as in `Blake::update()`.
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) {
+        native::update(self.raw_state.as_ffi(), data.as_ref());
+    }
+
+    /// Finish hashing and store the output result in the provided space, as in `Blake::finalise()`.
+    ///
+    /// Returns `Err(BlakeError::BadLength)`, leaving the state untouched, if the provided space is smaller than the hash
+    /// function's size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::{BlakeError, StackState};
+    /// # #[cfg(feature = "width-512")] {
+    /// let mut state = StackState::new(512).unwrap();
+    /// assert_eq!(state.finalise(&mut [0; 63]), Err(BlakeError::BadLength));
+    /// assert_eq!(state.finalise(&mut [0; 64]), Ok(()));
+    /// # }
+    /// ```
+    pub fn finalise(&mut self, hashval: &mut [u8]) -> Result<()> {
+        if hashval.len() < self.hashbitlen as usize / 8 {
+            return Err(BlakeError::BadLength);
+        }
+
+        unsafe {
+            native::BLAKE_Hash_Final(self.raw_state.as_ffi(), hashval.as_mut_ptr());
+        }
+        Ok(())
+    }
+
+    /// Reset the state to the one freshly returned from `StackState::new()`, as in `Blake::reset()`.
+    pub fn reset(&mut self) {
+        if cfg!(feature = "zeroize") {
+            native::zero_hash_state(self.raw_state.as_ffi());
+        }
+        unsafe {
+            native::BLAKE_Hash_Init(self.raw_state.as_ffi(), self.hashbitlen);
+        }
+    }
+}
+
+impl Clone for StackState {
+    fn clone(&self) -> StackState {
+        // hashState is plain old data
+        StackState {
+            raw_state: unsafe { ptr::read(&self.raw_state) },
+            hashbitlen: self.hashbitlen,
+        }
+    }
+}
+
+/// The `Write` implementation updates the state with the provided data.
+impl io::Write for StackState {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// With the `zeroize` feature, the state is zeroed when dropped.
+impl Drop for StackState {
+    fn drop(&mut self) {
+        if cfg!(feature = "zeroize") {
+            native::zero_hash_state(self.raw_state.as_ffi());
+        }
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+use super::bench::BACKEND;
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+use std::time::{Duration, Instant};
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+use std::fmt::Display;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+use std::io;
+
+
+/// Run the hashing operation in a span, then emit an event with the amount of data hashed, if it succeeded,
+/// and the time it took, and record them in the metrics.
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub fn instrumented<T, E: Display, F: FnOnce() -> Result<T, E>>(operation: &'static str, hashbitlen: i32, bytes: u64, f: F) -> Result<T, E> {
+    #[cfg(feature = "tracing")]
+    let span = ::tracing::debug_span!("blake", operation, hashbitlen, backend = BACKEND);
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+
+    let start = Instant::now();
+    let ret = f();
+    let elapsed = start.elapsed();
+    #[cfg(feature = "tracing")]
+    match ret {
+        Ok(_) => ::tracing::debug!(bytes, elapsed_us = elapsed.as_micros() as u64, "hashed"),
+        Err(ref e) => ::tracing::debug!(error = %e, elapsed_us = elapsed.as_micros() as u64, "failed"),
+    }
+    record(operation, hashbitlen, ret.as_ref().ok().map(|_| bytes), elapsed);
+    ret
+}
+
+#[cfg(not(any(feature = "tracing", feature = "metrics")))]
+#[inline(always)]
+pub fn instrumented<T, E, F: FnOnce() -> Result<T, E>>(_: &'static str, _: i32, _: u64, f: F) -> Result<T, E> {
+    f()
+}
+
+/// Run the streaming hashing operation in a span, then emit an event with the amount of data it returned it hashed
+/// and the time it took, and record them in the metrics.
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub fn instrumented_stream<F: FnOnce() -> io::Result<u64>>(operation: &'static str, hashbitlen: i32, f: F) -> io::Result<u64> {
+    #[cfg(feature = "tracing")]
+    let span = ::tracing::debug_span!("blake", operation, hashbitlen, backend = BACKEND);
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+
+    let start = Instant::now();
+    let ret = f();
+    let elapsed = start.elapsed();
+    #[cfg(feature = "tracing")]
+    match ret {
+        Ok(bytes) => ::tracing::debug!(bytes, elapsed_us = elapsed.as_micros() as u64, "hashed"),
+        Err(ref e) => ::tracing::debug!(error = %e, elapsed_us = elapsed.as_micros() as u64, "failed"),
+    }
+    record(operation, hashbitlen, ret.as_ref().ok().cloned(), elapsed);
+    ret
+}
+
+#[cfg(not(any(feature = "tracing", feature = "metrics")))]
+#[inline(always)]
+pub fn instrumented_stream<F: FnOnce() -> io::Result<u64>>(_: &'static str, _: i32, f: F) -> io::Result<u64> {
+    f()
+}
+
+/// Run the asynchronous streaming hashing operation in a span, as in `instrumented_stream()`.
+#[cfg(all(feature = "tokio", any(feature = "tracing", feature = "metrics")))]
+pub async fn instrumented_stream_async<F: Future<Output = io::Result<u64>>>(operation: &'static str, hashbitlen: i32, f: F) -> io::Result<u64> {
+    #[cfg(feature = "tracing")]
+    let span = ::tracing::debug_span!("blake", operation, hashbitlen, backend = BACKEND);
+
+    let start = Instant::now();
+    #[cfg(feature = "tracing")]
+    let ret = ::tracing::Instrument::instrument(f, span.clone()).await;
+    #[cfg(not(feature = "tracing"))]
+    let ret = f.await;
+    let elapsed = start.elapsed();
+    #[cfg(feature = "tracing")]
+    span.in_scope(|| match ret {
+        Ok(bytes) => ::tracing::debug!(bytes, elapsed_us = elapsed.as_micros() as u64, "hashed"),
+        Err(ref e) => ::tracing::debug!(error = %e, elapsed_us = elapsed.as_micros() as u64, "failed"),
+    });
+    record(operation, hashbitlen, ret.as_ref().ok().cloned(), elapsed);
+    ret
+}
+
+#[cfg(all(feature = "tokio", not(any(feature = "tracing", feature = "metrics"))))]
+#[inline(always)]
+pub async fn instrumented_stream_async<F: Future<Output = io::Result<u64>>>(_: &'static str, _: i32, f: F) -> io::Result<u64> {
+    f.await
+}
+
+
+/// Count the bytes hashed and the operation completed, or failed if `None`, and record the time it took.
+#[cfg(feature = "metrics")]
+fn record(operation: &'static str, hashbitlen: i32, bytes: Option<u64>, elapsed: Duration) {
+    let labels = [("operation", operation), ("hashbitlen", hashbitlen_label(hashbitlen)), ("backend", BACKEND)];
+    match bytes {
+        Some(bytes) => {
+            ::metrics::counter!("blake_bytes_hashed_total", &labels).increment(bytes);
+            ::metrics::counter!("blake_hashes_total", &labels).increment(1);
+            ::metrics::histogram!("blake_hash_duration_seconds", &labels).record(elapsed.as_secs_f64());
+        }
+        None => ::metrics::counter!("blake_hash_errors_total", &labels).increment(1),
+    }
+}
+
+#[cfg(all(feature = "tracing", not(feature = "metrics")))]
+#[inline(always)]
+fn record(_: &'static str, _: i32, _: Option<u64>, _: Duration) {}
+
+#[cfg(feature = "metrics")]
+fn hashbitlen_label(hashbitlen: i32) -> &'static str {
+    match hashbitlen {
+        224 => "224",
+        256 => "256",
+        384 => "384",
+        512 => "512",
+        _ => "invalid",
+    }
+}
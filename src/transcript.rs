@@ -0,0 +1,116 @@
+//! [Merlin](https://merlin.cool)-style transcripts for Fiat–Shamir transforms.
+//!
+//! A `Transcript` absorbs labelled messages from both sides of a protocol, and derives challenges bound to
+//! everything absorbed up to that point: a prover and a verifier appending the same messages derive the same
+//! challenges, and any difference in the messages, their labels, or their order, yields unrelated challenges.
+//!
+//! BLAKE has no extendable-output mode, so challenges are expanded from a BLAKE-512 digest of the transcript in
+//! counter mode: block *i* of the challenge is BLAKE-512 of the digest followed by *i* as a little-endian `u64`.
+//! Each message and challenge is recorded as a kind byte, its length-prefixed label, and its length and bytes,
+//! so the encoding is unambiguous, and every challenge is itself appended to the transcript.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::transcript::Transcript;
+//! let mut prover = Transcript::new(b"example protocol");
+//! prover.append_message(b"commitment", b"The lazy fox jumps over the lazy dog.");
+//!
+//! let mut verifier = prover.clone();
+//!
+//! let mut challenge_prover   = [0; 100];
+//! let mut challenge_verifier = [0; 100];
+//! prover.challenge_bytes(b"challenge", &mut challenge_prover);
+//! verifier.challenge_bytes(b"challenge", &mut challenge_verifier);
+//! assert_eq!(&challenge_prover[..], &challenge_verifier[..]);
+//!
+//! // Diverging transcripts derive unrelated challenges
+//! prover.append_message(b"response", b"forward");
+//! verifier.append_message(b"response", b"backward");
+//! prover.challenge_bytes(b"challenge", &mut challenge_prover);
+//! verifier.challenge_bytes(b"challenge", &mut challenge_verifier);
+//! assert!(&challenge_prover[..] != &challenge_verifier[..]);
+//! ```
+
+
+use super::Blake;
+
+
+/// A transcript of a protocol, as described in the module-level documentation.
+///
+/// Clone it to fork the transcript.
+#[derive(Clone)]
+pub struct Transcript {
+    state: Blake,
+}
+
+const DOMAIN: &str = "blake-rs transcript v1";
+const BLOCK_SIZE: usize = 64;
+
+
+impl Transcript {
+    /// Start a transcript for the protocol with the specified label.
+    ///
+    /// The label separates transcripts of different protocols, so that challenges from one aren't valid in another.
+    #[must_use]
+    pub fn new(label: &'static [u8]) -> Transcript {
+        let mut transcript = Transcript { state: Blake::new_domain(512, DOMAIN).expect("512 is a valid hashbitlen") };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    /// Append a message with the specified label.
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.append(0x01, label, message.len() as u64);
+        self.state.update(message);
+    }
+
+    /// Append a `u64` with the specified label, as its little-endian bytes.
+    pub fn append_u64(&mut self, label: &'static [u8], value: u64) {
+        self.append_message(label, &value.to_le_bytes());
+    }
+
+    /// Fill the buffer with a challenge bound to the transcript so far, then append it to the transcript.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::transcript::Transcript;
+    /// let mut transcript = Transcript::new(b"example protocol");
+    ///
+    /// let mut first  = [0; 32];
+    /// let mut second = [0; 32];
+    /// transcript.challenge_bytes(b"challenge", &mut first);
+    /// transcript.challenge_bytes(b"challenge", &mut second);
+    ///
+    /// // Each challenge is appended, so repeated challenges differ
+    /// assert!(first != second);
+    /// ```
+    pub fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.append(0x02, label, dest.len() as u64);
+
+        let mut seed = [0; BLOCK_SIZE];
+        self.state.clone().finalise(&mut seed);
+
+        let mut block = [0; BLOCK_SIZE];
+        for (i, chunk) in dest.chunks_mut(BLOCK_SIZE).enumerate() {
+            let mut expander = Blake::new(512).expect("512 is a valid hashbitlen");
+            expander.update(seed);
+            expander.update((i as u64).to_le_bytes());
+            expander.finalise(&mut block);
+
+            let len = chunk.len();
+            chunk.copy_from_slice(&block[..len]);
+        }
+
+        self.state.update(dest);
+    }
+
+
+    fn append(&mut self, kind: u8, label: &[u8], length: u64) {
+        self.state.update([kind]);
+        self.state.update((label.len() as u64).to_le_bytes());
+        self.state.update(label);
+        self.state.update(length.to_le_bytes());
+    }
+}
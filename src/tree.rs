@@ -0,0 +1,314 @@
+//! Tree digests of large files, whose chunks are hashed in parallel.
+//!
+//! The file is split into chunks of a fixed size (the last one shorter, if needed), which are hashed independently,
+//! then combined in a binary Merkle tree, where the left subtree of every node
+//! has the largest power of two of leaves smaller than the amount of leaves under it, as in RFC 6962.
+//! The chunk size and the file size are committed to in the root, and stored alongside it in a `TreeDigest`,
+//! so the digest can be recomputed and verified with any amount of threads.
+//!
+//! All nodes are hashed in the context of the domain-separation tag `"blake-rs tree"`, as in `hash_domain()`:
+//!
+//! |node  |digest                                                                                        |
+//! |------|----------------------------------------------------------------------------------------------|
+//! |leaf  |`0x00`, the chunk's index as a little-endian `u64`, the chunk                                 |
+//! |parent|`0x01`, the left child, the right child                                                        |
+//! |root  |`0x02`, the chunk size and the file size as little-endian `u64`s, the top node (if any chunks) |
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::tree::{self, TreeDigest};
+//! # use std::fs;
+//! # #[cfg(feature = "width-256")] {
+//! let digest = tree::hash_file_tree(256, "LICENSE", 256, 4).unwrap();
+//! assert_eq!(digest.size(), fs::metadata("LICENSE").unwrap().len());
+//!
+//! // The amount of threads doesn't change the result
+//! assert_eq!(tree::hash_file_tree(256, "LICENSE", 256, 1).unwrap(), digest);
+//! assert_eq!(tree::hash_tree(256, &fs::read("LICENSE").unwrap(), 256).unwrap(), digest);
+//!
+//! let stored = digest.to_string();
+//! assert!(stored.starts_with("blake256-tree-256-"));
+//! assert!(stored.parse::<TreeDigest>().unwrap().verify_file("LICENSE", 2).unwrap());
+//! assert!(!stored.parse::<TreeDigest>().unwrap().verify_file("README.md", 2).unwrap());
+//! # }
+//! ```
+
+
+use super::stack::StackState;
+use super::encoding::hex_decode;
+use super::{BlakeError, Result, digest_size, constant_time_eq};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::str::FromStr;
+use std::path::Path;
+use std::fs::File;
+use std::{cmp, fmt, thread};
+
+
+/// How much of a chunk is read at once.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+
+/// The root of a tree digest, along with the parameters needed to recompute it.
+///
+/// Its string form is `blake<hashbitlen>-tree-<chunk size>-<file size>-<hex digest>`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct TreeDigest {
+    hashbitlen: i32,
+    chunk_size: u64,
+    size: u64,
+    digest: Vec<u8>,
+}
+
+
+impl TreeDigest {
+    /// The length of the hash function used, in bits.
+    pub fn hashbitlen(&self) -> i32 {
+        self.hashbitlen
+    }
+
+    /// The size of the chunks the data was split into.
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+
+    /// The size of the data hashed.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The raw root digest.
+    pub fn digest(&self) -> &[u8] {
+        &self.digest[..]
+    }
+
+    /// Recompute the tree digest of the file at the specified path with the same parameters, on the specified amount
+    /// of threads, and check whether it matches.
+    ///
+    /// The comparison is done in constant time. A file of a different size doesn't match, and isn't read.
+    ///
+    /// Returns any error encountered opening or reading the file.
+    pub fn verify_file<P: AsRef<Path>>(&self, path: P, threads: usize) -> io::Result<bool> {
+        if File::open(path.as_ref())?.metadata()?.len() != self.size {
+            return Ok(false);
+        }
+
+        let actual = hash_file_tree(self.hashbitlen, path, self.chunk_size, threads)?;
+        Ok(actual.size == self.size && constant_time_eq(&actual.digest, &self.digest))
+    }
+}
+
+/// Parse a stored tree digest.
+///
+/// Returns:
+///
+///   * `Err(BlakeError::BadHashbitlen)` if the algorithm isn't a BLAKE one, or
+///   * `Err(BlakeError::BadEncoding)` if the string is otherwise malformed, the chunk size is `0`,
+///     or the digest has the wrong length.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::tree::TreeDigest;
+/// # use blake::BlakeError;
+/// # #[cfg(all(feature = "width-224", feature = "width-256"))] {
+/// let digest: TreeDigest = "blake224-tree-1024-0-00112233445566778899aabbccddeeff00112233445566778899aabb".parse().unwrap();
+/// assert_eq!((digest.hashbitlen(), digest.chunk_size(), digest.size()), (224, 1024, 0));
+///
+/// assert_eq!("sha256-tree-1024-0-00".parse::<TreeDigest>(), Err(BlakeError::BadHashbitlen));
+/// assert_eq!("blake224-tree-0-0-00112233445566778899aabbccddeeff00112233445566778899aabb".parse::<TreeDigest>(),
+///            Err(BlakeError::BadEncoding));
+/// assert_eq!("blake256-tree-1024-0-00112233445566778899aabbccddeeff00112233445566778899aabb".parse::<TreeDigest>(),
+///            Err(BlakeError::BadEncoding));
+/// # }
+/// ```
+impl FromStr for TreeDigest {
+    type Err = BlakeError;
+
+    fn from_str(s: &str) -> Result<TreeDigest> {
+        let fields: Vec<_> = s.split('-').collect();
+        if fields.len() != 5 || fields[1] != "tree" {
+            return Err(BlakeError::BadEncoding);
+        }
+
+        let hashbitlen = match fields[0] {
+            "blake224" => 224,
+            "blake256" => 256,
+            "blake384" => 384,
+            "blake512" => 512,
+            _ => return Err(BlakeError::BadHashbitlen),
+        };
+        let chunk_size = fields[2].parse().map_err(|_| BlakeError::BadEncoding)?;
+        let size = fields[3].parse().map_err(|_| BlakeError::BadEncoding)?;
+        let digest = hex_decode(fields[4]).ok_or(BlakeError::BadEncoding)?;
+
+        if chunk_size == 0 || digest.len() != digest_size(hashbitlen)? {
+            return Err(BlakeError::BadEncoding);
+        }
+        Ok(TreeDigest {
+            hashbitlen,
+            chunk_size,
+            size,
+            digest,
+        })
+    }
+}
+
+impl fmt::Display for TreeDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "blake{}-tree-{}-{}-", self.hashbitlen, self.chunk_size, self.size)?;
+        for byte in &self.digest {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Compute the tree digest of the file at the specified path, hashing its chunks on the specified amount of threads;
+/// `0` or `1` hash them on the calling thread.
+///
+/// Every thread opens the file separately, and hashes a contiguous run of chunks.
+/// The amount of threads doesn't change the result.
+///
+/// Returns `Err` with kind `io::ErrorKind::InvalidInput` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// or if `chunk_size` is `0`, one with kind `io::ErrorKind::UnexpectedEof` if the file was truncated while hashing,
+/// or any error encountered opening or reading the file.
+pub fn hash_file_tree<P: AsRef<Path>>(hashbitlen: i32, path: P, chunk_size: u64, threads: usize) -> io::Result<TreeDigest> {
+    let path = path.as_ref();
+    let size = File::open(path)?.metadata()?.len();
+    let chunks = check_parameters(hashbitlen, size, chunk_size)?;
+
+    let hash_run = |first: u64, count: u64| -> io::Result<Vec<Vec<u8>>> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(first * chunk_size))?;
+
+        let mut buf = vec![0; cmp::min(chunk_size, BUFFER_SIZE as u64) as usize];
+        (first..first + count)
+            .map(|index| {
+                let len = cmp::min(chunk_size, size - index * chunk_size);
+                let mut state = leaf_state(hashbitlen, index);
+                let mut left = len;
+                while left != 0 {
+                    let read = cmp::min(left, buf.len() as u64) as usize;
+                    file.read_exact(&mut buf[..read])?;
+                    state.update(&buf[..read]);
+                    left -= read as u64;
+                }
+                Ok(finalise(state))
+            })
+            .collect()
+    };
+
+    let leaves = if threads <= 1 || chunks <= 1 {
+        hash_run(0, chunks)?
+    } else {
+        let per_thread = chunks.div_ceil(threads as u64);
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..chunks)
+                .step_by(per_thread as usize)
+                .map(|first| scope.spawn(move || hash_run(first, cmp::min(per_thread, chunks - first))))
+                .collect();
+
+            let mut ret = Vec::with_capacity(chunks as usize);
+            for worker in workers {
+                ret.extend(worker.join().expect("hashing thread panicked")?);
+            }
+            Ok::<_, io::Error>(ret)
+        })?
+    };
+
+    Ok(root(hashbitlen, chunk_size, size, &leaves))
+}
+
+/// Compute the tree digest of the data, as `hash_file_tree()` would of a file containing it, on the calling thread.
+///
+/// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+/// and `Err(BlakeError::BadLength)` if `chunk_size` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::tree;
+/// # #[cfg(feature = "width-256")] {
+/// let digest = tree::hash_tree(256, b"The lazy fox jumps over the lazy dog.", 16).unwrap();
+/// assert_eq!((digest.chunk_size(), digest.size()), (16, 37));
+///
+/// // The chunk size is part of the digest
+/// assert!(tree::hash_tree(256, b"The lazy fox jumps over the lazy dog.", 32).unwrap().digest() != digest.digest());
+/// # }
+/// ```
+pub fn hash_tree(hashbitlen: i32, data: &[u8], chunk_size: u64) -> Result<TreeDigest> {
+    digest_size(hashbitlen)?;
+    if chunk_size == 0 {
+        return Err(BlakeError::BadLength);
+    }
+
+    let leaves: Vec<_> = data.chunks(cmp::min(chunk_size, usize::MAX as u64) as usize)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut state = leaf_state(hashbitlen, index as u64);
+            state.update(chunk);
+            finalise(state)
+        })
+        .collect();
+    Ok(root(hashbitlen, chunk_size, data.len() as u64, &leaves))
+}
+
+
+/// Validate the parameters, returning the amount of chunks.
+fn check_parameters(hashbitlen: i32, size: u64, chunk_size: u64) -> io::Result<u64> {
+    digest_size(hashbitlen)?;
+    if chunk_size == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "chunk size must be positive"));
+    }
+    Ok(size.div_ceil(chunk_size))
+}
+
+fn node_state(hashbitlen: i32, kind: u8) -> StackState {
+    let mut state = StackState::new_domain(hashbitlen, "blake-rs tree").expect("valid hashbitlen");
+    state.update([kind]);
+    state
+}
+
+fn leaf_state(hashbitlen: i32, index: u64) -> StackState {
+    let mut state = node_state(hashbitlen, 0x00);
+    state.update(index.to_le_bytes());
+    state
+}
+
+fn finalise(mut state: StackState) -> Vec<u8> {
+    let mut digest = vec![0; state.hashbitlen() as usize / 8];
+    state.finalise(&mut digest).expect("the buffer fits the digest");
+    digest
+}
+
+/// Combine the nodes into the top node of their subtree, splitting off the largest power of two smaller than their
+/// amount to the left.
+fn top(hashbitlen: i32, nodes: &[Vec<u8>]) -> Vec<u8> {
+    if nodes.len() == 1 {
+        return nodes[0].clone();
+    }
+
+    let split = 1 << (usize::BITS - 1 - (nodes.len() - 1).leading_zeros());
+    let mut state = node_state(hashbitlen, 0x01);
+    state.update(top(hashbitlen, &nodes[..split]));
+    state.update(top(hashbitlen, &nodes[split..]));
+    finalise(state)
+}
+
+fn root(hashbitlen: i32, chunk_size: u64, size: u64, leaves: &[Vec<u8>]) -> TreeDigest {
+    let mut state = node_state(hashbitlen, 0x02);
+    state.update(chunk_size.to_le_bytes());
+    state.update(size.to_le_bytes());
+    if !leaves.is_empty() {
+        state.update(top(hashbitlen, leaves));
+    }
+
+    TreeDigest {
+        hashbitlen,
+        chunk_size,
+        size,
+        digest: finalise(state),
+    }
+}
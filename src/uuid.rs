@@ -0,0 +1,32 @@
+//! Name-based [UUIDs](https://docs.rs/uuid), derived with BLAKE-256.
+//!
+//! Like version 5 UUIDs, but with BLAKE-256 in place of SHA-1: the namespace UUID's bytes, followed by the name,
+//! are hashed, and the first 16 bytes of the digest become a version 8 (custom) UUID.
+//!
+//! # Examples
+//!
+//! ```
+//! # use uuid::{Uuid, Version};
+//! # fn main() {
+//! let id = blake::uuid::derive(&Uuid::NAMESPACE_DNS, b"example.com");
+//! assert_eq!(id.get_version(), Some(Version::Custom));
+//! assert_eq!(id, blake::uuid::derive(&Uuid::NAMESPACE_DNS, b"example.com"));
+//! assert_ne!(id, blake::uuid::derive(&Uuid::NAMESPACE_URL, b"example.com"));
+//! # }
+//! ```
+
+
+use ::uuid::{Builder, Uuid};
+use super::b256::Hasher;
+
+
+/// Derive the UUID for the specified name in the specified namespace.
+pub fn derive(namespace: &Uuid, name: &[u8]) -> Uuid {
+    let mut hasher = Hasher::new();
+    hasher.update(namespace.as_bytes());
+    hasher.update(name);
+
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&hasher.finalise().as_bytes()[..16]);
+    Builder::from_custom_bytes(bytes).into_uuid()
+}
@@ -0,0 +1,184 @@
+use super::{Blake, BlakeError, Result, digest_size, constant_time_eq};
+use std::io::{self, Read, Write};
+
+
+/// A reader adapter passing data through unchanged, which fails at the end of the stream
+/// if the data doesn't hash to the expected digest.
+///
+/// At the end of the stream, a read returns an `io::Error` of kind `InvalidData`, wrapping
+/// `BlakeError::DigestMismatch`, instead of `Ok(0)`, if the digest doesn't match,
+/// so consumers of the data can't forget to check it.
+///
+/// # Examples
+///
+/// ```
+/// # use blake::{BlakeError, VerifyingReader};
+/// # use std::io::{self, Read, Write};
+/// # #[cfg(feature = "width-256")] {
+/// let mut expected = [0; 32];
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut expected).unwrap();
+///
+/// let mut data = vec![];
+/// VerifyingReader::new(&b"The lazy fox jumps over the lazy dog."[..], 256, &expected).unwrap()
+///     .read_to_end(&mut data)
+///     .unwrap();
+/// assert_eq!(&data[..], &b"The lazy fox jumps over the lazy dog."[..]);
+///
+/// let err = VerifyingReader::new(&b"The lazy dog jumps over the lazy fox."[..], 256, &expected).unwrap()
+///     .read_to_end(&mut data)
+///     .unwrap_err();
+/// assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+/// assert_eq!(BlakeError::from_io_error(&err), Some(BlakeError::DigestMismatch));
+/// # }
+/// ```
+pub struct VerifyingReader<R> {
+    inner: R,
+    state: Blake,
+    expected: Vec<u8>,
+    matched: Option<bool>,
+}
+
+/// A writer adapter passing data through unchanged, whose `finish()` fails
+/// unless the data written hashes to the expected digest.
+///
+/// # Examples
+///
+/// Staging a file, to be renamed into place only if it's intact.
+///
+/// ```
+/// # use blake::{BlakeError, VerifyingWriter};
+/// # use std::io::Write;
+/// # #[cfg(feature = "width-256")] {
+/// let mut expected = [0; 32];
+/// blake::hash(256, b"The lazy fox jumps over the lazy dog.", &mut expected).unwrap();
+///
+/// let mut staging = VerifyingWriter::new(vec![], 256, &expected).unwrap();
+/// staging.write_all(b"The lazy fox ").unwrap();
+/// staging.write_all(b"jumps over the lazy dog.").unwrap();
+/// assert_eq!(&staging.finish().unwrap()[..], &b"The lazy fox jumps over the lazy dog."[..]);
+///
+/// let mut staging = VerifyingWriter::new(vec![], 256, &expected).unwrap();
+/// staging.write_all(b"The lazy fox ").unwrap();
+/// let err = staging.finish().unwrap_err();
+/// assert_eq!(BlakeError::from_io_error(&err), Some(BlakeError::DigestMismatch));
+/// # }
+/// ```
+pub struct VerifyingWriter<W> {
+    inner: W,
+    state: Blake,
+    expected: Vec<u8>,
+}
+
+
+impl<R: Read> VerifyingReader<R> {
+    /// Wrap the specified reader, expecting the data read from it to hash to the specified digest
+    /// with the hash function of the specified length.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    /// and `Err(BlakeError::BadLength)` if the expected digest isn't of the hash function's size.
+    pub fn new(inner: R, hashbitlen: i32, expected: &[u8]) -> Result<VerifyingReader<R>> {
+        if digest_size(hashbitlen)? != expected.len() {
+            return Err(BlakeError::BadLength);
+        }
+
+        Ok(VerifyingReader {
+            inner,
+            state: Blake::new(hashbitlen)?,
+            expected: expected.to_vec(),
+            matched: None,
+        })
+    }
+
+    /// Get a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwrap the reader, without verifying the remaining data.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for VerifyingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.matched {
+            Some(true) => return Ok(0),
+            Some(false) => return Err(mismatch()),
+            None => {}
+        }
+
+        let read = self.inner.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            let mut digest = vec![0; self.expected.len()];
+            self.state.finalise(&mut digest);
+
+            let matched = constant_time_eq(&digest, &self.expected);
+            self.matched = Some(matched);
+            if !matched {
+                return Err(mismatch());
+            }
+        } else {
+            self.state.update(&buf[..read]);
+        }
+
+        Ok(read)
+    }
+}
+
+
+impl<W: Write> VerifyingWriter<W> {
+    /// Wrap the specified writer, expecting the data written to it to hash to the specified digest
+    /// with the hash function of the specified length.
+    ///
+    /// Returns `Err(BlakeError::BadHashbitlen)` if `hashbitlen` is invalid, as in `Blake::new()`,
+    /// and `Err(BlakeError::BadLength)` if the expected digest isn't of the hash function's size.
+    pub fn new(inner: W, hashbitlen: i32, expected: &[u8]) -> Result<VerifyingWriter<W>> {
+        if digest_size(hashbitlen)? != expected.len() {
+            return Err(BlakeError::BadLength);
+        }
+
+        Ok(VerifyingWriter {
+            inner,
+            state: Blake::new(hashbitlen)?,
+            expected: expected.to_vec(),
+        })
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Flush the wrapped writer and verify the data written so far, returning the writer if it matches.
+    ///
+    /// Returns an `io::Error` of kind `InvalidData`, wrapping `BlakeError::DigestMismatch`, if it doesn't.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.flush()?;
+
+        let mut digest = vec![0; self.expected.len()];
+        self.state.finalise(&mut digest);
+        if constant_time_eq(&digest, &self.expected) {
+            Ok(self.inner)
+        } else {
+            Err(mismatch())
+        }
+    }
+}
+
+impl<W: Write> Write for VerifyingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.state.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+
+fn mismatch() -> io::Error {
+    BlakeError::DigestMismatch.into()
+}
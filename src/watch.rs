@@ -0,0 +1,265 @@
+//! Continuous integrity monitoring of directory trees.
+//!
+//! A `Watcher` hashes every file under a directory with BLAKE-256, then watches it for changes via
+//! [`notify`](https://docs.rs/notify), re-hashing files as they're modified, and reporting any whose digests changed,
+//! and, if a manifest of expected digests was specified, any that don't match it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use blake::watch::{Watcher, WatchEvent};
+//! # use std::time::Duration;
+//! # use std::fs;
+//! let root = std::env::temp_dir().join("blake-rs-doctest-watch");
+//! # let _ = fs::remove_dir_all(&root);
+//! fs::create_dir_all(&root).unwrap();
+//!
+//! let watcher = Watcher::new(&root).unwrap();
+//! fs::write(root.join("fox.txt"), b"The lazy fox jumps over the lazy dog.").unwrap();
+//!
+//! let expected = blake::b256::hash(b"The lazy fox jumps over the lazy dog.");
+//! loop {
+//!     match watcher.events().recv_timeout(Duration::from_secs(10)).unwrap() {
+//!         WatchEvent::Changed { ref path, new, .. } if new == expected => {
+//!             assert_eq!(path.file_name().unwrap(), "fox.txt");
+//!             break;
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! # fs::remove_dir_all(&root).unwrap();
+//! ```
+
+
+use notify::{self, EventKind, RecommendedWatcher, RecursiveMode, Watcher as NotifyWatcher};
+use notify::event::ModifyKind;
+use super::b256::{self, Digest};
+use super::hash_file;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+
+/// A change observed by a `Watcher`.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// A file appeared, including in a directory that was created or moved in, or its digest changed
+    Changed {
+        /// The path to the file
+        path: PathBuf,
+        /// The file's previous digest, or `None` if it's new
+        old: Option<Digest>,
+        /// The file's current digest
+        new: Digest,
+    },
+    /// A file's digest doesn't match the one in the manifest
+    Unexpected {
+        /// The path to the file
+        path: PathBuf,
+        /// The digest in the manifest
+        expected: Digest,
+        /// The file's current digest
+        actual: Digest,
+    },
+    /// A file was removed, including with a directory that was removed or moved out
+    Removed {
+        /// The path the file was at
+        path: PathBuf,
+        /// The file's last digest
+        old: Digest,
+    },
+    /// Watching or hashing failed
+    Error(io::Error),
+}
+
+/// A watcher of a directory tree, sending a `WatchEvent` for every change until dropped.
+pub struct Watcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<WatchEvent>,
+}
+
+/// The digests of the files under the watched root, and the ones expected of them.
+struct State {
+    known: HashMap<PathBuf, Digest>,
+    manifest: HashMap<PathBuf, Digest>,
+    events: Sender<WatchEvent>,
+}
+
+
+impl Watcher {
+    /// Hash all files under the specified directory and start watching it.
+    ///
+    /// Returns any error encountered hashing the files or setting up the watch.
+    pub fn new<P: AsRef<Path>>(root: P) -> io::Result<Watcher> {
+        Watcher::with_manifest(root, HashMap::new())
+    }
+
+    /// Hash all files under the specified directory and start watching it, also reporting files whose digests differ
+    /// from the ones in the manifest, keyed by paths relative to the root.
+    ///
+    /// Files that already don't match the manifest are reported immediately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use blake::watch::{Watcher, WatchEvent};
+    /// # use std::collections::HashMap;
+    /// # use std::path::PathBuf;
+    /// # use std::fs;
+    /// let root = std::env::temp_dir().join("blake-rs-doctest-watch-manifest");
+    /// # let _ = fs::remove_dir_all(&root);
+    /// fs::create_dir_all(&root).unwrap();
+    /// fs::write(root.join("fox.txt"), b"The lazy dog jumps over the lazy fox.").unwrap();
+    ///
+    /// let mut manifest = HashMap::new();
+    /// manifest.insert(PathBuf::from("fox.txt"), blake::b256::hash(b"The lazy fox jumps over the lazy dog."));
+    ///
+    /// let watcher = Watcher::with_manifest(&root, manifest).unwrap();
+    /// match watcher.events().try_recv().unwrap() {
+    ///     WatchEvent::Unexpected { actual, .. } => {
+    ///         assert_eq!(actual, blake::b256::hash(b"The lazy dog jumps over the lazy fox."))
+    ///     }
+    ///     event => panic!("{:?}", event),
+    /// }
+    /// # fs::remove_dir_all(&root).unwrap();
+    /// ```
+    pub fn with_manifest<P: AsRef<Path>>(root: P, manifest: HashMap<PathBuf, Digest>) -> io::Result<Watcher> {
+        let root = fs::canonicalize(root)?;
+        let (sender, receiver) = mpsc::channel();
+
+        let mut state = State {
+            known: HashMap::new(),
+            manifest: manifest.into_iter().map(|(path, digest)| (root.join(path), digest)).collect(),
+            events: sender,
+        };
+        let mut files = vec![];
+        find_files(&root, &mut files)?;
+        for file in files {
+            let digest = hash(&file)?;
+            state.check_manifest(&file, digest);
+            state.known.insert(file, digest);
+        }
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| state.handle(event))
+            .map_err(notify_error)?;
+        watcher.watch(&root, RecursiveMode::Recursive).map_err(notify_error)?;
+
+        Ok(Watcher {
+            _watcher: watcher,
+            events: receiver,
+        })
+    }
+
+    /// Get the channel the events are sent to.
+    pub fn events(&self) -> &Receiver<WatchEvent> {
+        &self.events
+    }
+}
+
+impl State {
+    fn handle(&mut self, event: notify::Result<notify::Event>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                let _ = self.events.send(WatchEvent::Error(notify_error(e)));
+                return;
+            }
+        };
+        if let EventKind::Access(_) = event.kind {
+            return;
+        }
+
+        // Files in a directory created or moved in don't get events of their own
+        let appeared = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)));
+        for path in event.paths {
+            if path.is_file() {
+                self.rehash(path);
+            } else if path.is_dir() {
+                if appeared {
+                    let mut files = vec![];
+                    match find_files(&path, &mut files) {
+                        Ok(()) => files.into_iter().for_each(|file| self.rehash(file)),
+                        Err(e) => {
+                            let _ = self.events.send(WatchEvent::Error(e));
+                        }
+                    }
+                }
+            } else if !path.exists() {
+                self.removed(&path);
+            }
+        }
+    }
+
+    fn rehash(&mut self, path: PathBuf) {
+        match hash(&path) {
+            Ok(digest) => self.rehashed(path, digest),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => self.removed(&path),
+            Err(e) => {
+                let _ = self.events.send(WatchEvent::Error(e));
+            }
+        }
+    }
+
+    fn rehashed(&mut self, path: PathBuf, digest: Digest) {
+        let old = self.known.insert(path.clone(), digest);
+        if old != Some(digest) {
+            self.check_manifest(&path, digest);
+            let _ = self.events.send(WatchEvent::Changed {
+                path,
+                old,
+                new: digest,
+            });
+        }
+    }
+
+    /// Forget the file, or all files under the directory, at the path.
+    fn removed(&mut self, path: &Path) {
+        let mut gone: Vec<_> = self.known.keys().filter(|known| known.starts_with(path)).cloned().collect();
+        gone.sort();
+        for path in gone {
+            let old = self.known.remove(&path).expect("just found");
+            let _ = self.events.send(WatchEvent::Removed { path, old });
+        }
+    }
+
+    fn check_manifest(&self, path: &Path, actual: Digest) {
+        if let Some(&expected) = self.manifest.get(path) {
+            if expected != actual {
+                let _ = self.events.send(WatchEvent::Unexpected {
+                    path: path.to_path_buf(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+}
+
+
+fn find_files(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            find_files(&entry.path(), out)?;
+        } else if file_type.is_file() {
+            out.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+fn hash(path: &Path) -> io::Result<Digest> {
+    let mut digest = [0; b256::DIGEST_SIZE];
+    hash_file(256, path, &mut digest)?;
+    Ok(Digest::from_bytes(digest))
+}
+
+fn notify_error(err: notify::Error) -> io::Error {
+    match err.kind {
+        notify::ErrorKind::Io(e) => e,
+        kind => io::Error::other(format!("{:?}", kind)),
+    }
+}
@@ -0,0 +1,260 @@
+//! The contents of the per-width modules, which differ only in the lengths of the digest and salt.
+
+
+/// Generate `hash()`, `Hasher`, and `Digest` for the hash function of the specified length in bits,
+/// with digests and salts of the specified lengths in bytes.
+#[cfg_attr(not(any(feature = "width-224", feature = "width-256", feature = "width-384", feature = "width-512")), allow(unused_macros))]
+macro_rules! width_module {
+    ($hashbitlen:expr, $digest_size:expr, $salt_size:expr, $name:expr) => {
+        use super::Blake;
+        use std::fmt;
+        use std::io;
+
+
+        /// The length of the digest in bytes.
+        pub const DIGEST_SIZE: usize = $digest_size;
+
+        /// The length of the salt in bytes.
+        pub const SALT_SIZE: usize = $salt_size;
+
+
+        #[doc = concat!("Hash all data in one fell swoop with ", $name, ", as with `blake::hash()`.")]
+        pub fn hash<D: AsRef<[u8]>>(data: D) -> Digest {
+            let mut ret = [0; DIGEST_SIZE];
+            super::hash($hashbitlen, data, &mut ret).expect("valid hashbitlen");
+            Digest(ret)
+        }
+
+        /// Hash the string's UTF-8 bytes, as with `hash()`.
+        ///
+        /// Unlike the generic `hash()`, this can be passed as a function, like `names.iter().map(hash_str)`.
+        pub fn hash_str(data: &str) -> Digest {
+            hash(data.as_bytes())
+        }
+
+
+        #[doc = concat!("A ", $name, " hash state, for hashing multiple data segments.")]
+        #[derive(Clone)]
+        pub struct Hasher {
+            state: Blake,
+        }
+
+        #[doc = concat!("A ", $name, " digest.")]
+        ///
+        /// With the `sqlx` or `diesel` features, it's stored as a binary (`BYTEA`, `BLOB`) column.
+        ///
+        /// Digests are ordered lexicographically by their bytes.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "diesel", derive(::diesel::expression::AsExpression, ::diesel::deserialize::FromSqlRow))]
+        #[cfg_attr(feature = "diesel", diesel(sql_type = ::diesel::sql_types::Binary))]
+        #[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
+        #[cfg_attr(feature = "rkyv", rkyv(compare(PartialEq), derive(Clone, Copy, PartialEq, Eq, Hash, Debug)))]
+        pub struct Digest([u8; DIGEST_SIZE]);
+
+
+        impl Hasher {
+            /// Create a new unsalted hash state.
+            #[must_use]
+            pub fn new() -> Hasher {
+                Hasher { state: Blake::new($hashbitlen).expect("valid hashbitlen") }
+            }
+
+            /// Create a new hash state with the specified salt, as with `Blake::add_salt()`.
+            #[must_use]
+            pub fn with_salt(salt: &[u8; SALT_SIZE]) -> Hasher {
+                let mut ret = Hasher::new();
+                ret.state.add_salt(salt).expect("salt added before update");
+                ret
+            }
+
+            /// Append the provided data to the hash function, as with `Blake::update()`.
+            pub fn update<D: AsRef<[u8]>>(&mut self, data: D) {
+                self.state.update(data);
+            }
+
+            /// Append the provided data to the hash function and return the state, as with `Blake::chain_update()`.
+            pub fn chain_update<D: AsRef<[u8]>>(mut self, data: D) -> Hasher {
+                self.update(data);
+                self
+            }
+
+            /// Shorthand for `chain_update()`.
+            pub fn chain<D: AsRef<[u8]>>(self, data: D) -> Hasher {
+                self.chain_update(data)
+            }
+
+            /// Append all data remaining in the buffer to the hash function, as with `Blake::update_buf()`.
+            #[cfg(feature = "bytes")]
+            pub fn update_buf<B: ::bytes::Buf>(&mut self, buf: &mut B) {
+                self.state.update_buf(buf);
+            }
+
+            /// Finish hashing and return the digest.
+            pub fn finalise(mut self) -> Digest {
+                let mut ret = [0; DIGEST_SIZE];
+                self.state.finalise(&mut ret);
+                Digest(ret)
+            }
+
+            /// Reset the state to the one freshly returned from `Hasher::new()`, as with `Blake::reset()`.
+            pub fn reset(&mut self) {
+                self.state.reset();
+            }
+        }
+
+        impl Default for Hasher {
+            fn default() -> Hasher {
+                Hasher::new()
+            }
+        }
+
+        /// The `Write` implementation updates the state with the provided data.
+        impl io::Write for Hasher {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[cfg(feature = "embedded-io")]
+        impl ::embedded_io::ErrorType for Hasher {
+            type Error = ::std::convert::Infallible;
+        }
+
+        /// The `embedded_io::Write` implementation updates the state with the provided data, as with `Blake`.
+        #[cfg(feature = "embedded-io")]
+        impl ::embedded_io::Write for Hasher {
+            fn write(&mut self, buf: &[u8]) -> Result<usize, ::std::convert::Infallible> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> Result<(), ::std::convert::Infallible> {
+                Ok(())
+            }
+        }
+
+
+        impl Digest {
+            /// Wrap a digest received as raw bytes.
+            #[must_use]
+            pub fn from_bytes(bytes: [u8; DIGEST_SIZE]) -> Digest {
+                Digest(bytes)
+            }
+
+            /// Get the raw bytes of the digest.
+            pub fn as_bytes(&self) -> &[u8; DIGEST_SIZE] {
+                &self.0
+            }
+
+            /// Check, in constant time, whether the digest is of the specified data.
+            pub fn verify<D: AsRef<[u8]>>(&self, data: D) -> bool {
+                super::constant_time_eq(&hash(data).0, &self.0)
+            }
+        }
+
+        impl AsRef<[u8]> for Digest {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        /// The conversion fails with `BlakeError::BadLength` if the slice isn't `DIGEST_SIZE` bytes long.
+        impl TryFrom<&[u8]> for Digest {
+            type Error = super::BlakeError;
+
+            fn try_from(bytes: &[u8]) -> Result<Digest, super::BlakeError> {
+                <[u8; DIGEST_SIZE]>::try_from(bytes).map(Digest).map_err(|_| super::BlakeError::BadLength)
+            }
+        }
+
+        /// Formats the digest as lower-case hexadecimal.
+        impl fmt::Display for Digest {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for b in &self.0[..] {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl fmt::Debug for Digest {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "Digest({})", self)
+            }
+        }
+
+        /// Formats the digest as its bytes, in hexadecimal.
+        #[cfg(feature = "defmt")]
+        impl ::defmt::Format for Digest {
+            fn format(&self, f: ::defmt::Formatter<'_>) {
+                ::defmt::write!(f, "Digest({=[u8]:02x})", self.0)
+            }
+        }
+
+        /// Zeroising the digest sets all its bytes to `0`.
+        #[cfg(feature = "zeroize")]
+        impl ::zeroize::Zeroize for Digest {
+            fn zeroize(&mut self) {
+                ::zeroize::Zeroize::zeroize(&mut self.0);
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<DB: ::sqlx::Database> ::sqlx::Type<DB> for Digest
+            where Vec<u8>: ::sqlx::Type<DB>
+        {
+            fn type_info() -> DB::TypeInfo {
+                <Vec<u8> as ::sqlx::Type<DB>>::type_info()
+            }
+
+            fn compatible(ty: &DB::TypeInfo) -> bool {
+                <Vec<u8> as ::sqlx::Type<DB>>::compatible(ty)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'q, DB: ::sqlx::Database> ::sqlx::Encode<'q, DB> for Digest
+            where Vec<u8>: ::sqlx::Encode<'q, DB>
+        {
+            fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer<'q>) -> Result<::sqlx::encode::IsNull, ::sqlx::error::BoxDynError> {
+                self.0.to_vec().encode(buf)
+            }
+        }
+
+        /// Decoding fails with `BlakeError::BadLength` if the value isn't `DIGEST_SIZE` bytes long, as in `TryFrom<&[u8]>`.
+        #[cfg(feature = "sqlx")]
+        impl<'r, DB: ::sqlx::Database> ::sqlx::Decode<'r, DB> for Digest
+            where Vec<u8>: ::sqlx::Decode<'r, DB>
+        {
+            fn decode(value: DB::ValueRef<'r>) -> Result<Digest, ::sqlx::error::BoxDynError> {
+                let bytes = <Vec<u8> as ::sqlx::Decode<'r, DB>>::decode(value)?;
+                Ok(Digest::try_from(&bytes[..])?)
+            }
+        }
+
+        #[cfg(feature = "diesel")]
+        impl<DB: ::diesel::backend::Backend> ::diesel::serialize::ToSql<::diesel::sql_types::Binary, DB> for Digest
+            where [u8]: ::diesel::serialize::ToSql<::diesel::sql_types::Binary, DB>
+        {
+            fn to_sql<'b>(&'b self, out: &mut ::diesel::serialize::Output<'b, '_, DB>) -> ::diesel::serialize::Result {
+                <[u8] as ::diesel::serialize::ToSql<::diesel::sql_types::Binary, DB>>::to_sql(&self.0[..], out)
+            }
+        }
+
+        /// Deserialisation fails with `BlakeError::BadLength` if the value isn't `DIGEST_SIZE` bytes long, as in `TryFrom<&[u8]>`.
+        #[cfg(feature = "diesel")]
+        impl<DB: ::diesel::backend::Backend> ::diesel::deserialize::FromSql<::diesel::sql_types::Binary, DB> for Digest
+            where Vec<u8>: ::diesel::deserialize::FromSql<::diesel::sql_types::Binary, DB>
+        {
+            fn from_sql(bytes: DB::RawValue<'_>) -> ::diesel::deserialize::Result<Digest> {
+                let bytes = <Vec<u8> as ::diesel::deserialize::FromSql<::diesel::sql_types::Binary, DB>>::from_sql(bytes)?;
+                Ok(Digest::try_from(&bytes[..])?)
+            }
+        }
+    };
+}
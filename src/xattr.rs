@@ -0,0 +1,157 @@
+//! Storing files' digests in their extended attributes, to detect silent corruption later, as with `cshatag`.
+//!
+//! `update()` hashes a file and stores its BLAKE-256 digest, as lower-case hexadecimal, in the `user.blake256`
+//! attribute, and its modification time at that point, as `seconds.nanoseconds`, in `user.blake256.ts`.
+//! When checked again, a file whose contents changed but whose modification time didn't is reported as corrupt.
+//!
+//! Only available on Linux and Android, and only on filesystems supporting user extended attributes.
+//!
+//! # Examples
+//!
+//! ```
+//! # use blake::xattr::{self, Status};
+//! # use std::{fs, io};
+//! let path = std::env::temp_dir().join("blake-rs-doctest-xattr");
+//! # let _ = fs::remove_file(&path);
+//! fs::write(&path, b"The lazy fox jumps over the lazy dog.").unwrap();
+//!
+//! match xattr::update(&path) {
+//!     Ok(status) => {
+//!         assert_eq!(status, Status::New);
+//!         assert_eq!(xattr::verify(&path).unwrap(), Status::Ok);
+//!     }
+//!     Err(e) => assert_eq!(e.kind(), io::ErrorKind::Unsupported),  // No user xattrs on this filesystem
+//! }
+//! # fs::remove_file(&path).unwrap();
+//! ```
+
+
+use super::b256::{self, Digest};
+use super::hash_file;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr::null_mut;
+use std::fs;
+use std::io;
+
+
+/// The attribute the digest is stored in.
+pub const DIGEST_ATTRIBUTE: &str = "user.blake256";
+
+/// The attribute the modification time at the time of hashing is stored in.
+pub const TIMESTAMP_ATTRIBUTE: &str = "user.blake256.ts";
+
+
+/// The state of a file, compared to the digest stored in its attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// No digest was stored
+    New,
+    /// The contents match the stored digest
+    Ok,
+    /// The contents don't match the stored digest, but the file was modified since it was stored
+    Outdated,
+    /// The contents don't match the stored digest, even though the file wasn't modified since it was stored
+    Corrupt,
+}
+
+
+/// Compare the file's contents to the digest stored in its attributes, without changing them.
+///
+/// Returns any error encountered reading the file or its attributes.
+pub fn verify<P: AsRef<Path>>(path: P) -> io::Result<Status> {
+    Ok(check(path.as_ref())?.0)
+}
+
+/// Compare the file's contents to the digest stored in its attributes, as in `verify()`, then, if they're `New` or
+/// `Outdated`, store the current digest and modification time.
+///
+/// `Corrupt` files are left as-is, so they're reported again until dealt with.
+///
+/// Returns any error encountered reading the file, or reading or writing its attributes.
+pub fn update<P: AsRef<Path>>(path: P) -> io::Result<Status> {
+    let path = path.as_ref();
+    let (status, digest, timestamp) = check(path)?;
+    if status == Status::New || status == Status::Outdated {
+        set_attribute(path, DIGEST_ATTRIBUTE, digest.to_string().as_bytes())?;
+        set_attribute(path, TIMESTAMP_ATTRIBUTE, timestamp.as_bytes())?;
+    }
+    Ok(status)
+}
+
+/// Get the digest and modification time stored in the file's attributes, if any.
+pub fn stored<P: AsRef<Path>>(path: P) -> io::Result<Option<(String, String)>> {
+    let path = path.as_ref();
+    match (get_attribute(path, DIGEST_ATTRIBUTE)?, get_attribute(path, TIMESTAMP_ATTRIBUTE)?) {
+        (Some(digest), Some(timestamp)) => {
+            Ok(Some((String::from_utf8_lossy(&digest).into_owned(), String::from_utf8_lossy(&timestamp).into_owned())))
+        }
+        _ => Ok(None),
+    }
+}
+
+
+/// Hash the file and compare it to the stored attributes, returning the status, the digest,
+/// and the modification time from before it was hashed.
+fn check(path: &Path) -> io::Result<(Status, Digest, String)> {
+    let metadata = fs::metadata(path)?;
+    let timestamp = format!("{:010}.{:09}", metadata.mtime(), metadata.mtime_nsec());
+
+    let mut digest = [0; b256::DIGEST_SIZE];
+    hash_file(256, path, &mut digest)?;
+    let digest = Digest::from_bytes(digest);
+
+    let status = match stored(path)? {
+        None => Status::New,
+        Some((ref stored_digest, _)) if *stored_digest == digest.to_string() => Status::Ok,
+        Some((_, ref stored_timestamp)) if *stored_timestamp == timestamp => Status::Corrupt,
+        Some(_) => Status::Outdated,
+    };
+    Ok((status, digest, timestamp))
+}
+
+fn get_attribute(path: &Path, name: &str) -> io::Result<Option<Vec<u8>>> {
+    let (path, name) = (c_path(path)?, CString::new(name).expect("attribute names have no NULs"));
+    loop {
+        let size = unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), null_mut(), 0) };
+        if size < 0 {
+            return not_found_to_none(io::Error::last_os_error());
+        }
+
+        let mut value = vec![0u8; size as usize];
+        let size = unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), value.as_mut_ptr() as *mut libc::c_void, value.len()) };
+        if size < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ERANGE) {
+                // Grew in the meantime
+                continue;
+            }
+            return not_found_to_none(err);
+        }
+
+        value.truncate(size as usize);
+        return Ok(Some(value));
+    }
+}
+
+fn set_attribute(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    let (path, name) = (c_path(path)?, CString::new(name).expect("attribute names have no NULs"));
+    if unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr() as *const libc::c_void, value.len(), 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn not_found_to_none(err: io::Error) -> io::Result<Option<Vec<u8>>> {
+    if err.raw_os_error() == Some(libc::ENODATA) {
+        Ok(None)
+    } else {
+        Err(err)
+    }
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}